@@ -12,6 +12,11 @@ fi
 # Set optimization flags
 export RUSTFLAGS="-C embed-bitcode=yes -C codegen-units=1 -C opt-level=3 --cfg web_sys_unstable_apis"
 
-# Run wasm pack tool to build JS wrapper files and copy wasm to pkg directory.
-mkdir -p pkg
-wasm-pack build --out-dir pkg --release --target web --no-typescript --no-default-features --features $1
+# The weights are not embedded into the wasm module, but received from JavaScript with
+# `FaceNet.with_weights`.
+features="$1"
+
+# Run wasm pack tool to build JS wrapper files and copy wasm to pkg directory, or to `$PKG_DIR`.
+pkg_dir="${PKG_DIR:-pkg}"
+mkdir -p "$pkg_dir"
+wasm-pack build --out-dir "$pkg_dir" --release --target web --no-typescript --no-default-features --features $features
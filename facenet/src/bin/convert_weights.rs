@@ -0,0 +1,70 @@
+//! Converts a PyTorch MobileFaceNet checkpoint into the Burn record read by the `facenet` and
+//! `verify-bench` binaries, or streamed to the wasm `FaceNet`.
+//!
+//! Usage: `convert_weights [--precision full|half|int8] <checkpoint.pth> [output.bin] [config.json]`
+//!
+//! The weights are saved in full precision by default. Half precision halves the record, and int8
+//! quantization divides it by about four, at the cost of the accuracy reported by yolo's
+//! `precision-report`.
+//!
+//! The SHA-256 checksum of the record is printed, to verify it when streamed to the wasm module.
+
+use std::path::PathBuf;
+
+use burn::{backend::NdArray, config::Config, module::Module};
+use facenet_burn::mobilefacenet::{MobileFaceNet, MobileFaceNetConfig};
+use inference_runtime::{record_bytes, sha256_hex, Precision};
+
+pub fn main() {
+    // Parse arguments
+    let mut precision = Precision::Full;
+    let mut positional = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--precision" => {
+                precision = args.next().expect("No precision provided").parse().unwrap()
+            }
+            _ => positional.push(arg),
+        }
+    }
+    let mut args = positional.into_iter();
+    let checkpoint = PathBuf::from(args.next().expect("No PyTorch checkpoint path provided"));
+    let output = PathBuf::from(args.next().unwrap_or_else(|| "mobilefacenet.bin".into()));
+    let config = match args.next() {
+        Some(path) => MobileFaceNetConfig::load(&path)
+            .map_err(|err| format!("Failed to load config {path}.\nError: {err}"))
+            .unwrap(),
+        None => MobileFaceNetConfig::new(),
+    };
+    config
+        .validate()
+        .map_err(|err| format!("Invalid config.\nError: {err}"))
+        .unwrap();
+
+    // Load and remap the PyTorch weights
+    let device = Default::default();
+    let record = MobileFaceNet::<NdArray>::load_weights_record(&config, checkpoint, &device)
+        .map_err(|err| format!("Failed to load PyTorch weights.\nError: {err}"))
+        .unwrap();
+
+    // Encode in a record which `state.rs` decodes whatever its precision
+    let model = config.init::<NdArray>(&device).load_record(record);
+    let bytes = record_bytes(model, precision)
+        .map_err(|err| format!("Failed to encode record.\nError: {err}"))
+        .unwrap();
+
+    std::fs::write(&output, &bytes)
+        .map_err(|err| format!("Failed to write {}.\nError: {err}", output.display()))
+        .unwrap();
+    config
+        .save(output.with_extension("json"))
+        .map_err(|err| format!("Failed to write config.\nError: {err}"))
+        .unwrap();
+
+    println!(
+        "Saved {} in {precision} precision and its config",
+        output.display()
+    );
+    println!("SHA-256: {}", sha256_hex(&bytes));
+}
@@ -0,0 +1,80 @@
+//! Computes MobileFaceNet embeddings of aligned face crops.
+//!
+//! Usage: `facenet [--weights mobilefacenet.bin] [--config config.json] [--similarity] <images>...`
+//!
+//! Prints one embedding per image, or the cosine similarity of every image pair with
+//! `--similarity`. The weights are a record written by `convert_weights` from a trained checkpoint,
+//! and the config defaults to the JSON file written next to them, if any.
+
+use std::path::PathBuf;
+
+use burn::{backend::NdArray, tensor::Tensor};
+use facenet_burn::{
+    embedding::{cosine_similarity, image_to_tensor, l2_normalize},
+    state::load_model_file,
+};
+
+type Backend = NdArray<f32>;
+
+pub fn main() {
+    // Parse arguments
+    let mut weights = PathBuf::from("mobilefacenet.bin");
+    let mut config_path = None;
+    let mut similarity = false;
+    let mut images = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--weights" => weights = args.next().expect("No weights path provided").into(),
+            "--config" => {
+                config_path = Some(PathBuf::from(args.next().expect("No config path provided")))
+            }
+            "--similarity" => similarity = true,
+            _ => images.push(arg),
+        }
+    }
+    assert!(!images.is_empty(), "No image path provided");
+
+    // Create MobileFaceNet
+    let device = Default::default();
+    let (model, config) =
+        load_model_file::<Backend>(&weights, config_path.as_deref(), &device).unwrap();
+
+    // Load images into a single batch
+    let batch = images
+        .iter()
+        .map(|path| {
+            let img = image::open(path)
+                .map_err(|err| format!("Failed to load image {path}.\nError: {err}"))
+                .unwrap();
+            image_to_tensor::<Backend>(&img, config.input_size, &device)
+        })
+        .collect();
+    let x = Tensor::stack(batch, 0);
+
+    // Forward pass
+    let embeddings = model.forward(x);
+
+    if similarity {
+        let [n, _] = embeddings.dims();
+        let scores = cosine_similarity(embeddings.clone(), embeddings)
+            .into_data()
+            .to_vec::<f32>()
+            .unwrap();
+        for i in 0..n {
+            for j in i + 1..n {
+                println!("{}\t{}\t{:.4}", images[i], images[j], scores[i * n + j]);
+            }
+        }
+    } else {
+        let [_, embedding_size] = embeddings.dims();
+        let values = l2_normalize(embeddings)
+            .into_data()
+            .to_vec::<f32>()
+            .unwrap();
+        for (path, embedding) in images.iter().zip(values.chunks(embedding_size)) {
+            let embedding: Vec<_> = embedding.iter().map(|v| format!("{v:.6}")).collect();
+            println!("{path}\t{}", embedding.join(","));
+        }
+    }
+}
@@ -0,0 +1,267 @@
+//! Face verification benchmark on LFW-style pair lists.
+//!
+//! Usage: `verify-bench [--weights mobilefacenet.bin] [--config config.json] [--ext jpg]
+//! [--batch-size 32] [--folds 10] [--roc roc.csv] <pairs.txt> <image_dir>`
+//!
+//! `pairs.txt` follows the LFW format: an optional `<folds> <pairs per fold>` or `<pairs>` header,
+//! followed by `<name> <n1> <n2>` lines for matching pairs and `<name1> <n1> <name2> <n2>` lines
+//! for non-matching pairs. Images are read from `<image_dir>/<name>/<name>_<nnnn>.<ext>` and are
+//! expected to be aligned face crops. The weights are a record written by `convert_weights` from a
+//! trained checkpoint.
+//!
+//! The k-fold accuracy, best threshold and TAR@FAR are printed to stdout as CSV, and the ROC
+//! points are written to the `--roc` file.
+
+use std::{
+    collections::HashMap,
+    fmt::Write as _,
+    path::{Path, PathBuf},
+};
+
+use burn::{backend::NdArray, tensor::Tensor};
+use facenet_burn::{
+    embedding::{image_to_tensor, l2_normalize},
+    metrics::{best_threshold, k_fold, mean_std, roc, tar_at_far},
+    state::load_model_file,
+};
+
+type Backend = NdArray<f32>;
+
+/// False accept rates at which the true accept rate is reported.
+const FARS: [f32; 3] = [1e-3, 1e-2, 1e-1];
+
+/// A pair of images, with whether both show the same identity.
+struct Pair {
+    first: PathBuf,
+    second: PathBuf,
+    is_same: bool,
+}
+
+/// Parses an LFW `pairs.txt` file, returning the number of folds from its header (if any) and
+/// the list of pairs.
+///
+/// The header is either `<folds> <pairs per fold>`, as in `pairs.txt`, or the single number of
+/// pairs of each kind of `pairsDevTrain.txt` and `pairsDevTest.txt`, which have no folds.
+fn parse_pairs(
+    content: &str,
+    image_dir: &Path,
+    ext: &str,
+) -> Result<(Option<usize>, Vec<Pair>), String> {
+    let number = |value: &str, line_number: usize| {
+        value
+            .parse::<usize>()
+            .map_err(|err| format!("Invalid number {value} on line {}: {err}", line_number + 1))
+    };
+    let image = |name: &str, index: &str, line_number: usize| {
+        let index = number(index, line_number)?;
+        Ok::<_, String>(
+            image_dir
+                .join(name)
+                .join(format!("{name}_{index:04}.{ext}")),
+        )
+    };
+
+    let mut folds = None;
+    let mut pairs = Vec::new();
+    for (line_number, line) in content.lines().enumerate() {
+        let fields: Vec<_> = line.split_whitespace().collect();
+        match fields[..] {
+            [] => {}
+            [count] if line_number == 0 => {
+                number(count, line_number)?;
+            }
+            [num_folds, pairs_per_fold] if line_number == 0 => {
+                folds = Some(number(num_folds, line_number)?);
+                number(pairs_per_fold, line_number)?;
+            }
+            [name, first, second] => pairs.push(Pair {
+                first: image(name, first, line_number)?,
+                second: image(name, second, line_number)?,
+                is_same: true,
+            }),
+            [first_name, first, second_name, second] => pairs.push(Pair {
+                first: image(first_name, first, line_number)?,
+                second: image(second_name, second, line_number)?,
+                is_same: false,
+            }),
+            _ => return Err(format!("Invalid pair on line {}: {line}", line_number + 1)),
+        }
+    }
+
+    Ok((folds, pairs))
+}
+
+pub fn main() {
+    // Parse arguments
+    let mut weights = PathBuf::from("mobilefacenet.bin");
+    let mut config_path = None;
+    let mut ext = String::from("jpg");
+    let mut batch_size = 32;
+    let mut num_folds = None;
+    let mut roc_path = PathBuf::from("roc.csv");
+    let mut positional = Vec::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().unwrap_or_else(|| panic!("No {name} provided"));
+        match arg.as_str() {
+            "--weights" => weights = value("weights path").into(),
+            "--config" => config_path = Some(PathBuf::from(value("config path"))),
+            "--ext" => ext = value("image extension"),
+            "--batch-size" => batch_size = value("batch size").parse().unwrap(),
+            "--folds" => num_folds = Some(value("number of folds").parse().unwrap()),
+            "--roc" => roc_path = value("ROC output path").into(),
+            _ => positional.push(arg),
+        }
+    }
+    let [pairs_path, image_dir] = &positional[..] else {
+        panic!("Expected a pairs file and an image directory");
+    };
+
+    let content = std::fs::read_to_string(pairs_path)
+        .map_err(|err| format!("Failed to read pairs {pairs_path}.\nError: {err}"))
+        .unwrap();
+    let (header_folds, pairs) = parse_pairs(&content, Path::new(image_dir), &ext)
+        .map_err(|err| format!("Failed to parse pairs {pairs_path}.\nError: {err}"))
+        .unwrap();
+    let num_folds = num_folds.or(header_folds).unwrap_or(10);
+
+    // Create MobileFaceNet
+    let device = Default::default();
+    let (model, config) =
+        load_model_file::<Backend>(&weights, config_path.as_deref(), &device).unwrap();
+
+    // Embed every distinct image once
+    let mut images: Vec<&Path> = pairs
+        .iter()
+        .flat_map(|pair| [pair.first.as_path(), pair.second.as_path()])
+        .collect();
+    images.sort();
+    images.dedup();
+
+    let mut embeddings: HashMap<&Path, Vec<f32>> = HashMap::with_capacity(images.len());
+    for (batch_index, batch) in images.chunks(batch_size).enumerate() {
+        let x = batch
+            .iter()
+            .map(|path| {
+                let img = image::open(path)
+                    .map_err(|err| {
+                        format!("Failed to load image {}.\nError: {err}", path.display())
+                    })
+                    .unwrap();
+                image_to_tensor::<Backend>(&img, config.input_size, &device)
+            })
+            .collect();
+        let output = l2_normalize(model.forward(Tensor::stack(x, 0)));
+        let [_, embedding_size] = output.dims();
+        let values = output.into_data().to_vec::<f32>().unwrap();
+
+        for (path, embedding) in batch.iter().zip(values.chunks(embedding_size)) {
+            embeddings.insert(path, embedding.to_vec());
+        }
+        eprintln!(
+            "Embedded {}/{} images",
+            batch_index * batch_size + batch.len(),
+            images.len()
+        );
+    }
+
+    // Score pairs with the cosine similarity of their (unit norm) embeddings
+    let scores: Vec<f32> = pairs
+        .iter()
+        .map(|pair| {
+            let first = &embeddings[pair.first.as_path()];
+            let second = &embeddings[pair.second.as_path()];
+            first.iter().zip(second).map(|(a, b)| a * b).sum()
+        })
+        .collect();
+    let is_same: Vec<bool> = pairs.iter().map(|pair| pair.is_same).collect();
+
+    // Report metrics
+    let folds = k_fold(&scores, &is_same, num_folds)
+        .map_err(|err| format!("Invalid number of folds.\nError: {err}"))
+        .unwrap();
+    let accuracies: Vec<f32> = folds.iter().map(|fold| fold.accuracy).collect();
+    let (mean, std) = mean_std(&accuracies);
+    let (threshold, best_accuracy) = best_threshold(&scores, &is_same);
+    let roc = roc(&scores, &is_same);
+
+    println!("metric,value");
+    println!("pairs,{}", pairs.len());
+    for (i, fold) in folds.iter().enumerate() {
+        println!("fold_{}_threshold,{:.6}", i + 1, fold.threshold);
+        println!("fold_{}_accuracy,{:.6}", i + 1, fold.accuracy);
+    }
+    println!("accuracy_mean,{mean:.6}");
+    println!("accuracy_std,{std:.6}");
+    println!("best_threshold,{threshold:.6}");
+    println!("best_threshold_accuracy,{best_accuracy:.6}");
+    for far in FARS {
+        println!("tar@far={far},{:.6}", tar_at_far(&roc, far));
+    }
+
+    let mut csv = String::from("threshold,tar,far,accuracy\n");
+    for point in roc.iter() {
+        writeln!(
+            csv,
+            "{},{:.6},{:.6},{:.6}",
+            point.threshold, point.tar, point.far, point.accuracy
+        )
+        .unwrap();
+    }
+    std::fs::write(&roc_path, csv)
+        .map_err(|err| format!("Failed to write {}.\nError: {err}", roc_path.display()))
+        .unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PAIRS: &str =
+        "Abel_Pacheco 1 4\nAkhmed_Zakayev 1 3\nAbdel_Madi_Shabneh 1 Dean_Barker 1\n";
+
+    #[test]
+    fn pairs_with_folds_header() {
+        let content = format!("10 300\n{PAIRS}");
+        let (folds, pairs) = parse_pairs(&content, Path::new("lfw"), "jpg").unwrap();
+
+        assert_eq!(folds, Some(10));
+        assert_eq!(pairs.len(), 3);
+        assert_eq!(
+            pairs[0].first,
+            Path::new("lfw/Abel_Pacheco/Abel_Pacheco_0001.jpg")
+        );
+        assert_eq!(
+            pairs[2].second,
+            Path::new("lfw/Dean_Barker/Dean_Barker_0001.jpg")
+        );
+        assert!(pairs[1].is_same);
+        assert!(!pairs[2].is_same);
+    }
+
+    #[test]
+    fn pairs_with_count_header() {
+        let content = format!("1100\n{PAIRS}");
+        let (folds, pairs) = parse_pairs(&content, Path::new("lfw"), "png").unwrap();
+
+        assert_eq!(folds, None);
+        assert_eq!(pairs.len(), 3);
+        assert_eq!(
+            pairs[1].second,
+            Path::new("lfw/Akhmed_Zakayev/Akhmed_Zakayev_0003.png")
+        );
+    }
+
+    #[test]
+    fn malformed_pairs_are_errors() {
+        for (content, error) in [
+            ("ten 300\n", "Invalid number ten on line 1"),
+            ("Abel_Pacheco one 4\n", "Invalid number one on line 1"),
+            ("10 300\nAbel_Pacheco 1 4 5 6\n", "Invalid pair on line 2"),
+            ("10 300\n1100\n", "Invalid pair on line 2"),
+        ] {
+            let err = parse_pairs(content, Path::new("lfw"), "jpg").err().unwrap();
+            assert!(err.contains(error), "{err}");
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use alloc::vec::Vec;
+use burn::tensor::{backend::Backend, Device, Tensor, TensorData};
+use image::DynamicImage;
+
+/// Pixel mean used to normalize aligned face crops (`transforms.Normalize([0.5] * 3, [0.5] * 3)`
+/// of the reference PyTorch implementation, expressed in the `[0, 255]` range).
+pub const PIXEL_MEAN: f32 = 127.5;
+/// Pixel standard deviation used to normalize aligned face crops.
+pub const PIXEL_STD: f32 = 127.5;
+
+/// Converts an aligned face crop into a normalized `[3, input_size, input_size]` tensor.
+pub fn image_to_tensor<B: Backend>(
+    image: &DynamicImage,
+    input_size: usize,
+    device: &Device<B>,
+) -> Tensor<B, 3> {
+    let resized = image.resize_exact(
+        input_size as u32,
+        input_size as u32,
+        image::imageops::FilterType::Triangle, // also known as bilinear in 2D
+    );
+    let data: Vec<f32> = resized
+        .into_rgb8()
+        .into_raw()
+        .into_iter()
+        .map(|v| v as f32)
+        .collect();
+
+    let x = Tensor::<B, 3>::from_data(
+        TensorData::new(data, [input_size, input_size, 3]).convert::<B::FloatElem>(),
+        device,
+    )
+    // [H, W, C] -> [C, H, W]
+    .permute([2, 0, 1]);
+
+    (x - PIXEL_MEAN) / PIXEL_STD
+}
+
+/// Scales every embedding of a `[batch, embedding_size]` tensor to unit L2 norm.
+pub fn l2_normalize<B: Backend>(embeddings: Tensor<B, 2>) -> Tensor<B, 2> {
+    let norm = embeddings
+        .clone()
+        .powf_scalar(2.0)
+        .sum_dim(1)
+        .sqrt()
+        .clamp_min(1e-12);
+
+    embeddings / norm
+}
+
+/// Cosine similarity between every pair of rows of two `[n, embedding_size]` and
+/// `[m, embedding_size]` embedding tensors. Returns a `[n, m]` similarity matrix.
+pub fn cosine_similarity<B: Backend>(a: Tensor<B, 2>, b: Tensor<B, 2>) -> Tensor<B, 2> {
+    l2_normalize(a).matmul(l2_normalize(b).transpose())
+}
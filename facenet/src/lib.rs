@@ -1,7 +1,11 @@
 // #![cfg_attr(not(test), no_std)]
 
 // pub mod model;
+pub mod embedding;
+pub mod metrics;
 pub mod state;
 pub mod mobilefacenet;
+pub mod web;
+pub mod worker;
 
 extern crate alloc;
\ No newline at end of file
@@ -0,0 +1,300 @@
+//! Face verification metrics over scored pairs.
+//!
+//! A pair is predicted to share an identity when its similarity score is greater or equal than
+//! the decision threshold.
+
+use alloc::{format, string::String, vec::Vec};
+
+/// A point of the receiver operating characteristic (ROC) curve.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RocPoint {
+    /// Decision threshold on the similarity score.
+    pub threshold: f32,
+    /// True accept rate: fraction of matching pairs accepted at this threshold.
+    pub tar: f32,
+    /// False accept rate: fraction of non-matching pairs accepted at this threshold.
+    pub far: f32,
+    /// Fraction of correctly classified pairs at this threshold.
+    pub accuracy: f32,
+}
+
+/// Result of evaluating one fold of a k-fold verification protocol.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Fold {
+    /// Threshold selected on the remaining folds.
+    pub threshold: f32,
+    /// Accuracy obtained on this fold with the selected threshold.
+    pub accuracy: f32,
+}
+
+/// Fraction of pairs correctly classified with the given `threshold`.
+pub fn accuracy(scores: &[f32], is_same: &[bool], threshold: f32) -> f32 {
+    assert_eq!(scores.len(), is_same.len(), "Expected one label per score");
+    if scores.is_empty() {
+        return 0.;
+    }
+
+    let correct = scores
+        .iter()
+        .zip(is_same)
+        .filter(|(score, same)| (**score >= threshold) == **same)
+        .count();
+
+    correct as f32 / scores.len() as f32
+}
+
+/// Computes the ROC curve, with one point per distinct score in decreasing threshold order.
+///
+/// The first point uses an infinite threshold, at which no pair is accepted.
+pub fn roc(scores: &[f32], is_same: &[bool]) -> Vec<RocPoint> {
+    assert_eq!(scores.len(), is_same.len(), "Expected one label per score");
+
+    let num_same = is_same.iter().filter(|same| **same).count();
+    let num_diff = is_same.len() - num_same;
+    let rate = |count: usize, total: usize| {
+        if total == 0 {
+            0.
+        } else {
+            count as f32 / total as f32
+        }
+    };
+    let point = |threshold: f32, tp: usize, fp: usize| RocPoint {
+        threshold,
+        tar: rate(tp, num_same),
+        far: rate(fp, num_diff),
+        accuracy: rate(tp + num_diff - fp, is_same.len()),
+    };
+
+    let mut order: Vec<usize> = (0..scores.len()).collect();
+    order.sort_by(|a, b| scores[*b].total_cmp(&scores[*a]));
+
+    let mut points = Vec::with_capacity(scores.len() + 1);
+    points.push(point(f32::INFINITY, 0, 0));
+
+    let (mut tp, mut fp) = (0, 0);
+    for (i, idx) in order.iter().enumerate() {
+        if is_same[*idx] {
+            tp += 1;
+        } else {
+            fp += 1;
+        }
+        // Emit a point once all the pairs sharing this score have been accepted
+        let score = scores[*idx];
+        if order.get(i + 1).is_none_or(|next| scores[*next] != score) {
+            points.push(point(score, tp, fp));
+        }
+    }
+
+    points
+}
+
+/// Threshold that maximizes the accuracy over the given pairs, along with that accuracy.
+///
+/// The threshold is placed halfway between the lowest accepted and the highest rejected score,
+/// which gives the same accuracy on these pairs but generalizes better to unseen ones.
+pub fn best_threshold(scores: &[f32], is_same: &[bool]) -> (f32, f32) {
+    let points = roc(scores, is_same);
+
+    // Keep the first (highest) threshold on ties
+    let best = (0..points.len()).fold(0, |best, i| {
+        if points[i].accuracy > points[best].accuracy {
+            i
+        } else {
+            best
+        }
+    });
+
+    let threshold = match points.get(best + 1) {
+        Some(next) if best > 0 => (points[best].threshold + next.threshold) / 2.,
+        _ => points[best].threshold,
+    };
+
+    (threshold, points[best].accuracy)
+}
+
+/// Highest true accept rate reachable with a false accept rate of at most `far`.
+pub fn tar_at_far(roc: &[RocPoint], far: f32) -> f32 {
+    roc.iter()
+        .filter(|p| p.far <= far)
+        .map(|p| p.tar)
+        .fold(0., f32::max)
+}
+
+/// K-fold verification protocol (as used by LFW).
+///
+/// The matching and the non-matching pairs are each split into `num_folds` contiguous folds, so
+/// that every fold holds both kinds even when the pairs are sorted by kind, and the folds of LFW
+/// `pairs.txt` are kept. For each fold, the threshold is selected on the other folds and the
+/// accuracy is measured on the held-out fold.
+///
+/// # Returns
+///
+/// An error if there are fewer than 2 folds, or more folds than pairs of the most common kind.
+pub fn k_fold(scores: &[f32], is_same: &[bool], num_folds: usize) -> Result<Vec<Fold>, String> {
+    assert_eq!(scores.len(), is_same.len(), "Expected one label per score");
+
+    let num_same = is_same.iter().filter(|same| **same).count();
+    let num_diff = is_same.len() - num_same;
+    let max_folds = num_same.max(num_diff);
+    if num_folds < 2 || num_folds > max_folds {
+        return Err(format!(
+            "Expected between 2 and {max_folds} folds (the number of pairs of the most common \
+             kind), got {num_folds}"
+        ));
+    }
+
+    let (mut same_rank, mut diff_rank) = (0, 0);
+    let fold_of: Vec<usize> = is_same
+        .iter()
+        .map(|same| {
+            let (rank, count) = if *same {
+                (&mut same_rank, num_same)
+            } else {
+                (&mut diff_rank, num_diff)
+            };
+            let fold = *rank * num_folds / count;
+            *rank += 1;
+            fold
+        })
+        .collect();
+
+    let split = |fold: usize, held_out: bool| -> (Vec<f32>, Vec<bool>) {
+        scores
+            .iter()
+            .zip(is_same)
+            .zip(&fold_of)
+            .filter(|(_, pair_fold)| (**pair_fold == fold) == held_out)
+            .map(|((score, same), _)| (*score, *same))
+            .unzip()
+    };
+
+    Ok((0..num_folds)
+        .map(|fold| {
+            let (train_scores, train_same) = split(fold, false);
+            let (test_scores, test_same) = split(fold, true);
+
+            let (threshold, _) = best_threshold(&train_scores, &train_same);
+            let accuracy = accuracy(&test_scores, &test_same, threshold);
+
+            Fold {
+                threshold,
+                accuracy,
+            }
+        })
+        .collect())
+}
+
+/// Mean and (population) standard deviation of the given values.
+pub fn mean_std(values: &[f32]) -> (f32, f32) {
+    if values.is_empty() {
+        return (0., 0.);
+    }
+
+    let n = values.len() as f32;
+    let mean = values.iter().sum::<f32>() / n;
+    let var = values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / n;
+
+    (mean, var.sqrt())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SCORES: [f32; 6] = [0.9, 0.8, 0.7, 0.4, 0.3, 0.1];
+    const SAME: [bool; 6] = [true, true, false, true, false, false];
+
+    #[test]
+    fn accuracy_counts_scores_at_threshold_as_accepted() {
+        assert_eq!(accuracy(&SCORES, &SAME, 0.8), 5. / 6.);
+        assert_eq!(accuracy(&SCORES, &SAME, 0.7), 4. / 6.);
+        assert_eq!(accuracy(&[], &[], 0.5), 0.);
+    }
+
+    #[test]
+    fn roc_sweeps_every_score() {
+        let points = roc(&SCORES, &SAME);
+
+        assert_eq!(points.len(), SCORES.len() + 1);
+        assert_eq!(points[0].threshold, f32::INFINITY);
+        assert_eq!((points[0].tar, points[0].far), (0., 0.));
+        assert_eq!((points[2].tar, points[2].far), (2. / 3., 0.));
+        assert_eq!((points[3].tar, points[3].far), (2. / 3., 1. / 3.));
+        assert_eq!((points[6].tar, points[6].far), (1., 1.));
+    }
+
+    #[test]
+    fn roc_merges_tied_scores() {
+        let points = roc(&[0.5, 0.5, 0.2], &[true, false, false]);
+
+        assert_eq!(points.len(), 3);
+        assert_eq!(points[1].threshold, 0.5);
+        assert_eq!((points[1].tar, points[1].far), (1., 0.5));
+    }
+
+    #[test]
+    fn best_threshold_separates_perfectly_separable_pairs() {
+        let scores = [0.9, 0.6, 0.5, 0.1];
+        let same = [true, true, false, false];
+
+        let (threshold, accuracy) = best_threshold(&scores, &same);
+
+        assert!((threshold - 0.55).abs() < 1e-6);
+        assert_eq!(accuracy, 1.);
+    }
+
+    #[test]
+    fn tar_at_far_picks_highest_admissible_point() {
+        let points = roc(&SCORES, &SAME);
+
+        assert_eq!(tar_at_far(&points, 0.), 2. / 3.);
+        assert_eq!(tar_at_far(&points, 0.5), 1.);
+        assert_eq!(tar_at_far(&points, 1.), 1.);
+    }
+
+    #[test]
+    fn k_fold_selects_threshold_on_other_folds() {
+        let scores = [0.9, 0.2, 0.8, 0.3, 0.7, 0.1];
+        let same = [true, false, true, false, true, false];
+
+        let folds = k_fold(&scores, &same, 3).unwrap();
+
+        assert_eq!(folds.len(), 3);
+        for fold in folds {
+            assert_eq!(fold.accuracy, 1.);
+            assert!(fold.threshold > 0.3 && fold.threshold <= 0.9);
+        }
+    }
+
+    #[test]
+    fn k_fold_splits_pairs_sorted_by_kind() {
+        // All the matching pairs first, as in the lists with a count header
+        let scores = [0.9, 0.8, 0.7, 0.3, 0.2, 0.1];
+        let same = [true, true, true, false, false, false];
+
+        let folds = k_fold(&scores, &same, 3).unwrap();
+
+        for fold in folds {
+            assert_eq!(fold.accuracy, 1.);
+            assert!(fold.threshold > 0.3 && fold.threshold < 0.9);
+        }
+    }
+
+    #[test]
+    fn k_fold_rejects_invalid_numbers_of_folds() {
+        let same = [true, true, false];
+
+        assert!(k_fold(&[0.9, 0.8, 0.1], &same, 1).is_err());
+        assert!(k_fold(&[0.9, 0.8, 0.1], &same, 3).is_err());
+        assert!(k_fold(&[0.9, 0.8, 0.1], &same, 2).is_ok());
+        assert!(k_fold(&[], &[], 2).is_err());
+    }
+
+    #[test]
+    fn mean_std_of_values() {
+        let (mean, std) = mean_std(&[1., 3.]);
+
+        assert_eq!(mean, 2.);
+        assert_eq!(std, 1.);
+    }
+}
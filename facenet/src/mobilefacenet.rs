@@ -1,4 +1,5 @@
 #![allow(clippy::new_without_default)]
+#![allow(clippy::upper_case_acronyms)]
 
 /// use burn::prelude::*;
 use burn::{
@@ -11,6 +12,96 @@ use burn::{
         conv::Conv2d},
     prelude::*,
 };
+use inference_runtime::{fold_batch_norm, ConvNorm, ModelLoader};
+
+#[cfg(not(target_family = "wasm"))]
+use {
+    burn::record::{FullPrecisionSettings, Recorder, RecorderError},
+    burn_import::pytorch::{LoadArgs, PyTorchFileRecorder},
+    std::path::PathBuf,
+};
+
+/// Smallest input size, below which the last feature map would be empty after the four stride 2
+/// convolutions.
+pub const MIN_INPUT_SIZE: usize = 16;
+
+/// Compute the number of channels based on the provided width multiplier.
+fn expand(num_channels: usize, factor: f64) -> usize {
+    ((num_channels as f64 * factor).floor() as usize).max(1)
+}
+
+/// Spatial size of the last feature map for a square input of `input_size` pixels.
+fn feature_size(input_size: usize) -> usize {
+    // conv_1, conv_23, conv_34 and conv_45 are 3x3 convolutions with stride 2 and padding 1.
+    (0..4).fold(input_size, |size, _| (size - 1) / 2 + 1)
+}
+
+/// Embedding head placed on top of the MobileFaceNet feature extractor.
+#[derive(Config, Debug, PartialEq)]
+pub enum OutputHead {
+    /// Global norm-aware pooling.
+    Gnap,
+    /// Global depthwise convolution.
+    Gdc,
+}
+
+/// [MobileFaceNet](MobileFaceNet) configuration.
+#[derive(Config, Debug)]
+pub struct MobileFaceNetConfig {
+    /// Size of the output embedding.
+    #[config(default = "512")]
+    pub embedding_size: usize,
+    /// Head used to turn the feature map into an embedding.
+    #[config(default = "OutputHead::Gdc")]
+    pub output_head: OutputHead,
+    /// Factor applied to the number of channels of every intermediate block.
+    #[config(default = "1.0")]
+    pub width_multiplier: f64,
+    /// Height and width of the (square) input image.
+    #[config(default = "112")]
+    pub input_size: usize,
+}
+
+impl MobileFaceNetConfig {
+    /// Initialize a new [MobileFaceNet](MobileFaceNet) module.
+    ///
+    /// # Panics
+    ///
+    /// If the input size is smaller than [MIN_INPUT_SIZE], configs from users should be
+    /// [validated](MobileFaceNetConfig::validate) first.
+    pub fn init<B: Backend>(&self, device: &B::Device) -> MobileFaceNet<B> {
+        MobileFaceNet::new(self, device)
+    }
+
+    /// Checks that a model can be built from the config.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.input_size < MIN_INPUT_SIZE {
+            return Err(format!(
+                "The input size must be at least {MIN_INPUT_SIZE}, got {}",
+                self.input_size
+            ));
+        }
+        if self.embedding_size == 0 {
+            return Err("The embedding size must be positive".into());
+        }
+        if !(self.width_multiplier.is_finite() && self.width_multiplier > 0.) {
+            return Err(format!(
+                "The width multiplier must be positive, got {}",
+                self.width_multiplier
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+impl<B: Backend> ModelLoader<B> for MobileFaceNet<B> {
+    type Config = MobileFaceNetConfig;
+
+    fn init_model(config: &Self::Config, device: &B::Device) -> Self {
+        config.init(device)
+    }
+}
 #[derive(Module, Debug, Clone)] // Add Debug here
 pub struct Flatten; // Unit struct
 
@@ -31,8 +122,8 @@ impl Flatten {
 #[derive(Module, Debug)]
 pub struct ConvBlock<B: Backend> {
     conv: nn::conv::Conv2d<B>,
-    norm: BatchNorm<B, 2>,
-    activation: nn::Relu,
+    norm: ConvNorm<B>,
+    activation: nn::PRelu<B>,
 }
 
 impl<B: Backend> ConvBlock<B> {
@@ -41,9 +132,10 @@ impl<B: Backend> ConvBlock<B> {
             .with_padding(PaddingConfig2d::Explicit(padding[0], padding[1]))
             .with_groups(groups)
             .with_stride(stride)
+            .with_bias(false)
             .init(device);
-        let norm = nn::BatchNormConfig::new(out_c).init(device);
-        let activation = nn::Relu::new();
+        let norm = nn::BatchNormConfig::new(out_c).init(device).into();
+        let activation = nn::PReluConfig::new().with_num_parameters(out_c).init(device);
 
         Self {
             conv,
@@ -58,13 +150,21 @@ impl<B: Backend> ConvBlock<B> {
 
         self.activation.forward(x)
     }
+
+    /// Folds the batch norm into the convolution, which skips it at inference with the same
+    /// output. The fused block must not be trained.
+    pub fn fuse(self) -> Self {
+        let (conv, norm) = fold_batch_norm(self.conv, self.norm);
+
+        Self { conv, norm, ..self }
+    }
 }
 
 
 #[derive(Module, Debug)]
 pub struct LinearBlock<B: Backend> {
     conv: Conv2d<B>,
-    norm: BatchNorm<B, 2>,
+    norm: ConvNorm<B>,
 }
 
 impl<B: Backend> LinearBlock<B>{
@@ -73,9 +173,10 @@ impl<B: Backend> LinearBlock<B>{
             .with_padding(PaddingConfig2d::Explicit(padding[0], padding[1]))
             .with_groups(groups)
             .with_stride(stride)
+            .with_bias(false)
             .init(device);
 
-        let norm = nn::BatchNormConfig::new(out_c).init(device);
+        let norm = nn::BatchNormConfig::new(out_c).init(device).into();
 
         Self {
             conv,
@@ -85,8 +186,14 @@ impl<B: Backend> LinearBlock<B>{
 
     pub fn forward(&self, input: Tensor<B, 4>) -> Tensor<B, 4> {
         let x = self.conv.forward(input);
-        let x = self.norm.forward(x);
-        x
+        self.norm.forward(x)
+    }
+
+    /// Folds the batch norm into the convolution, see [ConvBlock::fuse].
+    pub fn fuse(self) -> Self {
+        let (conv, norm) = fold_batch_norm(self.conv, self.norm);
+
+        Self { conv, norm }
     }
 }
 
@@ -100,6 +207,7 @@ pub struct DepthWise<B: Backend> {
 }
 
 impl<B: Backend> DepthWise<B> {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(input_c: usize, output_c: usize, residual: bool, kernel: [usize;2], stride: [usize;2], padding: [usize;2], groups: usize, device: &B::Device) -> Self {
         let conv = ConvBlock::new(input_c, groups, [1,1], [1,1], [0,0], 1, device);
         let conv_dw = ConvBlock::new(groups, groups, kernel, stride, padding, groups, device);
@@ -124,6 +232,16 @@ impl<B: Backend> DepthWise<B> {
             output
         }
     }
+
+    /// Folds the batch norms into the convolutions, see [ConvBlock::fuse].
+    pub fn fuse(self) -> Self {
+        Self {
+            conv: self.conv.fuse(),
+            conv_dw: self.conv_dw.fuse(),
+            project: self.project.fuse(),
+            residual: self.residual,
+        }
+    }
 }
 
 
@@ -147,6 +265,13 @@ impl<B: Backend> Residual<B> {
             .iter()
             .fold(input, |x, block| block.forward(x))
     }
+
+    /// Folds the batch norms into the convolutions, see [ConvBlock::fuse].
+    pub fn fuse(self) -> Self {
+        let model = self.model.into_iter().map(DepthWise::fuse).collect();
+
+        Self { model }
+    }
 }
 
 #[derive(Module, Debug)]
@@ -166,21 +291,15 @@ impl<B: Backend> GNAP<B> {
     }
 
     pub fn forward(&self, input: Tensor<B, 4>) -> Tensor<B, 2> {
-        println!("Input shape to GNAP: {:?}", input.dims());
-        let x_bn1 = self.bn1.forward(input);
-        println!("After bn1: {:?}", x_bn1.dims());
-        // L2 Norm
-        let x_norm = x_bn1.clone().powf_scalar(2.0).sum_dim(1).unsqueeze_dim(1).sqrt();
-        let x_norm_mean = x_bn1.clone().mean();
+        let x = self.bn1.forward(input);
+        // L2 norm over the channels of every spatial location: [batch, 1, height, width]
+        let x_norm = x.clone().powf_scalar(2.0).sum_dim(1).sqrt();
+        let x_norm_mean = x_norm.clone().mean().reshape([1, 1, 1, 1]);
         let weight = x_norm_mean.div(x_norm);
-        let weight: Tensor<B, 2> = weight.unsqueeze_dim(1);
-        let weight: Tensor<B, 3> = weight.unsqueeze_dim(2);
-        let weight: Tensor<B, 4> = weight.unsqueeze_dim(3);
-        let x_weighted = x_bn1.mul(weight);
-        let x_pooled = self.pool.forward(x_weighted);
-        let x_shape = x_pooled.shape().dims;
-        let x_reshaped = x_pooled.reshape([x_shape[0], x_shape[1]*x_shape[2]*x_shape[3]]);
-        self.bn2.forward(x_reshaped)
+        let x = self.pool.forward(x.mul(weight));
+        let [batch_size, channels, _, _] = x.dims();
+        let x: Tensor<B, 3> = x.reshape([batch_size, channels, 1]);
+        self.bn2.forward(x).squeeze::<2>(2)
     }
 }
 
@@ -193,10 +312,11 @@ pub struct GDC<B: Backend> {
 }
 
 impl<B: Backend> GDC<B> {
-    pub fn new(embedding_size: usize, device: &B::Device) -> Self {
-        let conv_6_dw = LinearBlock::new(512, 512, [7, 7], [1, 1], [0, 0], 512, device);
+    /// Creates a GDC head reducing a `[batch, in_c, kernel, kernel]` feature map to an embedding.
+    pub fn new(in_c: usize, embedding_size: usize, kernel: usize, device: &B::Device) -> Self {
+        let conv_6_dw = LinearBlock::new(in_c, in_c, [kernel, kernel], [1, 1], [0, 0], in_c, device);
         let conv_6_flatten = Flatten::new();
-        let linear = nn::LinearConfig::new(512, embedding_size).with_bias(false).init(device);
+        let linear = nn::LinearConfig::new(in_c, embedding_size).with_bias(false).init(device);
         let bn = BatchNormConfig::new(embedding_size).init(device);
 
         Self { conv_6_dw, conv_6_flatten, linear, bn }
@@ -207,15 +327,18 @@ impl<B: Backend> GDC<B> {
         let x = self.conv_6_flatten.forward(x);
         let x = self.linear.forward(x);
         let x: Tensor<B, 3> = x.unsqueeze_dim(2);
-        println!("Input shape to GDC (bn): {:?}", x.dims());
         let x = self.bn.forward(x);
-        println!("After GDC (bn): {:?}", x.dims());
-        let x: Tensor<B, 2> = x.squeeze::<2>(2);
-        x
+        x.squeeze::<2>(2)
+    }
+
+    /// Folds the batch norm of the depthwise convolution into it, see [ConvBlock::fuse].
+    pub fn fuse(self) -> Self {
+        Self { conv_6_dw: self.conv_6_dw.fuse(), ..self }
     }
 }
 
 #[derive(Module, Debug)]
+#[allow(clippy::large_enum_variant)]
 enum OutputLayer<B: Backend> {
     GNAP(GNAP<B>),
     GDC(GDC<B>),
@@ -236,22 +359,39 @@ pub struct MobileFaceNet<B: Backend> {
 }
 
 impl<B: Backend> MobileFaceNet<B> {
-    pub fn new(embedding_size: usize, output_name: &str, device: &B::Device) -> Self {
-        // Xiao: Note, the input is ignored here as it is not used in the MobileFaceNet implementation???
-        assert!(matches!(output_name, "GNAP" | "GDC"));
-        let conv_1 = ConvBlock::new(3, 64, [3, 3], [2, 2], [1, 1], 1, device);
-        let conv_2_dw = ConvBlock::new(64, 64, [3, 3], [1, 1], [1, 1], 64, device);
-        let conv_23 = DepthWise::new(64, 64, false, [3, 3], [2, 2], [1, 1], 128, device);
-        let conv_3 = Residual::new(64, 4, 128, [3, 3], [1, 1], [1, 1], device);
-        let conv_34 = DepthWise::new(64, 128, false, [3, 3], [2, 2], [1, 1], 256, device);
-        let conv_4 = Residual::new(128, 6, 256, [3, 3], [1, 1], [1, 1], device);
-        let conv_45 = DepthWise::new(128, 128, false, [3, 3], [2, 2], [1, 1], 512, device);
-        let conv_5 = Residual::new(128, 2, 256, [3, 3], [1, 1], [1, 1], device);
-        let conv_6_sep = ConvBlock::new(128, 512, [1, 1], [1, 1], [0, 0], 1, device);
-        let output_layer = match output_name {
-            "GNAP" => OutputLayer::GNAP(GNAP::new(512, device)),
-            "GDC" => OutputLayer::GDC(GDC::new(embedding_size, device)),
-            _ => unreachable!(),
+    pub fn new(config: &MobileFaceNetConfig, device: &B::Device) -> Self {
+        assert!(
+            config.input_size >= MIN_INPUT_SIZE,
+            "invalid input size {}, must be at least {MIN_INPUT_SIZE}",
+            config.input_size
+        );
+
+        let width = |c: usize| expand(c, config.width_multiplier);
+        let (c64, c128, c256, c512) = (width(64), width(128), width(256), width(512));
+        // GNAP pools the conv_6_sep features directly, so they must already be embedding sized.
+        // GDC scales them as the other stages.
+        let features = match config.output_head {
+            OutputHead::Gnap => config.embedding_size,
+            OutputHead::Gdc => c512,
+        };
+
+        let conv_1 = ConvBlock::new(3, c64, [3, 3], [2, 2], [1, 1], 1, device);
+        let conv_2_dw = ConvBlock::new(c64, c64, [3, 3], [1, 1], [1, 1], c64, device);
+        let conv_23 = DepthWise::new(c64, c64, false, [3, 3], [2, 2], [1, 1], c128, device);
+        let conv_3 = Residual::new(c64, 4, c128, [3, 3], [1, 1], [1, 1], device);
+        let conv_34 = DepthWise::new(c64, c128, false, [3, 3], [2, 2], [1, 1], c256, device);
+        let conv_4 = Residual::new(c128, 6, c256, [3, 3], [1, 1], [1, 1], device);
+        let conv_45 = DepthWise::new(c128, c128, false, [3, 3], [2, 2], [1, 1], c512, device);
+        let conv_5 = Residual::new(c128, 2, c256, [3, 3], [1, 1], [1, 1], device);
+        let conv_6_sep = ConvBlock::new(c128, features, [1, 1], [1, 1], [0, 0], 1, device);
+        let output_layer = match config.output_head {
+            OutputHead::Gnap => OutputLayer::GNAP(GNAP::new(config.embedding_size, device)),
+            OutputHead::Gdc => OutputLayer::GDC(GDC::new(
+                features,
+                config.embedding_size,
+                feature_size(config.input_size),
+                device,
+            )),
         };
 
         Self {
@@ -303,4 +443,71 @@ impl<B: Backend> MobileFaceNet<B> {
             OutputLayer::GDC(gdc) => gdc.forward(conv_features),
         }
     }
-}
\ No newline at end of file
+
+    /// Folds the batch norm of every convolution block into its convolution, for a faster
+    /// inference with the same embeddings. The fused model must not be trained, and its records
+    /// cannot be loaded into an unfused model.
+    pub fn fuse(self) -> Self {
+        let output_layer = match self.output_layer {
+            OutputLayer::GNAP(gnap) => OutputLayer::GNAP(gnap),
+            OutputLayer::GDC(gdc) => OutputLayer::GDC(gdc.fuse()),
+        };
+
+        Self {
+            conv_1: self.conv_1.fuse(),
+            conv_2_dw: self.conv_2_dw.fuse(),
+            conv_23: self.conv_23.fuse(),
+            conv_3: self.conv_3.fuse(),
+            conv_34: self.conv_34.fuse(),
+            conv_4: self.conv_4.fuse(),
+            conv_45: self.conv_45.fuse(),
+            conv_5: self.conv_5.fuse(),
+            conv_6_sep: self.conv_6_sep.fuse(),
+            output_layer,
+        }
+    }
+    /// Load a MobileFaceNet from a PyTorch checkpoint (`state_dict`) of the reference
+    /// implementation.
+    #[cfg(not(target_family = "wasm"))]
+    pub fn from_pytorch(
+        config: &MobileFaceNetConfig,
+        checkpoint: PathBuf,
+        device: &B::Device,
+    ) -> Result<Self, RecorderError> {
+        let record = Self::load_weights_record(config, checkpoint, device)?;
+
+        Ok(config.init(device).load_record(record))
+    }
+
+    /// Load the weights of a PyTorch checkpoint (`state_dict`) as a record.
+    #[cfg(not(target_family = "wasm"))]
+    pub fn load_weights_record(
+        config: &MobileFaceNetConfig,
+        checkpoint: PathBuf,
+        device: &B::Device,
+    ) -> Result<MobileFaceNetRecord<B>, RecorderError> {
+        config.validate().map_err(RecorderError::Unknown)?;
+
+        // The reference GNAP head uses non-affine batch norms, which have no weight and bias to
+        // map onto Burn's gamma and beta.
+        if config.output_head == OutputHead::Gnap {
+            return Err(RecorderError::Unknown(
+                "Loading PyTorch weights is only supported for the GDC output head".into(),
+            ));
+        }
+
+        let load_args = LoadArgs::new(checkpoint)
+            // Map module.* -> * (checkpoints saved from nn.DataParallel)
+            .with_key_remap("^module\\.(.+)", "$1")
+            // Map conv1.* -> conv_1.*
+            .with_key_remap("^conv1\\.(.+)", "conv_1.$1")
+            // Map conv2_dw.* -> conv_2_dw.*
+            .with_key_remap("^conv2_dw\\.(.+)", "conv_2_dw.$1")
+            // Map {conv* | project}.bn.* -> {conv* | project}.norm.* (but not output_layer.bn.*)
+            .with_key_remap("^(.*(conv[^.]*|project))\\.bn\\.(.+)", "$1.norm.$3")
+            // Map *.prelu.weight -> *.activation.alpha
+            .with_key_remap("(.+)\\.prelu\\.weight", "$1.activation.alpha");
+
+        PyTorchFileRecorder::<FullPrecisionSettings>::new().load(load_args, device)
+    }
+}
@@ -1,29 +1,53 @@
-use crate::mobilefacenet::MobileFaceNet;
-use burn::{
-    module::Module,
-    record::{BinBytesRecorder, FullPrecisionSettings, Recorder},
-};
-
-#[cfg(feature = "wgpu")]
-use burn::backend::wgpu::{init_async, AutoGraphicsApi, Wgpu, WgpuDevice};
+use crate::mobilefacenet::{MobileFaceNet, MobileFaceNetConfig};
+use burn::record::RecorderError;
+use inference_runtime::load_model;
 
-#[cfg(feature = "wgpu")]
-pub type Backend = Wgpu<f32, i32>;
+#[cfg(not(target_family = "wasm"))]
+use {
+    alloc::{format, string::String},
+    burn::config::Config,
+    inference_runtime::ModelLoader,
+    std::path::Path,
+};
 
-#[cfg(all(feature = "ndarray", not(feature = "wgpu")))]
-pub type Backend = burn::backend::ndarray::NdArray<f32>;
+pub use inference_runtime::{init_device, Backend};
 
-static STATE_ENCODED: &[u8] = include_bytes!("../mobilefacenet.bin");
+/// Builds the model and loads the parameters of a record received from JavaScript,
+/// [fused](MobileFaceNet::fuse) for inference.
+pub async fn build_and_load_model_from(
+    config: &MobileFaceNetConfig,
+    bytes: &[u8],
+) -> Result<MobileFaceNet<Backend>, RecorderError> {
+    Ok(load_model::<MobileFaceNet<Backend>>(config, bytes)
+        .await?
+        .fuse())
+}
 
-/// Builds and loads trained parameters into the model.
-pub async fn build_and_load_model() -> MobileFaceNet<Backend> {
-    #[cfg(feature = "wgpu")]
-    init_async::<AutoGraphicsApi>(&WgpuDevice::default(), Default::default()).await;
+/// Loads a model from a record file written by `convert_weights`, [fused](MobileFaceNet::fuse)
+/// for inference.
+///
+/// The config is read from `config` when given, otherwise from the JSON file saved next to the
+/// weights, falling back to the default configuration.
+#[cfg(not(target_family = "wasm"))]
+pub fn load_model_file<B: burn::tensor::backend::Backend>(
+    weights: &Path,
+    config: Option<&Path>,
+    device: &B::Device,
+) -> Result<(MobileFaceNet<B>, MobileFaceNetConfig), String> {
+    let default_config = weights.with_extension("json");
+    let config = match config.or(default_config.exists().then_some(default_config.as_path())) {
+        Some(path) => MobileFaceNetConfig::load(path)
+            .map_err(|err| format!("Failed to load config {}: {err}", path.display()))?,
+        None => MobileFaceNetConfig::new(),
+    };
+    config
+        .validate()
+        .map_err(|err| format!("Invalid config: {err}"))?;
 
-    let model: MobileFaceNet<Backend> = MobileFaceNet::new(512, "GDC", &Default::default());
-    let record = BinBytesRecorder::<FullPrecisionSettings>::default()
-        .load(STATE_ENCODED.to_vec(), &Default::default())
-        .expect("Failed to decode state");
+    let bytes = std::fs::read(weights)
+        .map_err(|err| format!("Failed to read weights {}: {err}", weights.display()))?;
+    let model = MobileFaceNet::from_bytes(&config, bytes, device)
+        .map_err(|err| format!("Failed to decode weights: {err}"))?;
 
-    model.load_record(record)
-}
\ No newline at end of file
+    Ok((model.fuse(), config))
+}
@@ -0,0 +1,108 @@
+#![allow(clippy::new_without_default)]
+
+use alloc::{format, string::String, vec::Vec};
+use burn::config::Config;
+use image::{DynamicImage, RgbaImage};
+use inference_runtime::Weights;
+
+#[cfg(target_family = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::embedding::{image_to_tensor, l2_normalize};
+use crate::mobilefacenet::{MobileFaceNet, MobileFaceNetConfig};
+use crate::state::{build_and_load_model_from, Backend};
+
+#[cfg_attr(target_family = "wasm", wasm_bindgen(start))]
+pub fn start() {
+    console_error_panic_hook::set_once();
+}
+
+/// FaceNet structure that corresponds to JavaScript class, computing the MobileFaceNet embeddings
+/// of aligned face crops.
+///
+/// No weights are embedded in the wasm module, as the repository has no trained record: they are
+/// converted from a trained checkpoint by `convert_weights` and received from JavaScript.
+#[cfg_attr(target_family = "wasm", wasm_bindgen)]
+pub struct FaceNet {
+    config: MobileFaceNetConfig,
+    /// Weights received from JavaScript, dropped once loaded on the first embedding.
+    weights: Option<Vec<u8>>,
+    model: Option<MobileFaceNet<Backend>>,
+}
+
+#[cfg_attr(target_family = "wasm", wasm_bindgen)]
+impl FaceNet {
+    /// Returns a model using weights received from JavaScript, e.g. with `fetchWeights`.
+    ///
+    /// # Arguments
+    ///
+    /// * `weights` - MobileFaceNet record written by `convert_weights`, loaded on the first
+    ///   embedding.
+    /// * `config` - JSON config written next to the record, if the architecture is not the
+    ///   default one.
+    ///
+    /// # Returns
+    ///
+    /// An error if the weights are incomplete or do not match their checksum, or if the config is
+    /// invalid.
+    pub fn with_weights(weights: Weights, config: Option<String>) -> Result<FaceNet, String> {
+        let config = match config {
+            Some(config) => MobileFaceNetConfig::load_binary(config.as_bytes())
+                .map_err(|err| format!("Invalid config: {err}"))?,
+            None => MobileFaceNetConfig::new(),
+        };
+        config
+            .validate()
+            .map_err(|err| format!("Invalid config: {err}"))?;
+
+        Ok(Self::init(config, weights.into_bytes()?))
+    }
+
+    /// Returns the L2 normalized embedding of an aligned face crop.
+    ///
+    /// # Arguments
+    ///
+    /// * `rgba` - RGBA pixels of the crop, e.g. the `data` of a canvas `ImageData`. The crop is
+    ///   resized to the input size of the model.
+    /// * `width` - Crop width.
+    /// * `height` - Crop height.
+    pub async fn embed(
+        &mut self,
+        rgba: &[u8],
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<f32>, String> {
+        if self.model.is_none() {
+            let model = match self.weights.as_deref() {
+                Some(weights) => build_and_load_model_from(&self.config, weights)
+                    .await
+                    .map_err(|err| format!("Failed to load the weights: {err}"))?,
+                None => unreachable!("The weights are only dropped once loaded"),
+            };
+            self.weights = None;
+            self.model = Some(model);
+        }
+        let model = self.model.as_ref().unwrap();
+
+        let crop = RgbaImage::from_raw(width, height, rgba.to_vec())
+            .map(DynamicImage::ImageRgba8)
+            .ok_or("The pixels do not match the crop size")?;
+
+        let device = Default::default();
+        let input = image_to_tensor::<Backend>(&crop, self.config.input_size, &device).unsqueeze();
+        let embedding = l2_normalize(model.forward(input)).into_data_async().await;
+
+        Ok(embedding.iter::<f32>().collect())
+    }
+}
+
+impl FaceNet {
+    fn init(config: MobileFaceNetConfig, weights: Vec<u8>) -> Self {
+        console_error_panic_hook::set_once();
+        Self {
+            config,
+            weights: Some(weights),
+            model: None,
+        }
+    }
+}
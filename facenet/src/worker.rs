@@ -0,0 +1,88 @@
+use alloc::string::String;
+use core::cell::RefCell;
+
+use inference_metrics::now_ms;
+use inference_runtime::{DropPolicy, Frame, FrameQueue, Weights};
+use js_sys::Array;
+use wasm_bindgen::JsValue;
+
+#[cfg(target_family = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::web::FaceNet;
+
+/// FaceNetWorker structure that corresponds to JavaScript class, answering the face crops posted
+/// to a Web Worker (see `worker.js`) so that embedding does not block the main thread.
+///
+/// The crops are received with [receive](FaceNetWorker::receive) as soon as they arrive, and
+/// embedded one at a time with [process](FaceNetWorker::process). The crops received meanwhile
+/// are dropped by the [DropPolicy] when the model cannot keep up.
+///
+/// The output of the results is the `Float32Array` embedding, as returned by [FaceNet::embed].
+#[cfg_attr(target_family = "wasm", wasm_bindgen)]
+pub struct FaceNetWorker {
+    /// Taken while a crop is embedded.
+    facenet: RefCell<Option<FaceNet>>,
+    crops: RefCell<FrameQueue<Frame>>,
+}
+
+#[cfg_attr(target_family = "wasm", wasm_bindgen)]
+impl FaceNetWorker {
+    /// Constructor called by JavaScripts with the new keyword.
+    ///
+    /// # Arguments
+    ///
+    /// * `weights` - MobileFaceNet record written by `convert_weights`.
+    /// * `config` - JSON config written next to the record, if the architecture is not the
+    ///   default one.
+    /// * `policy` - What happens to the crops received during an embedding: `latest` (default)
+    ///   keeps the most recent one, `skip` drops them, and `queue` embeds all of them.
+    #[cfg_attr(target_family = "wasm", wasm_bindgen(constructor))]
+    pub fn new(
+        weights: Weights,
+        config: Option<String>,
+        policy: Option<String>,
+    ) -> Result<FaceNetWorker, String> {
+        let policy = policy
+            .map(|policy| policy.parse())
+            .transpose()?
+            .unwrap_or(DropPolicy::Latest);
+
+        Ok(Self {
+            facenet: RefCell::new(Some(FaceNet::with_weights(weights, config)?)),
+            crops: RefCell::new(FrameQueue::new(policy)),
+        })
+    }
+
+    /// Handles the data of a message received by the worker, see [FrameQueue::receive].
+    ///
+    /// # Returns
+    ///
+    /// The responses to post right away, for the crops dropped or cancelled by the message.
+    pub fn receive(&self, data: JsValue) -> Result<Array, String> {
+        self.crops.borrow_mut().receive(&data)
+    }
+
+    /// Embeds the next pending crop.
+    ///
+    /// # Returns
+    ///
+    /// The response to post, or `undefined` if no crop is pending or one is being embedded.
+    pub async fn process(&self) -> JsValue {
+        let Some(mut facenet) = self.facenet.take() else {
+            return JsValue::UNDEFINED;
+        };
+        let Some((id, crop)) = self.crops.borrow_mut().start() else {
+            self.facenet.replace(Some(facenet));
+            return JsValue::UNDEFINED;
+        };
+
+        let start = now_ms();
+        let output = facenet.embed(&crop.rgba(), crop.width, crop.height).await;
+        self.facenet.replace(Some(facenet));
+
+        self.crops
+            .borrow_mut()
+            .respond(id, crop, output, now_ms() - start)
+    }
+}
@@ -0,0 +1,558 @@
+use burn::{
+    backend::ndarray::NdArray,
+    config::Config,
+    module::{Module, ModuleMapper, ModuleVisitor, ParamId},
+    record::{BinBytesRecorder, FullPrecisionSettings, Recorder},
+    tensor::{Distribution, Tensor},
+};
+use facenet_burn::{
+    embedding::{image_to_tensor, l2_normalize},
+    mobilefacenet::{
+        ConvBlock, DepthWise, LinearBlock, MobileFaceNet, MobileFaceNetConfig, OutputHead,
+        Residual, GDC, GNAP, MIN_INPUT_SIZE,
+    },
+    web::FaceNet,
+};
+use image::{DynamicImage, RgbaImage};
+use inference_runtime::{record_bytes, sha256_hex, ModelLoader, Precision, Weights};
+
+type Backend = NdArray<f32>;
+
+fn random_input(shape: [usize; 4]) -> Tensor<Backend, 4> {
+    Tensor::random(shape, Distribution::Default, &Default::default())
+}
+
+/// A small network keeps the full-model tests fast on the ndarray backend.
+fn small_config() -> MobileFaceNetConfig {
+    MobileFaceNetConfig::new()
+        .with_embedding_size(128)
+        .with_width_multiplier(0.25)
+        .with_input_size(96)
+}
+
+/// Replaces the 1D parameters and running statistics, which the batch norms initialize to an
+/// identity, with random positive values, as after training.
+struct RandomStatistics;
+
+impl ModuleMapper<Backend> for RandomStatistics {
+    fn map_float<const D: usize>(
+        &mut self,
+        _id: &ParamId,
+        tensor: Tensor<Backend, D>,
+    ) -> Tensor<Backend, D> {
+        match D {
+            1 => Tensor::random(
+                tensor.shape(),
+                Distribution::Uniform(0.5, 1.5),
+                &tensor.device(),
+            ),
+            _ => tensor,
+        }
+    }
+}
+
+#[test]
+fn conv_block_output_shape() {
+    let device = Default::default();
+    let block = ConvBlock::<Backend>::new(3, 36, [3, 3], [1, 1], [0, 0], 1, &device);
+
+    let output = block.forward(random_input([2, 3, 112, 112]));
+
+    assert_eq!(output.dims(), [2, 36, 110, 110]);
+}
+
+#[test]
+fn linear_block_output_shape() {
+    let device = Default::default();
+    let block = LinearBlock::<Backend>::new(3, 36, [3, 3], [2, 2], [1, 1], 1, &device);
+
+    let output = block.forward(random_input([2, 3, 112, 112]));
+
+    assert_eq!(output.dims(), [2, 36, 56, 56]);
+}
+
+#[test]
+fn fused_blocks_match_unfused_blocks() {
+    let device = Default::default();
+    let conv = ConvBlock::<Backend>::new(8, 16, [3, 3], [2, 2], [1, 1], 1, &device)
+        .map(&mut RandomStatistics);
+    let depthwise = ConvBlock::<Backend>::new(8, 8, [3, 3], [1, 1], [1, 1], 8, &device)
+        .map(&mut RandomStatistics);
+    let linear = LinearBlock::<Backend>::new(8, 16, [1, 1], [1, 1], [0, 0], 1, &device)
+        .map(&mut RandomStatistics);
+    let input = random_input([2, 8, 14, 14]);
+
+    conv.clone()
+        .fuse()
+        .forward(input.clone())
+        .into_data()
+        .assert_approx_eq(&conv.forward(input.clone()).into_data(), 4);
+    depthwise
+        .clone()
+        .fuse()
+        .forward(input.clone())
+        .into_data()
+        .assert_approx_eq(&depthwise.forward(input.clone()).into_data(), 4);
+    linear
+        .clone()
+        .fuse()
+        .forward(input.clone())
+        .into_data()
+        .assert_approx_eq(&linear.forward(input).into_data(), 4);
+}
+
+#[test]
+fn depthwise_output_shape() {
+    let device = Default::default();
+    let block = DepthWise::<Backend>::new(16, 32, false, [3, 3], [2, 2], [1, 1], 64, &device);
+
+    let output = block.forward(random_input([2, 16, 28, 28]));
+
+    assert_eq!(output.dims(), [2, 32, 14, 14]);
+}
+
+#[test]
+fn residual_depthwise_adds_its_input() {
+    let device = Default::default();
+    let residual = DepthWise::<Backend>::new(8, 8, true, [3, 3], [1, 1], [1, 1], 16, &device);
+    // Same weights, without the shortcut connection
+    let plain = DepthWise::<Backend>::new(8, 8, false, [3, 3], [1, 1], [1, 1], 16, &device)
+        .load_record(residual.clone().into_record());
+    let input = random_input([2, 8, 14, 14]);
+
+    let output = residual.forward(input.clone()) - plain.forward(input.clone());
+
+    output.into_data().assert_approx_eq(&input.into_data(), 4);
+}
+
+#[test]
+fn residual_preserves_shape() {
+    let device = Default::default();
+    let block = Residual::<Backend>::new(8, 3, 16, [3, 3], [1, 1], [1, 1], &device);
+
+    let output = block.forward(random_input([2, 8, 14, 14]));
+
+    assert_eq!(output.dims(), [2, 8, 14, 14]);
+}
+
+#[test]
+fn empty_residual_is_identity() {
+    let device = Default::default();
+    let block = Residual::<Backend>::new(8, 0, 16, [3, 3], [1, 1], [1, 1], &device);
+    let input = random_input([2, 8, 14, 14]);
+
+    let output = block.forward(input.clone());
+
+    output.into_data().assert_eq(&input.into_data(), true);
+}
+
+#[test]
+fn gnap_output_size() {
+    let device = Default::default();
+    let head = GNAP::<Backend>::new(128, &device);
+
+    let output = head.forward(random_input([2, 128, 7, 7]));
+
+    assert_eq!(output.dims(), [2, 128]);
+}
+
+#[test]
+fn gdc_output_size() {
+    let device = Default::default();
+    let head = GDC::<Backend>::new(512, 256, 7, &device);
+
+    let output = head.forward(random_input([2, 512, 7, 7]));
+
+    assert_eq!(output.dims(), [2, 256]);
+}
+
+#[test]
+fn gdc_output_size_for_96x96_inputs() {
+    let device = Default::default();
+    let head = GDC::<Backend>::new(512, 128, 6, &device);
+
+    let output = head.forward(random_input([2, 512, 6, 6]));
+
+    assert_eq!(output.dims(), [2, 128]);
+}
+
+#[test]
+fn mobilefacenet_default_output_size() {
+    let device = Default::default();
+    let model: MobileFaceNet<Backend> = MobileFaceNetConfig::new().init(&device);
+
+    let output = model.forward(random_input([2, 3, 112, 112]));
+
+    assert_eq!(output.dims(), [2, 512]);
+}
+
+#[test]
+fn mobilefacenet_output_size_per_head() {
+    let device = Default::default();
+
+    for head in [OutputHead::Gnap, OutputHead::Gdc] {
+        let model: MobileFaceNet<Backend> = small_config().with_output_head(head).init(&device);
+
+        let output = model.forward(random_input([2, 3, 96, 96]));
+
+        assert_eq!(output.dims(), [2, 128]);
+    }
+}
+
+#[test]
+fn mobilefacenet_smallest_input_size() {
+    let device = Default::default();
+    let config = small_config().with_input_size(MIN_INPUT_SIZE);
+    let model: MobileFaceNet<Backend> = config.init(&device);
+
+    let output = model.forward(random_input([1, 3, MIN_INPUT_SIZE, MIN_INPUT_SIZE]));
+
+    assert_eq!(output.dims(), [1, 128]);
+}
+
+#[test]
+#[should_panic(expected = "invalid input size 0")]
+fn mobilefacenet_rejects_empty_inputs() {
+    let _: MobileFaceNet<Backend> = small_config().with_input_size(0).init(&Default::default());
+}
+
+#[test]
+fn invalid_configs_are_rejected() {
+    assert_eq!(MobileFaceNetConfig::new().validate(), Ok(()));
+    assert_eq!(
+        small_config().with_input_size(MIN_INPUT_SIZE).validate(),
+        Ok(())
+    );
+
+    let errors = [
+        small_config().with_input_size(MIN_INPUT_SIZE - 1),
+        small_config().with_embedding_size(0),
+        small_config().with_width_multiplier(0.),
+        small_config().with_width_multiplier(f64::NAN),
+    ]
+    .map(|config| config.validate().unwrap_err());
+
+    assert!(errors[0].contains("at least 16, got 15"), "{}", errors[0]);
+    assert!(errors[1].contains("embedding size"), "{}", errors[1]);
+    assert!(errors[2].contains("got 0"), "{}", errors[2]);
+    assert!(errors[3].contains("got NaN"), "{}", errors[3]);
+}
+
+#[test]
+fn mobilefacenet_is_deterministic() {
+    let device = Default::default();
+    let model: MobileFaceNet<Backend> = small_config().init(&device);
+    let input = random_input([2, 3, 96, 96]);
+
+    let first = model.forward(input.clone());
+    let second = model.forward(input);
+
+    first.into_data().assert_eq(&second.into_data(), true);
+}
+
+#[test]
+fn fused_mobilefacenet_matches_unfused_model() {
+    let device = Default::default();
+    let input = random_input([2, 3, 96, 96]);
+
+    for head in [OutputHead::Gnap, OutputHead::Gdc] {
+        let model: MobileFaceNet<Backend> = small_config()
+            .with_output_head(head)
+            .init(&device)
+            .map(&mut RandomStatistics);
+
+        let expected = model.forward(input.clone());
+        let output = model.fuse().forward(input.clone());
+
+        output
+            .into_data()
+            .assert_approx_eq(&expected.into_data(), 3);
+    }
+}
+
+#[test]
+#[should_panic(expected = "different variant")]
+fn fused_records_do_not_load_into_unfused_models() {
+    let device = Default::default();
+    let fused = small_config().init::<Backend>(&device).fuse();
+
+    let _ = small_config()
+        .init::<Backend>(&device)
+        .load_record(fused.into_record());
+}
+
+#[test]
+fn mobilefacenet_record_round_trip() {
+    let device = Default::default();
+    let config = small_config();
+    let model: MobileFaceNet<Backend> = config.init(&device);
+    let recorder = BinBytesRecorder::<FullPrecisionSettings>::default();
+
+    let bytes = recorder.record(model.clone().into_record(), ()).unwrap();
+    let record = recorder.load(bytes, &device).unwrap();
+    let loaded: MobileFaceNet<Backend> = config.init(&device).load_record(record);
+
+    let input = random_input([2, 3, 96, 96]);
+    model
+        .forward(input.clone())
+        .into_data()
+        .assert_eq(&loaded.forward(input).into_data(), true);
+}
+
+#[test]
+fn reduced_precision_weights_keep_the_embeddings() {
+    let device = Default::default();
+    let config = small_config();
+    let model: MobileFaceNet<Backend> = config.init(&device).map(&mut RandomStatistics);
+    // Normalized pixels are in [-1, 1]
+    let input = random_input([4, 3, 96, 96]) * 2 - 1;
+    let expected = l2_normalize(model.forward(input.clone()));
+
+    for (precision, min_similarity) in [(Precision::Half, 0.999), (Precision::Int8, 0.99)] {
+        let bytes = record_bytes(model.clone(), precision).unwrap();
+        let loaded = MobileFaceNet::<Backend>::from_bytes(&config, bytes, &device).unwrap();
+
+        let output = l2_normalize(loaded.forward(input.clone()));
+        let similarity = (output * expected.clone()).sum_dim(1).min().into_scalar();
+        assert!(
+            similarity >= min_similarity,
+            "Cosine similarity of {similarity} with {precision} weights"
+        );
+    }
+}
+
+#[test]
+fn mobilefacenet_config_round_trip() {
+    let config = small_config().with_output_head(OutputHead::Gnap);
+
+    let loaded = MobileFaceNetConfig::load_binary(config.to_string().as_bytes()).unwrap();
+
+    assert_eq!(loaded.embedding_size, 128);
+    assert_eq!(loaded.output_head, OutputHead::Gnap);
+    assert_eq!(loaded.width_multiplier, 0.25);
+    assert_eq!(loaded.input_size, 96);
+}
+
+#[test]
+fn web_model_embeds_with_streamed_weights() {
+    let device = Default::default();
+    let config = small_config();
+    let model: MobileFaceNet<Backend> = config.init(&device).map(&mut RandomStatistics);
+    let bytes = record_bytes(model.clone(), Precision::Full).unwrap();
+    let checksum = sha256_hex(&bytes);
+
+    let mut weights = Weights::new(Some(bytes.len()), Some(checksum)).unwrap();
+    for chunk in bytes.chunks(4096) {
+        weights.push(chunk).unwrap();
+    }
+    let mut facenet = FaceNet::with_weights(weights, Some(config.to_string())).unwrap();
+
+    // A crop of another size than the input of the model
+    let pixels = (0..64 * 48 * 4)
+        .map(|i| (i * 7 % 256) as u8)
+        .collect::<Vec<_>>();
+    let embedding = block_on(facenet.embed(&pixels, 64, 48)).unwrap();
+
+    let crop = DynamicImage::ImageRgba8(RgbaImage::from_raw(64, 48, pixels.clone()).unwrap());
+    let input = image_to_tensor::<Backend>(&crop, config.input_size, &device).unsqueeze();
+    let expected = l2_normalize(model.forward(input));
+    Tensor::<Backend, 1>::from_floats(embedding.as_slice(), &device)
+        .into_data()
+        .assert_approx_eq(&expected.reshape([-1]).into_data(), 4);
+
+    let err = block_on(facenet.embed(&pixels, 64, 64)).unwrap_err();
+    assert!(err.contains("do not match"), "{err}");
+
+    // Invalid configs are errors rather than panics of the wasm module
+    let config = config.with_input_size(8).to_string();
+    let weights = Weights::from_bytes(bytes, None).unwrap();
+    let err = FaceNet::with_weights(weights, Some(config)).err().unwrap();
+    assert!(err.contains("Invalid config"), "{err}");
+}
+
+/// Collects the first value of every float parameter, in visiting order.
+struct FirstValues(Vec<f32>);
+
+impl ModuleVisitor<Backend> for FirstValues {
+    fn visit_float<const D: usize>(&mut self, _id: &ParamId, tensor: &Tensor<Backend, D>) {
+        self.0.push(tensor.to_data().iter::<f32>().next().unwrap());
+    }
+}
+
+/// Names and shapes of a `state_dict` of the reference implementation, in module order.
+fn reference_state_dict(config: &MobileFaceNetConfig) -> Vec<(String, Vec<usize>)> {
+    fn conv_block(
+        tensors: &mut Vec<(String, Vec<usize>)>,
+        name: &str,
+        [in_c, out_c, kernel, groups]: [usize; 4],
+        prelu: bool,
+    ) {
+        tensors.push((
+            format!("{name}.conv.weight"),
+            vec![out_c, in_c / groups, kernel, kernel],
+        ));
+        for stat in ["weight", "bias", "running_mean", "running_var"] {
+            tensors.push((format!("{name}.bn.{stat}"), vec![out_c]));
+        }
+        if prelu {
+            tensors.push((format!("{name}.prelu.weight"), vec![out_c]));
+        }
+    }
+    fn depthwise(
+        tensors: &mut Vec<(String, Vec<usize>)>,
+        name: &str,
+        [in_c, out_c, groups]: [usize; 3],
+    ) {
+        conv_block(tensors, &format!("{name}.conv"), [in_c, groups, 1, 1], true);
+        conv_block(
+            tensors,
+            &format!("{name}.conv_dw"),
+            [groups, groups, 3, groups],
+            true,
+        );
+        conv_block(
+            tensors,
+            &format!("{name}.project"),
+            [groups, out_c, 1, 1],
+            false,
+        );
+    }
+
+    let width = |c: f64| (c * config.width_multiplier) as usize;
+    let (c64, c128, c256, c512) = (width(64.), width(128.), width(256.), width(512.));
+    let kernel = (0..4).fold(config.input_size, |size, _| (size - 1) / 2 + 1);
+
+    let mut tensors = Vec::new();
+    conv_block(&mut tensors, "module.conv1", [3, c64, 3, 1], true);
+    conv_block(&mut tensors, "module.conv2_dw", [c64, c64, 3, c64], true);
+    depthwise(&mut tensors, "module.conv_23", [c64, c64, c128]);
+    for i in 0..4 {
+        depthwise(
+            &mut tensors,
+            &format!("module.conv_3.model.{i}"),
+            [c64, c64, c128],
+        );
+    }
+    depthwise(&mut tensors, "module.conv_34", [c64, c128, c256]);
+    for i in 0..6 {
+        depthwise(
+            &mut tensors,
+            &format!("module.conv_4.model.{i}"),
+            [c128, c128, c256],
+        );
+    }
+    depthwise(&mut tensors, "module.conv_45", [c128, c128, c512]);
+    for i in 0..2 {
+        depthwise(
+            &mut tensors,
+            &format!("module.conv_5.model.{i}"),
+            [c128, c128, c256],
+        );
+    }
+    conv_block(&mut tensors, "module.conv_6_sep", [c128, c512, 1, 1], true);
+    conv_block(
+        &mut tensors,
+        "module.output_layer.conv_6_dw",
+        [c512, c512, kernel, c512],
+        false,
+    );
+    tensors.push((
+        "module.output_layer.linear.weight".into(),
+        vec![config.embedding_size, c512],
+    ));
+    for stat in ["weight", "bias", "running_mean", "running_var"] {
+        tensors.push((
+            format!("module.output_layer.bn.{stat}"),
+            vec![config.embedding_size],
+        ));
+    }
+    tensors
+}
+
+/// Writes the tensors as a `torch.save` zip archive, filling the tensor `i` with `i + 1`.
+fn write_pytorch_checkpoint(path: &std::path::Path, tensors: &[(String, Vec<usize>)]) {
+    use std::io::Write;
+    use zip::{write::SimpleFileOptions, CompressionMethod, ZipWriter};
+
+    fn unicode(pickle: &mut Vec<u8>, value: &str) {
+        pickle.push(b'X');
+        pickle.extend((value.len() as u32).to_le_bytes());
+        pickle.extend(value.as_bytes());
+    }
+    fn int(pickle: &mut Vec<u8>, value: usize) {
+        pickle.push(b'J');
+        pickle.extend((value as i32).to_le_bytes());
+    }
+    fn tuple(pickle: &mut Vec<u8>, values: impl IntoIterator<Item = usize>) {
+        pickle.push(b'(');
+        values.into_iter().for_each(|value| int(pickle, value));
+        pickle.push(b't');
+    }
+
+    // An ordered dict of `torch._utils._rebuild_tensor_v2` calls on persistent storages.
+    let mut pickle = vec![0x80, 2, b'}', b'('];
+    for (index, (name, shape)) in tensors.iter().enumerate() {
+        let numel = shape.iter().product();
+        let strides = (0..shape.len()).map(|dim| shape[dim + 1..].iter().product());
+        unicode(&mut pickle, name);
+        pickle.extend(b"ctorch._utils\n_rebuild_tensor_v2\n(");
+        pickle.push(b'(');
+        unicode(&mut pickle, "storage");
+        pickle.extend(b"ctorch\nFloatStorage\n");
+        unicode(&mut pickle, &index.to_string());
+        unicode(&mut pickle, "cpu");
+        int(&mut pickle, numel);
+        pickle.extend(b"tQ");
+        int(&mut pickle, 0);
+        tuple(&mut pickle, shape.iter().copied());
+        tuple(&mut pickle, strides);
+        pickle.extend([0x89, b't', b'R']);
+    }
+    pickle.extend(b"u.");
+
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+    let mut zip = ZipWriter::new(std::fs::File::create(path).unwrap());
+    zip.start_file("archive/data.pkl", options).unwrap();
+    zip.write_all(&pickle).unwrap();
+    for (index, (_, shape)) in tensors.iter().enumerate() {
+        let value = (index + 1) as f32;
+        let numel = shape.iter().product::<usize>();
+        zip.start_file(format!("archive/data/{index}"), options)
+            .unwrap();
+        zip.write_all(&value.to_le_bytes().repeat(numel)).unwrap();
+    }
+    zip.start_file("archive/version", options).unwrap();
+    zip.write_all(b"3\n").unwrap();
+    zip.finish().unwrap();
+}
+
+#[test]
+fn pytorch_state_dict_keys_are_remapped() {
+    let config = small_config();
+    let tensors = reference_state_dict(&config);
+    let path = std::env::temp_dir().join(format!("mobilefacenet-{}.pth", std::process::id()));
+    write_pytorch_checkpoint(&path, &tensors);
+
+    let model = MobileFaceNet::<Backend>::from_pytorch(&config, path.clone(), &Default::default());
+    std::fs::remove_file(&path).unwrap();
+
+    // Every tensor of the state dict lands on the parameter of the same position in the model.
+    let mut values = FirstValues(Vec::new());
+    model.unwrap().visit(&mut values);
+    let expected = (1..=tensors.len())
+        .map(|value| value as f32)
+        .collect::<Vec<_>>();
+    assert_eq!(values.0, expected);
+}
+
+/// Polls a future which completes without waiting, as the ndarray backend does not.
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    use std::task::{Context, Poll, Waker};
+
+    let mut future = std::pin::pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+    match future.as_mut().poll(&mut context) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("The future is not ready"),
+    }
+}
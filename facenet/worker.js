@@ -0,0 +1,53 @@
+/**
+ * Web Worker computing MobileFaceNet embeddings off the main thread, e.g.
+ * `new Worker("worker.js", { type: "module" })`.
+ *
+ * Messages of the main thread:
+ * - `{type: "init", weights, checksum, config, policy}` - Loads the `weights` ArrayBuffer, whose
+ *   SHA-256 `checksum` and JSON `config` are optional, and answers `{type: "ready"}`. The drop
+ *   `policy` is `latest`, `skip` or `queue`.
+ * - `{id, type: "frame", pixels, width, height}` - Embeds an aligned face crop, whose RGBA `pixels`
+ *   ArrayBuffer should be transferred.
+ * - `{id, type: "cancel"}` - Cancels a crop.
+ *
+ * Each crop is answered once, see `FaceNetWorker`, with its pixels transferred back.
+ */
+import { default as wasm, FaceNetWorker, Weights } from "./pkg/facenet_burn.js";
+
+let worker;
+let draining = false;
+
+function post(response) {
+    const transfer = [response.pixels, response.output?.buffer].filter((buffer) => buffer);
+    self.postMessage(response, transfer);
+}
+
+async function drain() {
+    for (let response = await worker.process(); response !== undefined; response = await worker.process()) {
+        post(response);
+        // Let the crops received meanwhile reach the queue, so that the drop policy applies
+        await new Promise((resolve) => setTimeout(resolve, 0));
+    }
+    draining = false;
+}
+
+self.onmessage = async (event) => {
+    try {
+        if (event.data.type === "init") {
+            const { weights, checksum, config, policy } = event.data;
+            await wasm();
+            worker = new FaceNetWorker(Weights.from_bytes(new Uint8Array(weights), checksum), config, policy);
+            self.postMessage({ type: "ready" });
+            return;
+        }
+        worker.receive(event.data).forEach(post);
+    } catch (error) {
+        self.postMessage({ id: event.data.id, type: "error", message: String(error) });
+        return;
+    }
+
+    if (!draining) {
+        draining = true;
+        setTimeout(drain, 0);
+    }
+};
@@ -3,10 +3,13 @@ import "./App.css";
 import { FabricCanvas } from "./components/FabricCanvas";
 import { ScaledCanvas } from "./components/ScaledCanvas";
 import { ProbabilityChart } from "./components/ProbabilityChart";
+import { WebcamDetector } from "./components/WebcamDetector";
+import { FaceRecognition } from "./components/FaceRecognition";
 
 function App() {
   const [inferenceData, setInferenceData] = useState(null);
   const [clearCanvas, setClearCanvas] = useState(false);
+  const [page, setPage] = useState("mnist");
 
   const handleClear = () => {
     setClearCanvas(true);
@@ -15,42 +18,67 @@ function App() {
 
   return (
     <div className="App">
-      <h1>Burn MNIST Inference Demo</h1>
-      <table>
-        <thead>
-          <tr>
-            <th>Draw a digit here</th>
-            <th>Cropped and scaled</th>
-            <th>Probability result</th>
-          </tr>
-        </thead>
-        <tbody>
-          <tr>
-            <td>
-              <FabricCanvas
-                onInference={setInferenceData}
-                clearCanvas={clearCanvas}
-                setClearCanvas={setClearCanvas}
-              />
-            </td>
-            <td>
-              <ScaledCanvas inferenceData={inferenceData} />
-            </td>
-            <td>
-              <ProbabilityChart data={inferenceData} />
-            </td>
-          </tr>
-          <tr>
-            <td>
-              <button id="clear" onClick={handleClear}>
-                Clear
-              </button>
-            </td>
-            <td></td>
-            <td></td>
-          </tr>
-        </tbody>
-      </table>
+      <nav>
+        <button onClick={() => setPage("mnist")} disabled={page === "mnist"}>
+          MNIST
+        </button>
+        <button onClick={() => setPage("webcam")} disabled={page === "webcam"}>
+          Webcam detection
+        </button>
+        <button onClick={() => setPage("faces")} disabled={page === "faces"}>
+          Face recognition
+        </button>
+      </nav>
+      {page === "webcam" ? (
+        <>
+          <h1>Burn YOLOX Webcam Detection Demo</h1>
+          <WebcamDetector />
+        </>
+      ) : page === "faces" ? (
+        <>
+          <h1>Burn MobileFaceNet Face Recognition Demo</h1>
+          <FaceRecognition />
+        </>
+      ) : (
+        <>
+          <h1>Burn MNIST Inference Demo</h1>
+          <table>
+            <thead>
+              <tr>
+                <th>Draw a digit here</th>
+                <th>Cropped and scaled</th>
+                <th>Probability result</th>
+              </tr>
+            </thead>
+            <tbody>
+              <tr>
+                <td>
+                  <FabricCanvas
+                    onInference={setInferenceData}
+                    clearCanvas={clearCanvas}
+                    setClearCanvas={setClearCanvas}
+                  />
+                </td>
+                <td>
+                  <ScaledCanvas inferenceData={inferenceData} />
+                </td>
+                <td>
+                  <ProbabilityChart data={inferenceData} />
+                </td>
+              </tr>
+              <tr>
+                <td>
+                  <button id="clear" onClick={handleClear}>
+                    Clear
+                  </button>
+                </td>
+                <td></td>
+                <td></td>
+              </tr>
+            </tbody>
+          </table>
+        </>
+      )}
     </div>
   );
 }
@@ -1,6 +1,5 @@
 import React, { useRef, useEffect } from "react";
 import { Canvas as FabricCanvas } from "fabric";
-import { cropScaleGetImageData } from "./utils";
 
 const DrawingCanvas = React.forwardRef(({ onDraw }, ref) => {
   const fabricCanvasRef = useRef(null);
@@ -1,13 +1,10 @@
 import React, { useEffect, useRef } from "react";
 import { fabric } from "fabric";
-import { cropScaleGetImageData } from "./utils";
 import { default as wasm, Mnist } from "../pkg/mnist_inference_web.js";
 
 export const FabricCanvas = ({ onInference, clearCanvas, setClearCanvas }) => {
   const canvasRef = useRef(null);
   const mainContextRef = useRef(null);
-  const cropCanvasRef = useRef(null);
-  const scaledCanvasRef = useRef(null);
   const mnistRef = useRef(null);
   let timeoutId = useRef(null);
   let isDrawing = useRef(false);
@@ -25,22 +22,6 @@ export const FabricCanvas = ({ onInference, clearCanvas, setClearCanvas }) => {
     const mainContext = canvasEl.getContext("2d", { willReadFrequently: true });
     mainContextRef.current = mainContext;
 
-    const cropCanvasEl = document.createElement("canvas");
-    cropCanvasEl.width = 28;
-    cropCanvasEl.height = 28;
-    const cropContext = cropCanvasEl.getContext("2d", {
-      willReadFrequently: true,
-    });
-    cropCanvasRef.current = cropContext;
-
-    const scaledCanvasEl = document.createElement("canvas");
-    scaledCanvasEl.width = 28;
-    scaledCanvasEl.height = 28;
-    const scaledContext = scaledCanvasEl.getContext("2d", {
-      willReadFrequently: true,
-    });
-    scaledCanvasRef.current = scaledContext;
-
     const fabricCanvas = new fabric.Canvas(canvasEl, {
       isDrawingMode: true,
     });
@@ -54,12 +35,14 @@ export const FabricCanvas = ({ onInference, clearCanvas, setClearCanvas }) => {
       timeoutId.current = setTimeout(async () => {
         isTimeOutSet.current = true;
         fabricCanvas.freeDrawingBrush._finalizeAndAddPath();
-        const data = cropScaleGetImageData(
-          mainContextRef.current,
-          cropCanvasRef.current,
-          scaledCanvasRef.current
+        // The drawing is cropped, scaled and normalized by the model
+        const { data, width, height } = mainContextRef.current.getImageData(
+          0,
+          0,
+          canvasEl.width,
+          canvasEl.height
         );
-        const output = await mnistRef.current.inference(data);
+        const output = await mnistRef.current.inference(data, width, height);
         onInference(output);
         isTimeOutSet.current = false;
       }, 50);
@@ -0,0 +1,320 @@
+import React, { useCallback, useEffect, useRef, useState } from "react";
+import { loadDetector } from "./detection";
+import { drawFaces, faceRegion, loadFaceNet, thumbnail } from "./faces";
+import {
+  addFaces,
+  clearFaces,
+  deleteFace,
+  exportGallery,
+  identify,
+  listFaces,
+  openGallery,
+  parseGallery,
+} from "./gallery";
+import { useWebcam } from "./useWebcam";
+
+// COCO class of the people, whose heads are cropped unless the boxes are faces
+const PERSON = 0;
+// Faces embedded per frame, the largest first
+const MAX_FACES = 4;
+
+/**
+ * Recognizes the faces of the webcam video against a gallery stored in IndexedDB, to which faces
+ * are enrolled from the video. Faces are found by the yolo detector and embedded by the facenet
+ * wasm module.
+ */
+export const FaceRecognition = () => {
+  const videoRef = useRef(null);
+  const canvasRef = useRef(null);
+  const dbRef = useRef(null);
+  // Read by the recognition loop
+  const facesRef = useRef([]);
+  const settingsRef = useRef(null);
+  const enrollRef = useRef(undefined);
+  const [faces, setFaces] = useState([]);
+  const [name, setName] = useState("");
+  const [enrolling, setEnrolling] = useState(false);
+  const [threshold, setThreshold] = useState(0.5);
+  const [boxesAreFaces, setBoxesAreFaces] = useState(false);
+  const [ready, setReady] = useState(false);
+  const [status, setStatus] = useState("");
+  const [stats, setStats] = useState(null);
+
+  facesRef.current = faces;
+  settingsRef.current = { threshold, boxesAreFaces };
+
+  useWebcam(videoRef, setStatus);
+
+  const refreshGallery = useCallback(async () => {
+    setFaces(await listFaces(dbRef.current));
+  }, []);
+
+  useEffect(() => {
+    openGallery()
+      .then((db) => {
+        dbRef.current = db;
+        return refreshGallery();
+      })
+      .catch((error) => setStatus(`Failed to open the gallery: ${error}`));
+  }, [refreshGallery]);
+
+  useEffect(() => {
+    let models;
+    let stopped = false;
+    let busy = false;
+    let frameRequest;
+    let latency;
+    let lastStats = 0;
+    const frameTimes = [];
+
+    const captureCanvas = document.createElement("canvas");
+    const captureContext = captureCanvas.getContext("2d", {
+      willReadFrequently: true,
+    });
+
+    const free = () => {
+      models.detector.free();
+      models.facenet.free();
+      models = undefined;
+    };
+
+    const enroll = async (face) => {
+      const name = enrollRef.current;
+      enrollRef.current = undefined;
+      await addFaces(dbRef.current, [
+        {
+          name,
+          embedding: face.embedding,
+          thumbnail: thumbnail(captureCanvas, face.region),
+        },
+      ]);
+      await refreshGallery();
+      setEnrolling(false);
+    };
+
+    const processFrame = async (video, width, height) => {
+      if (captureCanvas.width !== width || captureCanvas.height !== height) {
+        captureCanvas.width = width;
+        captureCanvas.height = height;
+      }
+      captureContext.drawImage(video, 0, 0, width, height);
+      const { data } = captureContext.getImageData(0, 0, width, height);
+      const { threshold, boxesAreFaces } = settingsRef.current;
+
+      const start = performance.now();
+      const boxes = await models.detector.detect(data, width, height);
+      const faces = boxes
+        .filter((box) => boxesAreFaces || box[0] === PERSON)
+        .map((box) => ({ region: faceRegion(box, boxesAreFaces, width, height) }))
+        .filter(({ region }) => region)
+        .sort((a, b) => b.region.width * b.region.height - a.region.width * a.region.height)
+        .slice(0, MAX_FACES);
+      for (const face of faces) {
+        const { x, y, width, height } = face.region;
+        const crop = captureContext.getImageData(x, y, width, height);
+        face.embedding = await models.facenet.embed(crop.data, width, height);
+        face.identity = identify(facesRef.current, face.embedding, threshold);
+      }
+      const end = performance.now();
+
+      // The largest face is enrolled
+      if (enrollRef.current !== undefined && faces.length > 0) {
+        await enroll(faces[0]);
+      }
+
+      const canvas = canvasRef.current;
+      if (canvas.width !== width || canvas.height !== height) {
+        canvas.width = width;
+        canvas.height = height;
+      }
+      const context = canvas.getContext("2d");
+      context.drawImage(captureCanvas, 0, 0);
+      drawFaces(context, faces);
+
+      frameTimes.push(end);
+      while (frameTimes[0] <= end - 1000) {
+        frameTimes.shift();
+      }
+      latency = latency === undefined ? end - start : 0.9 * latency + 0.1 * (end - start);
+      if (end - lastStats >= 500) {
+        setStats({ fps: frameTimes.length, latency, faces: faces.length });
+        lastStats = end;
+      }
+    };
+
+    const loop = async () => {
+      busy = true;
+      const video = videoRef.current;
+      try {
+        if (video.readyState >= video.HAVE_CURRENT_DATA && video.videoWidth > 0) {
+          await processFrame(video, video.videoWidth, video.videoHeight);
+        }
+      } catch (error) {
+        setStatus(`Recognition failed: ${error}`);
+        stopped = true;
+      }
+      busy = false;
+
+      if (!stopped) {
+        frameRequest = requestAnimationFrame(loop);
+      } else if (models) {
+        free();
+      }
+    };
+
+    const onProgress = (model) => (loaded, total) => {
+      const size = total ? ` / ${(total / 1e6).toFixed(1)}` : "";
+      setStatus(`Downloading the ${model} weights: ${(loaded / 1e6).toFixed(1)}${size} MB`);
+    };
+
+    setStatus("Loading the models...");
+    Promise.all([
+      loadDetector("ndarray", onProgress("detector")),
+      loadFaceNet(onProgress("face embedding")),
+    ])
+      .then(([{ detector }, facenet]) => {
+        models = { detector, facenet };
+        if (stopped) {
+          free();
+          return;
+        }
+        setStatus("Running");
+        setReady(true);
+        frameRequest = requestAnimationFrame(loop);
+      })
+      .catch((error) => {
+        if (!stopped) {
+          setStatus(`Failed to load the models: ${error}`);
+        }
+      });
+
+    return () => {
+      stopped = true;
+      cancelAnimationFrame(frameRequest);
+      // A running recognition frees the models once it completes
+      if (models && !busy) {
+        free();
+      }
+    };
+  }, [refreshGallery]);
+
+  const startEnrollment = (event) => {
+    event.preventDefault();
+    enrollRef.current = name.trim();
+    setEnrolling(true);
+    setName("");
+  };
+
+  const removeFace = async (id) => {
+    await deleteFace(dbRef.current, id);
+    await refreshGallery();
+  };
+
+  const clearGallery = async () => {
+    if (window.confirm("Remove all the faces of the gallery?")) {
+      await clearFaces(dbRef.current);
+      await refreshGallery();
+    }
+  };
+
+  const downloadGallery = () => {
+    const url = URL.createObjectURL(exportGallery(faces));
+    const link = document.createElement("a");
+    link.href = url;
+    link.download = "face-gallery.json";
+    link.click();
+    URL.revokeObjectURL(url);
+  };
+
+  const importGallery = async (event) => {
+    const file = event.target.files[0];
+    event.target.value = "";
+    if (!file) {
+      return;
+    }
+    try {
+      const imported = parseGallery(await file.text(), faces[0]?.embedding.length);
+      await addFaces(dbRef.current, imported);
+      await refreshGallery();
+      setStatus(`Imported ${imported.length} faces`);
+    } catch (error) {
+      setStatus(`Failed to import ${file.name}: ${error.message}`);
+    }
+  };
+
+  return (
+    <div>
+      <form className="controls" onSubmit={startEnrollment}>
+        <label>
+          Name{" "}
+          <input
+            value={name}
+            onChange={(e) => setName(e.target.value)}
+            placeholder="Name of the face"
+          />
+        </label>
+        <button type="submit" disabled={!ready || enrolling || !name.trim()}>
+          {enrolling ? "Look at the camera..." : "Enroll"}
+        </button>
+        <label>
+          Similarity threshold{" "}
+          <input
+            type="range"
+            min="0.1"
+            max="0.9"
+            step="0.05"
+            value={threshold}
+            onChange={(e) => setThreshold(Number(e.target.value))}
+          />{" "}
+          {threshold.toFixed(2)}
+        </label>
+        <label>
+          <input
+            type="checkbox"
+            checked={boxesAreFaces}
+            onChange={(e) => setBoxesAreFaces(e.target.checked)}
+          />{" "}
+          Boxes are faces
+        </label>
+      </form>
+      <p>
+        {status}
+        {stats &&
+          ` | FPS: ${stats.fps} | Latency: ${stats.latency.toFixed(1)} ms | Faces: ${stats.faces}`}
+      </p>
+      <video ref={videoRef} playsInline muted style={{ display: "none" }} />
+      <canvas
+        id="faces-canvas"
+        ref={canvasRef}
+        width="640"
+        height="480"
+        style={{ border: "1px solid #aaa" }}
+      />
+      <h2>Gallery ({faces.length} faces)</h2>
+      <div className="controls">
+        <button onClick={downloadGallery} disabled={faces.length === 0}>
+          Export
+        </button>
+        <label>
+          Import <input type="file" accept="application/json" onChange={importGallery} />
+        </label>
+        <button onClick={clearGallery} disabled={faces.length === 0}>
+          Clear
+        </button>
+      </div>
+      <div className="gallery">
+        {faces.map((face) => (
+          <figure key={face.id}>
+            <img src={face.thumbnail} alt={face.name} width="64" height="64" />
+            <figcaption>
+              {face.name}{" "}
+              <button onClick={() => removeFace(face.id)} title="Remove">
+                ×
+              </button>
+            </figcaption>
+          </figure>
+        ))}
+      </div>
+    </div>
+  );
+};
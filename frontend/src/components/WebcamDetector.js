@@ -0,0 +1,211 @@
+import React, { useEffect, useRef, useState } from "react";
+import {
+  BACKENDS,
+  drawBoxes,
+  isBackendSupported,
+  loadDetector,
+} from "./detection";
+import { useWebcam } from "./useWebcam";
+
+const INPUT_SIZES = [320, 416, 640];
+
+/**
+ * Detects objects in the webcam video with the yolo wasm detector, one frame at a time, and shows
+ * the boxes over the frames with the frame rate and the detection latency.
+ */
+export const WebcamDetector = () => {
+  const videoRef = useRef(null);
+  const canvasRef = useRef(null);
+  // Read by the detection loop, which applies the options between two frames
+  const optionsRef = useRef(null);
+  const [backend, setBackend] = useState("ndarray");
+  const [options, setOptions] = useState({
+    inputSize: 416,
+    scoreThreshold: 0.5,
+    iouThreshold: 0.65,
+  });
+  const [status, setStatus] = useState("");
+  const [stats, setStats] = useState(null);
+
+  optionsRef.current = options;
+
+  useWebcam(videoRef, setStatus);
+
+  // The detector is reloaded when the backend changes, as each backend is a wasm package
+  useEffect(() => {
+    let session;
+    let stopped = false;
+    let busy = false;
+    let frameRequest;
+    let appliedOptions;
+    let latency;
+    let lastStats = 0;
+    const frameTimes = [];
+
+    const captureCanvas = document.createElement("canvas");
+    const captureContext = captureCanvas.getContext("2d", {
+      willReadFrequently: true,
+    });
+
+    const detectFrame = async (video, width, height) => {
+      if (optionsRef.current !== appliedOptions) {
+        const { inputSize, scoreThreshold, iouThreshold } = optionsRef.current;
+        session.detector.set_options(inputSize, scoreThreshold, iouThreshold);
+        appliedOptions = optionsRef.current;
+      }
+
+      if (captureCanvas.width !== width || captureCanvas.height !== height) {
+        captureCanvas.width = width;
+        captureCanvas.height = height;
+      }
+      captureContext.drawImage(video, 0, 0, width, height);
+      const { data } = captureContext.getImageData(0, 0, width, height);
+
+      const start = performance.now();
+      const boxes = await session.detector.detect(data, width, height);
+      const end = performance.now();
+
+      const canvas = canvasRef.current;
+      if (canvas.width !== width || canvas.height !== height) {
+        canvas.width = width;
+        canvas.height = height;
+      }
+      const context = canvas.getContext("2d");
+      context.drawImage(captureCanvas, 0, 0);
+      drawBoxes(context, boxes, session.classes);
+
+      // Frame rate over the last second, and smoothed latency
+      frameTimes.push(end);
+      while (frameTimes[0] <= end - 1000) {
+        frameTimes.shift();
+      }
+      latency = latency === undefined ? end - start : 0.9 * latency + 0.1 * (end - start);
+      if (end - lastStats >= 500) {
+        setStats({ fps: frameTimes.length, latency, boxes: boxes.length });
+        lastStats = end;
+      }
+    };
+
+    const loop = async () => {
+      busy = true;
+      const video = videoRef.current;
+      try {
+        if (video.readyState >= video.HAVE_CURRENT_DATA && video.videoWidth > 0) {
+          await detectFrame(video, video.videoWidth, video.videoHeight);
+        }
+      } catch (error) {
+        setStatus(`Detection failed: ${error}`);
+        stopped = true;
+      }
+      busy = false;
+
+      if (!stopped) {
+        frameRequest = requestAnimationFrame(loop);
+      } else if (session) {
+        session.detector.free();
+        session = undefined;
+      }
+    };
+
+    setStats(null);
+    setStatus(`Loading the ${backend} detector...`);
+    loadDetector(backend, (loaded, total) => {
+      const size = total ? ` / ${(total / 1e6).toFixed(1)}` : "";
+      setStatus(`Downloading the weights: ${(loaded / 1e6).toFixed(1)}${size} MB`);
+    })
+      .then((loaded) => {
+        if (stopped) {
+          loaded.detector.free();
+          return;
+        }
+        session = loaded;
+        setStatus(`Running on ${loaded.backend}`);
+        frameRequest = requestAnimationFrame(loop);
+      })
+      .catch((error) => {
+        if (!stopped) {
+          setStatus(`Failed to load the ${backend} detector: ${error}`);
+        }
+      });
+
+    return () => {
+      stopped = true;
+      cancelAnimationFrame(frameRequest);
+      // A running detection frees the detector once it completes
+      if (session && !busy) {
+        session.detector.free();
+        session = undefined;
+      }
+    };
+  }, [backend]);
+
+  const setOption = (name, value) =>
+    setOptions((options) => ({ ...options, [name]: value }));
+
+  return (
+    <div>
+      <div className="controls">
+        <label>
+          Backend{" "}
+          <select value={backend} onChange={(e) => setBackend(e.target.value)}>
+            {BACKENDS.map((name) => (
+              <option key={name} value={name} disabled={!isBackendSupported(name)}>
+                {name}
+              </option>
+            ))}
+          </select>
+        </label>
+        <label>
+          Input size{" "}
+          <select
+            value={options.inputSize}
+            onChange={(e) => setOption("inputSize", Number(e.target.value))}
+          >
+            {INPUT_SIZES.map((size) => (
+              <option key={size} value={size}>
+                {size}x{size}
+              </option>
+            ))}
+          </select>
+        </label>
+        <label>
+          Score threshold{" "}
+          <input
+            type="range"
+            min="0.05"
+            max="0.95"
+            step="0.05"
+            value={options.scoreThreshold}
+            onChange={(e) => setOption("scoreThreshold", Number(e.target.value))}
+          />{" "}
+          {options.scoreThreshold.toFixed(2)}
+        </label>
+        <label>
+          IoU threshold{" "}
+          <input
+            type="range"
+            min="0.05"
+            max="0.95"
+            step="0.05"
+            value={options.iouThreshold}
+            onChange={(e) => setOption("iouThreshold", Number(e.target.value))}
+          />{" "}
+          {options.iouThreshold.toFixed(2)}
+        </label>
+      </div>
+      <p>
+        {status}
+        {stats &&
+          ` | FPS: ${stats.fps} | Latency: ${stats.latency.toFixed(1)} ms | Boxes: ${stats.boxes}`}
+      </p>
+      <video ref={videoRef} playsInline muted style={{ display: "none" }} />
+      <canvas
+        id="webcam-canvas"
+        ref={canvasRef}
+        width="640"
+        height="480"
+        style={{ border: "1px solid #aaa" }}
+      />
+    </div>
+  );
+};
@@ -0,0 +1,69 @@
+import { fetchWeights } from "./utils";
+
+/**
+ * Backends of the yolo wasm packages, each built into `public/yolo/<backend>` (see the README).
+ */
+export const BACKENDS = ["ndarray", "wgpu"];
+
+/**
+ * Whether the browser can run a backend. The wgpu backend requires WebGPU.
+ * @param {string} backend - Backend name.
+ */
+export function isBackendSupported(backend) {
+  return backend !== "wgpu" || "gpu" in navigator;
+}
+
+/**
+ * Loads the yolo wasm package of a backend and returns its detector, with the YOLOX-Tiny weights
+ * of `public/yolo/yolox_tiny.bin`.
+ * @param {string} backend - Backend name.
+ * @param {function} onProgress - Called with the received and total bytes of the weights.
+ */
+export async function loadDetector(backend, onProgress) {
+  const base = `${process.env.PUBLIC_URL}/yolo`;
+  // The packages are served as they are, rather than bundled, so that any of them can be missing
+  const yolo = await import(/* webpackIgnore: true */ `${base}/${backend}/yolo.js`);
+  await yolo.default();
+
+  const weights = await fetchWeights(
+    yolo.Weights,
+    `${base}/yolox_tiny.bin`,
+    process.env.REACT_APP_YOLOX_CHECKSUM,
+    onProgress
+  );
+
+  return {
+    detector: yolo.Detector.with_weights(weights),
+    classes: yolo.Detector.classes(),
+    backend: yolo.Detector.backend(),
+  };
+}
+
+/**
+ * Draws the boxes returned by `Detector.detect` with their labels.
+ * @param {CanvasRenderingContext2D} context - Context of the canvas showing the frame.
+ * @param {Array} boxes - `[class, xmin, ymin, xmax, ymax, confidence, track_id]` boxes.
+ * @param {string[]} classes - Class names.
+ */
+export function drawBoxes(context, boxes, classes) {
+  context.lineWidth = 2;
+  context.font = "14px sans-serif";
+  context.textBaseline = "bottom";
+
+  for (const [cls, xmin, ymin, xmax, ymax, confidence, trackId] of boxes) {
+    // Spread the class colors around the hue circle
+    const color = `hsl(${(cls * 137) % 360}, 90%, 50%)`;
+    const track = trackId >= 0 ? ` #${trackId}` : "";
+    const label = `${classes[cls] ?? cls}${track} ${confidence.toFixed(2)}`;
+
+    context.strokeStyle = color;
+    context.strokeRect(xmin, ymin, xmax - xmin, ymax - ymin);
+
+    const width = context.measureText(label).width + 6;
+    const top = Math.max(ymin, 18);
+    context.fillStyle = color;
+    context.fillRect(xmin - 1, top - 18, width, 18);
+    context.fillStyle = "black";
+    context.fillText(label, xmin + 2, top - 1);
+  }
+}
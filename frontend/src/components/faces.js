@@ -0,0 +1,95 @@
+import { fetchWeights } from "./utils";
+
+/**
+ * Loads the facenet wasm package of `public/facenet`, with the MobileFaceNet weights written by
+ * `convert_weights` to `public/facenet/mobilefacenet.bin`, and their config next to them.
+ * @param {function} onProgress - Called with the received and total bytes of the weights.
+ */
+export async function loadFaceNet(onProgress) {
+  const base = `${process.env.PUBLIC_URL}/facenet`;
+  const facenet = await import(/* webpackIgnore: true */ `${base}/facenet_burn.js`);
+  await facenet.default();
+
+  // The config is only needed if the architecture is not the default one
+  const config = await fetch(`${base}/mobilefacenet.json`).then((response) =>
+    response.ok ? response.text() : undefined
+  );
+  const weights = await fetchWeights(
+    facenet.Weights,
+    `${base}/mobilefacenet.bin`,
+    process.env.REACT_APP_MOBILEFACENET_CHECKSUM,
+    onProgress
+  );
+
+  return facenet.FaceNet.with_weights(weights, config);
+}
+
+/**
+ * Returns the face region `{x, y, width, height}` of a detected box, clamped to the frame.
+ *
+ * With boxes of faces, the region is the box itself. With boxes of people, it is the square at the
+ * top of the box, where the head is when the person stands or sits upright.
+ * @param {number[]} box - `[class, xmin, ymin, xmax, ymax, ...]` box.
+ * @param {boolean} boxesAreFaces - Whether the boxes are faces rather than people.
+ * @param {number} frameWidth - Frame width.
+ * @param {number} frameHeight - Frame height.
+ */
+export function faceRegion(box, boxesAreFaces, frameWidth, frameHeight) {
+  const [, xmin, ymin, xmax, ymax] = box;
+  let [x, y, width, height] = [xmin, ymin, xmax - xmin, ymax - ymin];
+  if (!boxesAreFaces) {
+    const side = Math.min(width * 0.6, height);
+    [x, y, width, height] = [x + (width - side) / 2, y, side, side];
+  }
+
+  const left = Math.max(0, Math.floor(x));
+  const top = Math.max(0, Math.floor(y));
+  const right = Math.min(frameWidth, Math.ceil(x + width));
+  const bottom = Math.min(frameHeight, Math.ceil(y + height));
+  if (right - left < 8 || bottom - top < 8) {
+    return undefined;
+  }
+  return { x: left, y: top, width: right - left, height: bottom - top };
+}
+
+/**
+ * Returns a small JPEG data URL of a face region, shown in the gallery.
+ * @param {HTMLCanvasElement} frame - Canvas of the frame.
+ * @param {object} region - Face region.
+ */
+export function thumbnail(frame, { x, y, width, height }) {
+  const canvas = document.createElement("canvas");
+  canvas.width = 64;
+  canvas.height = 64;
+  canvas.getContext("2d").drawImage(frame, x, y, width, height, 0, 0, 64, 64);
+  return canvas.toDataURL("image/jpeg", 0.8);
+}
+
+/**
+ * Draws the face regions with the names of the recognized identities.
+ * @param {CanvasRenderingContext2D} context - Context of the canvas showing the frame.
+ * @param {object[]} faces - `{region, identity}` faces, where the identity is `{name, similarity}`
+ *   or undefined if unknown.
+ */
+export function drawFaces(context, faces) {
+  context.lineWidth = 2;
+  context.font = "14px sans-serif";
+  context.textBaseline = "bottom";
+
+  for (const { region, identity } of faces) {
+    const color = identity ? "limegreen" : "orange";
+    const label = identity
+      ? `${identity.name} ${identity.similarity.toFixed(2)}`
+      : "Unknown";
+
+    context.strokeStyle = color;
+    context.strokeRect(region.x, region.y, region.width, region.height);
+
+    const width = context.measureText(label).width + 6;
+    const top = Math.max(region.y, 18);
+    context.fillStyle = color;
+    context.fillRect(region.x - 1, top - 18, width, 18);
+    context.fillStyle = "black";
+    context.fillText(label, region.x + 2, top - 1);
+  }
+}
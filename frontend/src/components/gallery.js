@@ -0,0 +1,137 @@
+const DATABASE = "face-gallery";
+const STORE = "faces";
+const EXPORT_VERSION = 1;
+
+/**
+ * Wraps an IndexedDB request into a promise.
+ * @param {IDBRequest} request - Request.
+ */
+function promise(request) {
+  return new Promise((resolve, reject) => {
+    request.onsuccess = () => resolve(request.result);
+    request.onerror = () => reject(request.error);
+  });
+}
+
+/**
+ * Opens the gallery database, whose faces are `{id, name, embedding, thumbnail, created}` with a
+ * `Float32Array` embedding and a data URL thumbnail.
+ */
+export function openGallery() {
+  const request = indexedDB.open(DATABASE, 1);
+  request.onupgradeneeded = () => {
+    request.result.createObjectStore(STORE, {
+      keyPath: "id",
+      autoIncrement: true,
+    });
+  };
+  return promise(request);
+}
+
+/**
+ * Returns all the faces of the gallery, in enrollment order.
+ * @param {IDBDatabase} db - Gallery database.
+ */
+export function listFaces(db) {
+  return promise(db.transaction(STORE).objectStore(STORE).getAll());
+}
+
+/**
+ * Adds faces to the gallery.
+ * @param {IDBDatabase} db - Gallery database.
+ * @param {object[]} faces - `{name, embedding, thumbnail}` faces.
+ */
+export async function addFaces(db, faces) {
+  const transaction = db.transaction(STORE, "readwrite");
+  const store = transaction.objectStore(STORE);
+  for (const { name, embedding, thumbnail, created } of faces) {
+    store.add({
+      name,
+      embedding: Float32Array.from(embedding),
+      thumbnail,
+      created: created ?? Date.now(),
+    });
+  }
+  await new Promise((resolve, reject) => {
+    transaction.oncomplete = resolve;
+    transaction.onerror = () => reject(transaction.error);
+  });
+}
+
+/**
+ * Removes a face from the gallery.
+ * @param {IDBDatabase} db - Gallery database.
+ * @param {number} id - Face ID.
+ */
+export function deleteFace(db, id) {
+  return promise(db.transaction(STORE, "readwrite").objectStore(STORE).delete(id));
+}
+
+/**
+ * Removes all the faces of the gallery.
+ * @param {IDBDatabase} db - Gallery database.
+ */
+export function clearFaces(db) {
+  return promise(db.transaction(STORE, "readwrite").objectStore(STORE).clear());
+}
+
+/**
+ * Serializes faces into a JSON file, as read by `parseGallery`.
+ * @param {object[]} faces - Faces of the gallery.
+ */
+export function exportGallery(faces) {
+  const gallery = {
+    version: EXPORT_VERSION,
+    faces: faces.map(({ name, embedding, thumbnail, created }) => ({
+      name,
+      embedding: Array.from(embedding),
+      thumbnail,
+      created,
+    })),
+  };
+  return new Blob([JSON.stringify(gallery)], { type: "application/json" });
+}
+
+/**
+ * Parses a gallery file written by `exportGallery`.
+ * @param {string} text - File content.
+ * @param {number} embeddingSize - Expected embedding size, or undefined.
+ */
+export function parseGallery(text, embeddingSize) {
+  const gallery = JSON.parse(text);
+  if (gallery.version !== EXPORT_VERSION || !Array.isArray(gallery.faces)) {
+    throw new Error("Not a face gallery file");
+  }
+  for (const { name, embedding } of gallery.faces) {
+    if (typeof name !== "string" || !Array.isArray(embedding)) {
+      throw new Error("Faces must have a name and an embedding");
+    }
+    if (embeddingSize !== undefined && embedding.length !== embeddingSize) {
+      throw new Error(
+        `Expected embeddings of size ${embeddingSize}, got ${embedding.length}`
+      );
+    }
+  }
+  return gallery.faces;
+}
+
+/**
+ * Returns the best matching identity of an embedding and its cosine similarity, if above the
+ * threshold. The embeddings are L2 normalized, so their similarity is their dot product.
+ * @param {object[]} faces - Faces of the gallery, several of them may share a name.
+ * @param {Float32Array} embedding - Embedding of the face to identify.
+ * @param {number} threshold - Minimum cosine similarity.
+ */
+export function identify(faces, embedding, threshold) {
+  let best;
+  for (const face of faces) {
+    let similarity = 0;
+    for (let i = 0; i < embedding.length; i++) {
+      similarity += face.embedding[i] * embedding[i];
+    }
+    if (similarity >= threshold && (!best || similarity > best.similarity)) {
+      best = { name: face.name, similarity };
+    }
+  }
+  return best;
+}
@@ -0,0 +1,31 @@
+import { useEffect } from "react";
+
+/**
+ * Plays the webcam video in a video element while the component is mounted.
+ * @param {object} videoRef - Ref of the video element.
+ * @param {function} onError - Called with the error message if the webcam cannot be opened.
+ */
+export function useWebcam(videoRef, onError) {
+  useEffect(() => {
+    let stream;
+    let stopped = false;
+
+    navigator.mediaDevices
+      .getUserMedia({ video: { width: 640, height: 480 }, audio: false })
+      .then((mediaStream) => {
+        stream = mediaStream;
+        if (stopped) {
+          stream.getTracks().forEach((track) => track.stop());
+          return;
+        }
+        videoRef.current.srcObject = stream;
+        return videoRef.current.play();
+      })
+      .catch((error) => onError(`Failed to open the webcam: ${error.message}`));
+
+    return () => {
+      stopped = true;
+      stream?.getTracks().forEach((track) => track.stop());
+    };
+  }, [videoRef, onError]);
+}
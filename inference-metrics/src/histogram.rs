@@ -0,0 +1,110 @@
+/// Default latency bucket upper bounds, in milliseconds.
+pub const DEFAULT_BUCKETS_MS: [f64; 15] = [
+    0.5, 1., 2.5, 5., 10., 25., 50., 100., 250., 500., 1000., 2500., 5000., 10000., 30000.,
+];
+
+/// Histogram with fixed buckets, as in Prometheus.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Histogram {
+    /// Increasing bucket upper bounds. The last, implicit, bucket is unbounded.
+    bounds: Vec<f64>,
+    /// Number of observations per bucket (not cumulative), with the unbounded bucket last.
+    counts: Vec<u64>,
+    sum: f64,
+    max: f64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self::new(&DEFAULT_BUCKETS_MS)
+    }
+}
+
+impl Histogram {
+    /// Creates an empty histogram with the given bucket upper bounds, which are sorted.
+    pub fn new(bounds: &[f64]) -> Self {
+        let mut bounds = bounds.to_vec();
+        bounds.retain(|bound| bound.is_finite());
+        bounds.sort_by(f64::total_cmp);
+        bounds.dedup();
+
+        Self {
+            counts: vec![0; bounds.len() + 1],
+            bounds,
+            sum: 0.,
+            max: 0.,
+        }
+    }
+
+    pub fn observe(&mut self, value: f64) {
+        let bucket = self.bounds.partition_point(|bound| *bound < value);
+        self.counts[bucket] += 1;
+        self.sum += value;
+        self.max = if self.count() == 1 {
+            value
+        } else {
+            self.max.max(value)
+        };
+    }
+
+    /// Number of observations.
+    pub fn count(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// Sum of the observations.
+    pub fn sum(&self) -> f64 {
+        self.sum
+    }
+
+    /// Largest observation, or 0 without observations.
+    pub fn max(&self) -> f64 {
+        self.max
+    }
+
+    /// Average observation, or 0 without observations.
+    pub fn mean(&self) -> f64 {
+        self.sum / self.count().max(1) as f64
+    }
+
+    /// Estimates the `q` quantile (`q` in `[0, 1]`) by linear interpolation within its bucket, as
+    /// Prometheus' `histogram_quantile`. Quantiles in the unbounded bucket are estimated with the
+    /// largest observation.
+    pub fn quantile(&self, q: f64) -> f64 {
+        let count = self.count();
+        if count == 0 {
+            return 0.;
+        }
+        let rank = q.clamp(0., 1.) * count as f64;
+
+        let mut cumulative = 0;
+        for (i, bucket_count) in self.counts.iter().enumerate() {
+            let previous = cumulative;
+            cumulative += bucket_count;
+            if (cumulative as f64) < rank || *bucket_count == 0 {
+                continue;
+            }
+            let Some(upper) = self.bounds.get(i) else {
+                return self.max;
+            };
+            let lower = if i == 0 { 0. } else { self.bounds[i - 1] };
+            let fraction = (rank - previous as f64) / *bucket_count as f64;
+            // The observations of a bucket cannot exceed the largest one
+            return (lower + (upper - lower) * fraction).min(self.max);
+        }
+
+        self.max
+    }
+
+    /// Cumulative bucket counts `(upper bound, count)`, ending with the unbounded bucket.
+    pub fn buckets(&self) -> impl Iterator<Item = (f64, u64)> + '_ {
+        self.bounds
+            .iter()
+            .copied()
+            .chain(core::iter::once(f64::INFINITY))
+            .zip(self.counts.iter().scan(0, |cumulative, count| {
+                *cumulative += count;
+                Some(*cumulative)
+            }))
+    }
+}
@@ -0,0 +1,29 @@
+//! Inference metrics shared by the demos: latency histograms per stage, frame rate over a sliding
+//! window and counters.
+//!
+//! Metrics are exported in the [Prometheus text format](https://prometheus.io/docs/instrumenting/exposition_formats/)
+//! natively, and as a JavaScript object in the browser.
+
+mod histogram;
+mod metrics;
+mod rate;
+
+pub use histogram::{Histogram, DEFAULT_BUCKETS_MS};
+pub use metrics::{CounterSnapshot, Metrics, MetricsSnapshot, StageSnapshot};
+pub use rate::RateWindow;
+
+/// Milliseconds since the Unix epoch, from `Date.now()` in the browser where `std::time` is not
+/// available.
+pub fn now_ms() -> f64 {
+    #[cfg(target_family = "wasm")]
+    {
+        js_sys::Date::now()
+    }
+    #[cfg(not(target_family = "wasm"))]
+    {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs_f64() * 1000.)
+            .unwrap_or_default()
+    }
+}
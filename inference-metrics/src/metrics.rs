@@ -0,0 +1,324 @@
+use std::{collections::BTreeMap, fmt::Write, sync::Mutex};
+
+use js_sys::{Object, Reflect};
+use wasm_bindgen::JsValue;
+
+use crate::{now_ms, Histogram, RateWindow, DEFAULT_BUCKETS_MS};
+
+/// Registry of the metrics of an inference pipeline:
+/// * the latency of each stage (e.g. `preprocess`, `forward`, `nms`, `embed`), in a
+///   [histogram](Histogram),
+/// * the rate of processed frames over a sliding window,
+/// * counters, e.g. of detections, optionally split by a label such as the class.
+///
+/// Methods take `&self`, so that the metrics can be shared between threads and components.
+/// Timestamps are passed explicitly, e.g. from [now_ms](crate::now_ms).
+#[derive(Debug)]
+pub struct Metrics {
+    namespace: String,
+    buckets: Vec<f64>,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    stages: BTreeMap<String, Histogram>,
+    counters: BTreeMap<String, Counter>,
+    frames: RateWindow,
+}
+
+#[derive(Debug, Default)]
+struct Counter {
+    total: u64,
+    label: Option<String>,
+    by_label: BTreeMap<String, u64>,
+}
+
+/// Latency statistics of a stage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageSnapshot {
+    pub name: String,
+    pub count: u64,
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+    pub histogram: Histogram,
+}
+
+/// Value of a counter.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CounterSnapshot {
+    pub name: String,
+    pub total: u64,
+    /// Name of the label the counter is split by, if any.
+    pub label: Option<String>,
+    /// Count per label value.
+    pub by_label: Vec<(String, u64)>,
+}
+
+/// Values of all the metrics at a point in time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricsSnapshot {
+    /// Frames per second over the sliding window.
+    pub fps: f64,
+    /// Number of frames since the creation of the metrics.
+    pub frames: u64,
+    pub stages: Vec<StageSnapshot>,
+    pub counters: Vec<CounterSnapshot>,
+}
+
+impl Metrics {
+    /// Creates empty metrics, whose exported names are prefixed with `namespace`. The frame rate
+    /// is computed over 5 seconds.
+    pub fn new(namespace: &str) -> Self {
+        Self {
+            namespace: sanitize(namespace),
+            buckets: DEFAULT_BUCKETS_MS.to_vec(),
+            inner: Mutex::new(Inner {
+                stages: BTreeMap::new(),
+                counters: BTreeMap::new(),
+                frames: RateWindow::new(5000.),
+            }),
+        }
+    }
+
+    /// Sets the length of the frame rate window, in milliseconds.
+    pub fn with_window_ms(self, window_ms: f64) -> Self {
+        self.lock().frames = RateWindow::new(window_ms);
+        self
+    }
+
+    /// Sets the latency histogram bucket upper bounds, in milliseconds.
+    pub fn with_buckets(mut self, buckets: &[f64]) -> Self {
+        self.buckets = buckets.to_vec();
+        self
+    }
+
+    /// Records the latency of a stage, in milliseconds.
+    pub fn observe(&self, stage: &str, latency_ms: f64) {
+        let buckets = &self.buckets;
+        self.lock()
+            .stages
+            .entry(stage.to_string())
+            .or_insert_with(|| Histogram::new(buckets))
+            .observe(latency_ms);
+    }
+
+    /// Runs `f`, recording its duration as the latency of a stage.
+    ///
+    /// With asynchronous backends, only the time to enqueue the work is measured unless `f` reads
+    /// its results.
+    pub fn time<R>(&self, stage: &str, f: impl FnOnce() -> R) -> R {
+        let start = now_ms();
+        let result = f();
+        self.observe(stage, now_ms() - start);
+        result
+    }
+
+    /// Adds `n` to a counter.
+    pub fn increment(&self, counter: &str, n: u64) {
+        self.lock()
+            .counters
+            .entry(counter.to_string())
+            .or_default()
+            .total += n;
+    }
+
+    /// Adds `n` to a counter split by a label, e.g. `("class", "person")`.
+    pub fn increment_labeled(&self, counter: &str, (label, value): (&str, &str), n: u64) {
+        let mut inner = self.lock();
+        let counter = inner.counters.entry(counter.to_string()).or_default();
+        counter.total += n;
+        counter.label.get_or_insert_with(|| sanitize(label));
+        *counter.by_label.entry(value.to_string()).or_default() += n;
+    }
+
+    /// Records a processed frame at the given timestamp, in milliseconds.
+    pub fn record_frame(&self, timestamp_ms: f64) {
+        self.lock().frames.record(timestamp_ms);
+    }
+
+    /// Frames per second over the window ending at `now_ms`.
+    pub fn fps(&self, now_ms: f64) -> f64 {
+        self.lock().frames.rate(now_ms)
+    }
+
+    /// Forgets all the recorded values.
+    pub fn reset(&self) {
+        let mut inner = self.lock();
+        inner.stages.clear();
+        inner.counters.clear();
+        let window_ms = inner.frames.window_ms();
+        inner.frames = RateWindow::new(window_ms);
+    }
+
+    /// Values of all the metrics, with the frame rate over the window ending at `now_ms`.
+    pub fn snapshot(&self, now_ms: f64) -> MetricsSnapshot {
+        let mut inner = self.lock();
+
+        MetricsSnapshot {
+            fps: inner.frames.rate(now_ms),
+            frames: inner.frames.total(),
+            stages: inner
+                .stages
+                .iter()
+                .map(|(name, histogram)| StageSnapshot {
+                    name: name.clone(),
+                    count: histogram.count(),
+                    mean_ms: histogram.mean(),
+                    p50_ms: histogram.quantile(0.5),
+                    p95_ms: histogram.quantile(0.95),
+                    p99_ms: histogram.quantile(0.99),
+                    max_ms: histogram.max(),
+                    histogram: histogram.clone(),
+                })
+                .collect(),
+            counters: inner
+                .counters
+                .iter()
+                .map(|(name, counter)| CounterSnapshot {
+                    name: name.clone(),
+                    total: counter.total,
+                    label: counter.label.clone(),
+                    by_label: counter
+                        .by_label
+                        .iter()
+                        .map(|(value, count)| (value.clone(), *count))
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Metrics in the Prometheus text format, e.g. for a `/metrics` endpoint.
+    pub fn to_prometheus(&self, now_ms: f64) -> String {
+        let snapshot = self.snapshot(now_ms);
+        let ns = &self.namespace;
+        let mut text = String::new();
+
+        let latency = format!("{ns}_stage_latency_milliseconds");
+        writeln!(text, "# HELP {latency} Latency of the inference stages.").unwrap();
+        writeln!(text, "# TYPE {latency} histogram").unwrap();
+        for stage in snapshot.stages.iter() {
+            let name = escape(&stage.name);
+            for (bound, count) in stage.histogram.buckets() {
+                let bound = if bound.is_finite() {
+                    bound.to_string()
+                } else {
+                    "+Inf".to_string()
+                };
+                writeln!(
+                    text,
+                    "{latency}_bucket{{stage=\"{name}\",le=\"{bound}\"}} {count}"
+                )
+                .unwrap();
+            }
+            writeln!(
+                text,
+                "{latency}_sum{{stage=\"{name}\"}} {}",
+                stage.histogram.sum()
+            )
+            .unwrap();
+            writeln!(text, "{latency}_count{{stage=\"{name}\"}} {}", stage.count).unwrap();
+        }
+
+        writeln!(text, "# HELP {ns}_frames_total Processed frames.").unwrap();
+        writeln!(text, "# TYPE {ns}_frames_total counter").unwrap();
+        writeln!(text, "{ns}_frames_total {}", snapshot.frames).unwrap();
+        writeln!(
+            text,
+            "# HELP {ns}_fps Frames per second over a sliding window."
+        )
+        .unwrap();
+        writeln!(text, "# TYPE {ns}_fps gauge").unwrap();
+        writeln!(text, "{ns}_fps {}", snapshot.fps).unwrap();
+
+        for counter in snapshot.counters.iter() {
+            let name = format!("{ns}_{}_total", sanitize(&counter.name));
+            writeln!(text, "# TYPE {name} counter").unwrap();
+            match &counter.label {
+                Some(label) => {
+                    for (value, count) in counter.by_label.iter() {
+                        writeln!(text, "{name}{{{label}=\"{}\"}} {count}", escape(value)).unwrap();
+                    }
+                }
+                None => writeln!(text, "{name} {}", counter.total).unwrap(),
+            }
+        }
+
+        text
+    }
+
+    /// Metrics as a JavaScript object, only available in the browser:
+    ///
+    /// ```js
+    /// {
+    ///   fps, frames,
+    ///   stages: { forward: { count, mean_ms, p50_ms, p95_ms, p99_ms, max_ms }, ... },
+    ///   counters: { detections: 12, detections_by_class: { person: 10, car: 2 }, ... },
+    /// }
+    /// ```
+    pub fn to_js(&self, now_ms: f64) -> Object {
+        let snapshot = self.snapshot(now_ms);
+        let set = |object: &Object, key: &str, value: JsValue| {
+            Reflect::set(object, &key.into(), &value).unwrap();
+        };
+
+        let stages = Object::new();
+        for stage in snapshot.stages.iter() {
+            let object = Object::new();
+            set(&object, "count", (stage.count as f64).into());
+            set(&object, "mean_ms", stage.mean_ms.into());
+            set(&object, "p50_ms", stage.p50_ms.into());
+            set(&object, "p95_ms", stage.p95_ms.into());
+            set(&object, "p99_ms", stage.p99_ms.into());
+            set(&object, "max_ms", stage.max_ms.into());
+            set(&stages, &stage.name, object.into());
+        }
+
+        let counters = Object::new();
+        for counter in snapshot.counters.iter() {
+            set(&counters, &counter.name, (counter.total as f64).into());
+            if let Some(label) = &counter.label {
+                let by_label = Object::new();
+                for (value, count) in counter.by_label.iter() {
+                    set(&by_label, value, (*count as f64).into());
+                }
+                set(
+                    &counters,
+                    &format!("{}_by_{label}", counter.name),
+                    by_label.into(),
+                );
+            }
+        }
+
+        let object = Object::new();
+        set(&object, "fps", snapshot.fps.into());
+        set(&object, "frames", (snapshot.frames as f64).into());
+        set(&object, "stages", stages.into());
+        set(&object, "counters", counters.into());
+        object
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        // The metrics stay consistent even if a thread panicked while recording
+        self.inner.lock().unwrap_or_else(|err| err.into_inner())
+    }
+}
+
+/// Replaces the characters not allowed in Prometheus metric and label names.
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Escapes a Prometheus label value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
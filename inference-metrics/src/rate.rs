@@ -0,0 +1,66 @@
+use std::collections::VecDeque;
+
+/// Rate of events, e.g. frames, over a sliding time window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateWindow {
+    window_ms: f64,
+    /// Timestamps of the events in the window, in milliseconds.
+    events: VecDeque<f64>,
+    /// Timestamp of the first event.
+    start_ms: Option<f64>,
+    total: u64,
+}
+
+impl RateWindow {
+    pub fn new(window_ms: f64) -> Self {
+        Self {
+            window_ms,
+            events: VecDeque::new(),
+            start_ms: None,
+            total: 0,
+        }
+    }
+
+    /// Records an event at the given timestamp, in milliseconds.
+    pub fn record(&mut self, timestamp_ms: f64) {
+        self.start_ms.get_or_insert(timestamp_ms);
+        self.events.push_back(timestamp_ms);
+        self.total += 1;
+        self.evict(timestamp_ms);
+    }
+
+    /// Events per second over the window ending at `now_ms`. Until a whole window elapsed since
+    /// the first event, the rate is computed over the elapsed time.
+    pub fn rate(&mut self, now_ms: f64) -> f64 {
+        self.evict(now_ms);
+        let Some(start_ms) = self.start_ms else {
+            return 0.;
+        };
+        let span_ms = self.window_ms.min(now_ms - start_ms);
+        if span_ms <= 0. {
+            return 0.;
+        }
+
+        self.events.len() as f64 * 1000. / span_ms
+    }
+
+    /// Number of events since the creation of the window.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Length of the window, in milliseconds.
+    pub fn window_ms(&self) -> f64 {
+        self.window_ms
+    }
+
+    fn evict(&mut self, now_ms: f64) {
+        while self
+            .events
+            .front()
+            .is_some_and(|timestamp| *timestamp <= now_ms - self.window_ms)
+        {
+            self.events.pop_front();
+        }
+    }
+}
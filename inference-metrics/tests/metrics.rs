@@ -0,0 +1,131 @@
+use inference_metrics::{Histogram, Metrics, RateWindow};
+
+#[test]
+fn histogram_quantiles_interpolate_within_buckets() {
+    let mut histogram = Histogram::new(&[10., 20., 40.]);
+    for value in [2., 4., 6., 8., 12., 14., 16., 18., 30., 35.] {
+        histogram.observe(value);
+    }
+
+    assert_eq!(histogram.count(), 10);
+    assert_eq!(histogram.sum(), 145.);
+    assert_eq!(histogram.max(), 35.);
+    assert_eq!(histogram.mean(), 14.5);
+    // 4 observations in [0, 10], 4 in (10, 20], 2 in (20, 40]
+    assert_eq!(histogram.quantile(0.2), 5.);
+    assert_eq!(histogram.quantile(0.5), 12.5);
+    // Interpolated to 40 within the last bucket, capped by the largest observation
+    assert_eq!(histogram.quantile(1.), 35.);
+}
+
+#[test]
+fn histogram_buckets_are_cumulative() {
+    let mut histogram = Histogram::new(&[5., 1.]);
+    for value in [0.5, 1., 3., 100.] {
+        histogram.observe(value);
+    }
+
+    let buckets: Vec<_> = histogram.buckets().collect();
+    assert_eq!(buckets, vec![(1., 2), (5., 3), (f64::INFINITY, 4)]);
+    // Quantiles beyond the last bound are estimated with the largest observation
+    assert_eq!(histogram.quantile(0.99), 100.);
+    assert_eq!(Histogram::default().quantile(0.5), 0.);
+}
+
+#[test]
+fn rate_window_slides() {
+    let mut rate = RateWindow::new(1000.);
+    assert_eq!(rate.rate(0.), 0.);
+
+    // 10 events per second during 2 seconds
+    for i in 0..20 {
+        rate.record(i as f64 * 100.);
+    }
+    assert_eq!(rate.total(), 20);
+    assert_eq!(rate.rate(1900.), 10.);
+
+    // Before a whole window elapsed, the rate is over the elapsed time
+    let mut rate = RateWindow::new(1000.);
+    rate.record(0.);
+    rate.record(100.);
+    assert_eq!(rate.rate(250.), 8.);
+
+    // Old events leave the window
+    assert_eq!(rate.rate(1050.), 1.);
+    assert_eq!(rate.rate(5000.), 0.);
+}
+
+#[test]
+fn snapshot_aggregates_stages_counters_and_frames() {
+    let metrics = Metrics::new("yolo").with_window_ms(1000.);
+    metrics.observe("forward", 20.);
+    metrics.observe("forward", 40.);
+    metrics.observe("nms", 1.);
+    let value = metrics.time("preprocess", || 42);
+    metrics.increment("requests", 2);
+    metrics.increment_labeled("detections", ("class", "person"), 3);
+    metrics.increment_labeled("detections", ("class", "car"), 1);
+    for i in 0..5 {
+        metrics.record_frame(i as f64 * 200.);
+    }
+
+    let snapshot = metrics.snapshot(1000.);
+    assert_eq!(value, 42);
+    assert_eq!(snapshot.fps, 4.);
+    assert_eq!(snapshot.frames, 5);
+
+    let stages: Vec<_> = snapshot
+        .stages
+        .iter()
+        .map(|stage| (stage.name.as_str(), stage.count))
+        .collect();
+    assert_eq!(stages, vec![("forward", 2), ("nms", 1), ("preprocess", 1)]);
+    assert_eq!(snapshot.stages[0].mean_ms, 30.);
+    assert_eq!(snapshot.stages[0].max_ms, 40.);
+
+    let detections = &snapshot.counters[0];
+    assert_eq!(detections.name, "detections");
+    assert_eq!(detections.total, 4);
+    assert_eq!(detections.label.as_deref(), Some("class"));
+    assert_eq!(
+        detections.by_label,
+        vec![("car".to_string(), 1), ("person".to_string(), 3)]
+    );
+    assert_eq!(snapshot.counters[1].total, 2);
+    assert_eq!(snapshot.counters[1].label, None);
+
+    metrics.reset();
+    let snapshot = metrics.snapshot(1000.);
+    assert!(snapshot.stages.is_empty() && snapshot.counters.is_empty());
+    assert_eq!(snapshot.frames, 0);
+}
+
+#[test]
+fn prometheus_text_format() {
+    let metrics = Metrics::new("yolo").with_buckets(&[10., 100.]);
+    metrics.observe("forward", 50.);
+    metrics.increment("requests", 2);
+    metrics.increment_labeled("detections", ("class", "traffic \"light\""), 3);
+    metrics.record_frame(0.);
+
+    let text = metrics.to_prometheus(500.);
+    let expected = [
+        "# TYPE yolo_stage_latency_milliseconds histogram",
+        "yolo_stage_latency_milliseconds_bucket{stage=\"forward\",le=\"10\"} 0",
+        "yolo_stage_latency_milliseconds_bucket{stage=\"forward\",le=\"100\"} 1",
+        "yolo_stage_latency_milliseconds_bucket{stage=\"forward\",le=\"+Inf\"} 1",
+        "yolo_stage_latency_milliseconds_sum{stage=\"forward\"} 50",
+        "yolo_stage_latency_milliseconds_count{stage=\"forward\"} 1",
+        "yolo_frames_total 1",
+        "yolo_fps 2",
+        "# TYPE yolo_detections_total counter",
+        "yolo_detections_total{class=\"traffic \\\"light\\\"\"} 3",
+        "yolo_requests_total 2",
+    ];
+    for line in expected {
+        assert!(
+            text.lines().any(|l| l == line),
+            "Missing line {line:?} in\n{text}"
+        );
+    }
+}
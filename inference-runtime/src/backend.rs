@@ -0,0 +1,161 @@
+use alloc::{format, string::String, vec::Vec};
+use core::{fmt, str::FromStr};
+
+use burn::tensor::Device;
+
+#[cfg(feature = "wgpu")]
+use burn::backend::wgpu::{init_async, AutoGraphicsApi, WgpuDevice};
+
+#[cfg(feature = "wgpu")]
+pub type Backend = burn::backend::Wgpu<f32, i32>;
+
+#[cfg(all(feature = "candle", not(feature = "wgpu")))]
+pub type Backend = burn::backend::Candle<f32, i64>;
+
+#[cfg(all(feature = "tch", not(any(feature = "wgpu", feature = "candle"))))]
+pub type Backend = burn::backend::LibTorch<f32>;
+
+#[cfg(all(
+    feature = "ndarray",
+    not(any(feature = "wgpu", feature = "candle", feature = "tch"))
+))]
+pub type Backend = burn::backend::NdArray<f32>;
+
+/// Initializes the [Backend], which must be done before building a model, and returns its
+/// default device.
+///
+/// The wgpu device is only initialized on the first call. It must be initialized asynchronously
+/// in the browser, the other backends need no initialization.
+pub async fn init_device() -> Device<Backend> {
+    #[cfg(feature = "wgpu")]
+    {
+        use core::sync::atomic::{AtomicBool, Ordering};
+
+        static INITIALIZED: AtomicBool = AtomicBool::new(false);
+        if !INITIALIZED.load(Ordering::Acquire) {
+            init_async::<AutoGraphicsApi>(&WgpuDevice::default(), Default::default()).await;
+            INITIALIZED.store(true, Ordering::Release);
+        }
+    }
+
+    Default::default()
+}
+
+/// Backends which can be selected at runtime, if their feature is enabled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BackendKind {
+    /// [NdArray](burn::backend::NdArray) CPU backend, `ndarray` feature.
+    NdArray,
+    /// [Wgpu](burn::backend::Wgpu) GPU backend, `wgpu` feature.
+    Wgpu,
+    /// [Candle](https://github.com/huggingface/candle) CPU backend, `candle` feature.
+    Candle,
+    /// LibTorch CPU backend, `tch` feature. Requires a LibTorch installation.
+    LibTorch,
+}
+
+impl BackendKind {
+    /// All the backends, enabled or not.
+    pub const ALL: [BackendKind; 4] = [Self::NdArray, Self::Wgpu, Self::Candle, Self::LibTorch];
+
+    /// Kind of the [Backend] selected by feature.
+    pub fn selected() -> Self {
+        if cfg!(feature = "wgpu") {
+            Self::Wgpu
+        } else if cfg!(feature = "candle") {
+            Self::Candle
+        } else if cfg!(feature = "tch") {
+            Self::LibTorch
+        } else {
+            Self::NdArray
+        }
+    }
+
+    /// The backends whose feature is enabled.
+    pub fn available() -> Vec<Self> {
+        Self::ALL
+            .into_iter()
+            .filter(|kind| kind.is_available())
+            .collect()
+    }
+
+    /// Whether the feature of the backend is enabled.
+    pub fn is_available(&self) -> bool {
+        match self {
+            Self::NdArray => cfg!(feature = "ndarray"),
+            Self::Wgpu => cfg!(feature = "wgpu"),
+            Self::Candle => cfg!(feature = "candle"),
+            Self::LibTorch => cfg!(feature = "tch"),
+        }
+    }
+
+    /// Name of the backend, as parsed by [from_str](BackendKind::from_str).
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::NdArray => "ndarray",
+            Self::Wgpu => "wgpu",
+            Self::Candle => "candle",
+            Self::LibTorch => "tch",
+        }
+    }
+
+    /// Runs the visitor with the backend and its default device.
+    ///
+    /// Only available natively, as the wgpu device must be initialized asynchronously in the
+    /// browser.
+    ///
+    /// # Returns
+    ///
+    /// The output of the visitor, or an error if the feature of the backend is not enabled.
+    #[cfg(not(target_family = "wasm"))]
+    pub fn visit<V: BackendVisitor>(&self, visitor: V) -> Result<V::Output, String> {
+        match self {
+            #[cfg(feature = "ndarray")]
+            Self::NdArray => Ok(visitor.visit::<burn::backend::NdArray<f32>>(Default::default())),
+            #[cfg(feature = "wgpu")]
+            Self::Wgpu => Ok(visitor.visit::<burn::backend::Wgpu<f32, i32>>(Default::default())),
+            #[cfg(feature = "candle")]
+            Self::Candle => {
+                Ok(visitor.visit::<burn::backend::Candle<f32, i64>>(Default::default()))
+            }
+            #[cfg(feature = "tch")]
+            Self::LibTorch => Ok(visitor.visit::<burn::backend::LibTorch<f32>>(Default::default())),
+            #[allow(unreachable_patterns)]
+            kind => Err(format!(
+                "The {kind} backend is not available, enable the `{kind}` feature"
+            )),
+        }
+    }
+}
+
+impl fmt::Display for BackendKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for BackendKind {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|kind| kind.name()).collect();
+                format!(
+                    "Unknown backend {name}, expected one of {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Code generic over the backend, run by [BackendKind::visit] with the backend selected at
+/// runtime.
+pub trait BackendVisitor {
+    type Output;
+
+    /// Runs with the backend `B` and its device.
+    fn visit<B: burn::tensor::backend::Backend>(self, device: B::Device) -> Self::Output;
+}
@@ -0,0 +1,74 @@
+use burn::{
+    module::{Module, Param},
+    nn::{conv::Conv2d, BatchNorm},
+    tensor::{backend::Backend, Tensor},
+};
+
+/// Batch norm following a convolution, or the marker left once it is folded into the convolution
+/// by [fold_batch_norm].
+///
+/// Both states have different records, so that loading the record of a fused model into an
+/// unfused one, or the opposite, fails instead of skipping or applying the batch norm twice.
+#[derive(Module, Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum ConvNorm<B: Backend> {
+    /// Batch norm applied to the output of the convolution, as trained.
+    BatchNorm(BatchNorm<B, 2>),
+    /// Batch norm folded into the weights and bias of the convolution.
+    Folded(Folded),
+}
+
+/// Marker of a batch norm folded into its convolution, see [ConvNorm].
+#[derive(Module, Clone, Debug)]
+pub struct Folded;
+
+impl<B: Backend> ConvNorm<B> {
+    /// Applies the batch norm to the output of the convolution, unless it is folded into it.
+    pub fn forward(&self, x: Tensor<B, 4>) -> Tensor<B, 4> {
+        match self {
+            Self::BatchNorm(norm) => norm.forward(x),
+            Self::Folded(_) => x,
+        }
+    }
+}
+
+impl<B: Backend> From<BatchNorm<B, 2>> for ConvNorm<B> {
+    fn from(norm: BatchNorm<B, 2>) -> Self {
+        Self::BatchNorm(norm)
+    }
+}
+
+/// Folds the batch norm following a convolution into the weights and bias of the convolution.
+///
+/// At inference, the batch norm normalizes each channel with its running statistics,
+/// `(x - mean) / sqrt(var + epsilon) * gamma + beta`, which is an affine function folded by
+/// scaling the filters of the channel and shifting its bias. The folded convolution produces the
+/// output of both modules in a single pass, up to rounding.
+///
+/// # Returns
+///
+/// The folded convolution, which always has a bias, and the [folded](ConvNorm::Folded) norm. An
+/// already folded pair is returned unchanged.
+pub fn fold_batch_norm<B: Backend>(conv: Conv2d<B>, norm: ConvNorm<B>) -> (Conv2d<B>, ConvNorm<B>) {
+    let ConvNorm::BatchNorm(norm) = norm else {
+        return (conv, norm);
+    };
+    let device = conv.weight.device();
+    let scale = norm.gamma.val() / (norm.running_var.value() + norm.epsilon).sqrt();
+    let [channels] = scale.dims();
+
+    let bias = match &conv.bias {
+        Some(bias) => bias.val(),
+        None => Tensor::zeros([channels], &device),
+    };
+    let bias = (bias - norm.running_mean.value()) * scale.clone() + norm.beta.val();
+    let conv = Conv2d {
+        weight: conv
+            .weight
+            .map(|weight| weight * scale.clone().reshape([channels, 1, 1, 1])),
+        bias: Some(Param::from_tensor(bias)),
+        ..conv
+    };
+
+    (conv, ConvNorm::Folded(Folded))
+}
@@ -0,0 +1,34 @@
+#![cfg_attr(not(test), no_std)]
+
+//! Backend selection and model loading shared by the demos.
+//!
+//! The [Backend] of the demos is selected by feature, in order of preference: `wgpu`, `candle`,
+//! `tch` (LibTorch CPU) and `ndarray`. Native binaries can also run any of the enabled backends,
+//! selected at runtime with [BackendKind].
+//!
+//! [fold_batch_norm] optimizes the models for inference, by folding their batch norms into the
+//! preceding convolutions, and [record_bytes] shrinks their weights to half precision or int8.
+//!
+//! In the browser, the weights are either embedded in the wasm module or received from JavaScript
+//! as [Weights], verified against their SHA-256 checksum. Models run in Web Workers receive their
+//! frames through a [FrameQueue], which drops the frames they cannot keep up with.
+//!
+//! The digit classifiers of the demos share their preprocessing and options in [mnist].
+
+mod backend;
+mod fuse;
+mod loader;
+pub mod mnist;
+mod precision;
+mod quantize;
+mod weights;
+mod worker;
+
+pub use backend::{init_device, Backend, BackendKind, BackendVisitor};
+pub use fuse::{fold_batch_norm, ConvNorm, Folded};
+pub use loader::{load_model, ModelLoader};
+pub use precision::{record_bytes, Precision};
+pub use weights::{sha256_hex, Weights};
+pub use worker::{DropPolicy, Frame, FrameQueue};
+
+extern crate alloc;
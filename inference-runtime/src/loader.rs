@@ -0,0 +1,68 @@
+use alloc::vec::Vec;
+
+use burn::{
+    module::Module,
+    record::{
+        BurnRecord, FullPrecisionSettings, HalfPrecisionSettings, PrecisionSettings, Record,
+        RecorderError,
+    },
+    tensor::backend::Backend,
+};
+
+use crate::{init_device, precision::decode_bin, quantize, Precision};
+
+/// Model which can be built from a configuration and loaded from the records written by the
+/// weight conversion binaries.
+pub trait ModelLoader<B: Backend>: Module<B> {
+    /// Configuration of the architecture, `()` when fixed.
+    type Config;
+
+    /// Builds the model with initialized, untrained weights.
+    fn init_model(config: &Self::Config, device: &B::Device) -> Self;
+
+    /// Builds the model and loads the weights of a record saved by
+    /// [record_bytes](crate::record_bytes), or with
+    /// [BinBytesRecorder](burn::record::BinBytesRecorder) and [FullPrecisionSettings], whose
+    /// [precision](Precision::of_record) is detected.
+    fn from_bytes(
+        config: &Self::Config,
+        bytes: Vec<u8>,
+        device: &B::Device,
+    ) -> Result<Self, RecorderError> {
+        let model = Self::init_model(config, device);
+
+        match Precision::of_record(&bytes)? {
+            Precision::Full => {
+                let record = load_bin::<B, FullPrecisionSettings, _>(&bytes, device)?;
+                Ok(model.load_record(record))
+            }
+            Precision::Half => {
+                let record = load_bin::<B, HalfPrecisionSettings, _>(&bytes, device)?;
+                Ok(model.load_record(record))
+            }
+            Precision::Int8 => quantize::decode(model, &bytes),
+        }
+    }
+}
+
+/// Initializes the [backend](crate::Backend) and loads a model on its default device, e.g. from
+/// the weights embedded in a wasm module.
+pub async fn load_model<M: ModelLoader<crate::Backend>>(
+    config: &M::Config,
+    bytes: &[u8],
+) -> Result<M, RecorderError> {
+    let device = init_device().await;
+
+    M::from_bytes(config, bytes.to_vec(), &device)
+}
+
+/// Loads a record saved by [BinBytesRecorder](burn::record::BinBytesRecorder) with the given
+/// settings, like [Recorder::load](burn::record::Recorder::load) but failing on truncated or
+/// corrupted bytes instead of panicking.
+fn load_bin<B: Backend, S: PrecisionSettings, R: Record<B>>(
+    bytes: &[u8],
+    device: &B::Device,
+) -> Result<R, RecorderError> {
+    let record: BurnRecord<R::Item<S>, B> = decode_bin(bytes)?;
+    Ok(R::from_item(record.item, device))
+}
@@ -0,0 +1,10 @@
+//! Preprocessing and options of the MNIST digit classifiers, shared by the `Mnist` wasm classes of
+//! the demos so that they classify the same drawings the same way.
+
+mod options;
+mod preprocess;
+
+pub use options::{top_k, MnistOptions, NUM_CLASSES};
+pub use preprocess::{
+    drawing_to_ink, drawing_to_input, ink, INPUT_SIZE, MARGIN, PIXEL_MEAN, PIXEL_STD,
+};
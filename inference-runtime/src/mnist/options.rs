@@ -0,0 +1,90 @@
+use alloc::{format, string::String, vec::Vec};
+use burn::config::Config;
+
+use super::{MARGIN, PIXEL_MEAN, PIXEL_STD};
+
+/// Number of classes of the model, the digits.
+pub const NUM_CLASSES: usize = 10;
+
+/// Options of the MNIST inference, from preprocessing to the returned scores.
+///
+/// In JavaScript, the options are a plain object with the snake case field names, where the
+/// missing fields keep their default value, e.g. `{top_k: 3, logits: true}`.
+#[derive(Config, Debug, PartialEq)]
+pub struct MnistOptions {
+    /// Whether the drawings are cropped to their ink and centered, as the MNIST digits are,
+    /// rather than scaled whole.
+    #[config(default = "true")]
+    pub crop: bool,
+    /// Side of the square around the cropped digit, relative to the largest side of its bounding
+    /// box.
+    #[config(default = "MARGIN")]
+    pub margin: f32,
+    /// Mean of the pixels in `[0, 1]`, subtracted before the model.
+    #[config(default = "PIXEL_MEAN")]
+    pub mean: f32,
+    /// Standard deviation of the pixels in `[0, 1]`, by which they are divided before the model.
+    #[config(default = "PIXEL_STD")]
+    pub std: f32,
+    /// Number of best classes returned with their labels and scores, rather than the scores of
+    /// all the classes in class order.
+    pub top_k: Option<usize>,
+    /// Labels of the classes, the digits by default.
+    pub labels: Option<Vec<String>>,
+    /// Whether the raw logits are returned rather than the softmax probabilities.
+    #[config(default = "false")]
+    pub logits: bool,
+}
+
+impl MnistOptions {
+    /// Checks that the options are valid.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.margin.is_nan() || self.margin < 1. {
+            return Err(format!(
+                "The margin must be at least 1, got {}",
+                self.margin
+            ));
+        }
+        if self.std.is_nan() || self.std <= 0. {
+            return Err(format!(
+                "The standard deviation must be positive, got {}",
+                self.std
+            ));
+        }
+        if let Some(k) = self.top_k {
+            if !(1..=NUM_CLASSES).contains(&k) {
+                return Err(format!(
+                    "Top-k must be between 1 and {NUM_CLASSES}, got {k}"
+                ));
+            }
+        }
+        if let Some(labels) = &self.labels {
+            if labels.len() != NUM_CLASSES {
+                return Err(format!(
+                    "Expected {NUM_CLASSES} labels, got {}",
+                    labels.len()
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Label of a class.
+    pub fn label(&self, class: usize) -> String {
+        match &self.labels {
+            Some(labels) => labels[class].clone(),
+            None => format!("{class}"),
+        }
+    }
+}
+
+/// Returns the `k` best `(class, score)` pairs, by decreasing score.
+pub fn top_k(scores: &[f32], k: usize) -> Vec<(usize, f32)> {
+    let mut classes: Vec<_> = scores.iter().copied().enumerate().collect();
+    // Stable sort, so that tied classes stay in class order
+    classes.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+    classes.truncate(k);
+
+    classes
+}
@@ -0,0 +1,108 @@
+use alloc::{format, string::String, vec, vec::Vec};
+use burn::tensor::{backend::Backend, module::adaptive_avg_pool2d, Tensor, TensorData};
+
+use super::MnistOptions;
+
+/// Side of the square MNIST digits.
+pub const INPUT_SIZE: usize = 28;
+
+/// Mean of the MNIST pixels in `[0, 1]`, copied from the PyTorch MNIST example
+/// https://github.com/pytorch/examples/blob/54f4572509891883a947411fd7239237dd2a39c3/mnist/main.py#L122
+pub const PIXEL_MEAN: f32 = 0.1307;
+/// Standard deviation of the MNIST pixels in `[0, 1]`.
+pub const PIXEL_STD: f32 = 0.3081;
+
+/// Default side of the square around the digit, relative to the largest side of its bounding box.
+pub const MARGIN: f32 = 1.2;
+
+/// Returns the ink of RGBA pixels drawn over a white background, from 0 (white or transparent) to
+/// 255 (black), as the MNIST digits are white on black.
+pub fn ink(rgba: &[u8]) -> Vec<f32> {
+    rgba.chunks_exact(4)
+        .map(|pixel| {
+            // RGB grayscale coefficients (https://imagej.nih.gov/ij/docs/menus/image.html)
+            let darkness = 0.299 * (255 - pixel[0]) as f32
+                + 0.587 * (255 - pixel[1]) as f32
+                + 0.114 * (255 - pixel[2]) as f32;
+            darkness * pixel[3] as f32 / 255.
+        })
+        .collect()
+}
+
+/// Bounding box `[left, top, right, bottom]` of the inked pixels, or `None` if there are none.
+fn bounding_box(ink: &[f32], width: usize) -> Option<[usize; 4]> {
+    let mut inked = ink
+        .iter()
+        .enumerate()
+        .filter(|(_, value)| **value > 0.)
+        .map(|(index, _)| (index % width, index / width));
+
+    let (x, y) = inked.next()?;
+    Some(inked.fold(
+        [x, y, x + 1, y + 1],
+        |[left, top, right, bottom], (x, y)| {
+            [left.min(x), top.min(y), right.max(x + 1), bottom.max(y + 1)]
+        },
+    ))
+}
+
+/// Converts an RGBA drawing of any size, e.g. the `data` of a canvas `ImageData`, into the
+/// `[1, 28, 28]` ink of a digit, from 0 to 255.
+///
+/// With a `margin`, the drawing is cropped to its ink and centered in a square whose side is the
+/// largest side of the ink times the margin (e.g. [MARGIN]), as the MNIST digits are. Without a
+/// margin, and for blank drawings, the whole drawing is centered in a square. The square is then
+/// downscaled by averaging the pixels of each output pixel.
+pub fn drawing_to_ink<B: Backend>(
+    rgba: &[u8],
+    width: usize,
+    height: usize,
+    margin: Option<f32>,
+    device: &B::Device,
+) -> Result<Tensor<B, 3>, String> {
+    if rgba.len() != width * height * 4 {
+        return Err(format!(
+            "Expected {} bytes of RGBA pixels for a {width}x{height} drawing, got {}",
+            width * height * 4,
+            rgba.len()
+        ));
+    }
+
+    let ink = ink(rgba);
+    let crop = margin.zip(bounding_box(&ink, width));
+    let [left, top, right, bottom] = crop.map_or([0, 0, width, height], |(_, crop)| crop);
+    let (crop_width, crop_height) = (right - left, bottom - top);
+
+    let margin = crop.map_or(1., |(margin, _)| margin);
+    let side = ((crop_width.max(crop_height) as f32 * margin) as usize).max(1);
+    let (x, y) = ((side - crop_width) / 2, (side - crop_height) / 2);
+    let mut square = vec![0f32; side * side];
+    for row in 0..crop_height {
+        let start = (top + row) * width + left;
+        let offset = (y + row) * side + x;
+        square[offset..offset + crop_width].copy_from_slice(&ink[start..start + crop_width]);
+    }
+
+    let square = Tensor::<B, 4>::from_data(
+        TensorData::new(square, [1, 1, side, side]).convert::<B::FloatElem>(),
+        device,
+    );
+
+    Ok(adaptive_avg_pool2d(square, [INPUT_SIZE, INPUT_SIZE]).reshape([1, INPUT_SIZE, INPUT_SIZE]))
+}
+
+/// Converts an RGBA drawing into the normalized `[1, 28, 28]` input of the model, see
+/// [drawing_to_ink], cropped and normalized following the options.
+pub fn drawing_to_input<B: Backend>(
+    rgba: &[u8],
+    width: usize,
+    height: usize,
+    options: &MnistOptions,
+    device: &B::Device,
+) -> Result<Tensor<B, 3>, String> {
+    let margin = options.crop.then_some(options.margin);
+    let ink = drawing_to_ink(rgba, width, height, margin, device)?;
+
+    // Make the pixels between [0, 1], with mean=0 and std=1
+    Ok(((ink / 255) - options.mean) / options.std)
+}
@@ -0,0 +1,112 @@
+use alloc::{format, string::String, vec::Vec};
+use core::{any::type_name, fmt, str::FromStr};
+
+use burn::{
+    module::Module,
+    record::{
+        BinBytesRecorder, BurnRecordNoItem, FullPrecisionSettings, HalfPrecisionSettings,
+        PrecisionSettings, Recorder, RecorderError,
+    },
+    serde::de::DeserializeOwned,
+    tensor::backend::Backend,
+};
+
+use crate::quantize;
+
+/// Precision of the weights saved in a record, see [record_bytes].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Precision {
+    /// `f32` weights, in a Burn record.
+    Full,
+    /// `f16` weights, in a Burn record half the size.
+    Half,
+    /// `i8` weights with one `f32` scale per output channel, about a quarter of the size. The
+    /// other tensors, e.g. the biases, are kept in `f32`. The weights are dequantized when loaded.
+    Int8,
+}
+
+impl Precision {
+    /// All the precisions.
+    pub const ALL: [Precision; 3] = [Self::Full, Self::Half, Self::Int8];
+
+    /// Name of the precision, as parsed by [from_str](Precision::from_str).
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Full => "full",
+            Self::Half => "half",
+            Self::Int8 => "int8",
+        }
+    }
+
+    /// Precision of a record saved by [record_bytes].
+    ///
+    /// # Returns
+    ///
+    /// The precision, or an error if the record is a Burn record with another float type.
+    pub fn of_record(bytes: &[u8]) -> Result<Self, RecorderError> {
+        if bytes.starts_with(quantize::MAGIC) {
+            return Ok(Self::Int8);
+        }
+
+        // The metadata precedes the item, so that it is decoded without the weights
+        let record: BurnRecordNoItem = decode_bin(bytes)?;
+        let float = record.metadata.float;
+        if float == type_name::<<FullPrecisionSettings as PrecisionSettings>::FloatElem>() {
+            Ok(Self::Full)
+        } else if float == type_name::<<HalfPrecisionSettings as PrecisionSettings>::FloatElem>() {
+            Ok(Self::Half)
+        } else {
+            Err(RecorderError::Unknown(format!(
+                "Unsupported record float type {float}"
+            )))
+        }
+    }
+}
+
+impl fmt::Display for Precision {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Precision {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|precision| precision.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|precision| precision.name()).collect();
+                format!(
+                    "Unknown precision {name}, expected one of {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Saves the weights of a model with the given precision, in a record loaded by
+/// [ModelLoader::from_bytes](crate::ModelLoader::from_bytes) whatever its precision.
+pub fn record_bytes<B: Backend, M: Module<B>>(
+    model: M,
+    precision: Precision,
+) -> Result<Vec<u8>, RecorderError> {
+    match precision {
+        Precision::Full => {
+            BinBytesRecorder::<FullPrecisionSettings>::default().record(model.into_record(), ())
+        }
+        Precision::Half => {
+            BinBytesRecorder::<HalfPrecisionSettings>::default().record(model.into_record(), ())
+        }
+        Precision::Int8 => Ok(quantize::encode(&model)),
+    }
+}
+
+/// Decodes an item saved by [BinBytesRecorder], with the same configuration. Unlike the recorder,
+/// which panics on invalid bytes, returns an error.
+pub(crate) fn decode_bin<I: DeserializeOwned>(bytes: &[u8]) -> Result<I, RecorderError> {
+    let (item, _) = bincode::serde::decode_from_slice(bytes, bincode::config::standard())
+        .map_err(|err| RecorderError::Unknown(format!("Invalid record: {err}")))?;
+    Ok(item)
+}
@@ -0,0 +1,231 @@
+use alloc::{format, string::String, vec, vec::Vec};
+
+use burn::{
+    module::{Module, ModuleMapper, ModuleVisitor, ParamId},
+    record::RecorderError,
+    tensor::{backend::Backend, Tensor, TensorData},
+};
+
+/// Start of the records with int8 weights, which are not Burn records.
+pub(crate) const MAGIC: &[u8; 8] = b"BURNQ8v1";
+
+/// Tensor stored as `f32` values, e.g. biases and batch norm statistics.
+const RAW: u8 = 0;
+/// Tensor stored as `i8` values and one `f32` scale per output channel.
+const INT8: u8 = 1;
+
+/// Axis of the output channels of a weight: the first one of convolution weights, and the last
+/// one of linear weights, which Burn stores as `[input, output]`.
+fn channel_axis(rank: usize) -> usize {
+    if rank == 2 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Encodes the float tensors of a module, in the order they are visited.
+///
+/// The weights, of rank 2 or more, are quantized symmetrically per output channel: each channel
+/// is scaled so that its largest absolute value is 127, and rounded to `i8`. The other tensors
+/// are small and kept in full precision.
+///
+/// Layout, in little endian: [MAGIC], the number of tensors as `u32`, then for each tensor its
+/// rank as `u8`, its dimensions as `u32`, its encoding as `u8`, and its values, preceded by the
+/// channel scales for [INT8].
+pub(crate) fn encode<B: Backend, M: Module<B>>(model: &M) -> Vec<u8> {
+    let mut encoder = Encoder {
+        bytes: MAGIC.to_vec(),
+        count: 0,
+    };
+    encoder.bytes.extend(0u32.to_le_bytes());
+    model.visit(&mut encoder);
+
+    let count = encoder.count.to_le_bytes();
+    encoder.bytes[MAGIC.len()..MAGIC.len() + count.len()].copy_from_slice(&count);
+
+    encoder.bytes
+}
+
+/// Loads the tensors encoded by [encode] into a module of the same architecture.
+pub(crate) fn decode<B: Backend, M: Module<B>>(model: M, bytes: &[u8]) -> Result<M, RecorderError> {
+    let mut reader = Reader {
+        bytes: bytes
+            .strip_prefix(MAGIC)
+            .ok_or_else(|| error("Not an int8 record"))?,
+    };
+    let remaining = reader.u32()? as usize;
+
+    let mut decoder = Decoder {
+        reader,
+        remaining,
+        error: None,
+    };
+    let model = model.map(&mut decoder);
+
+    if let Some(err) = decoder.error {
+        return Err(err);
+    }
+    if decoder.remaining > 0 {
+        return Err(error(format!(
+            "The record has {} more tensors than the model",
+            decoder.remaining
+        )));
+    }
+    if !decoder.reader.bytes.is_empty() {
+        return Err(error("The int8 record has trailing bytes"));
+    }
+
+    Ok(model)
+}
+
+fn error(message: impl Into<String>) -> RecorderError {
+    RecorderError::Unknown(message.into())
+}
+
+struct Encoder {
+    bytes: Vec<u8>,
+    count: u32,
+}
+
+impl<B: Backend> ModuleVisitor<B> for Encoder {
+    fn visit_float<const D: usize>(&mut self, _id: &ParamId, tensor: &Tensor<B, D>) {
+        let dims = tensor.dims();
+        let values = tensor.to_data().convert::<f32>().to_vec::<f32>().unwrap();
+
+        self.count += 1;
+        self.bytes.push(D as u8);
+        for dim in dims {
+            self.bytes.extend((dim as u32).to_le_bytes());
+        }
+
+        if D < 2 {
+            self.bytes.push(RAW);
+            for value in values {
+                self.bytes.extend(value.to_le_bytes());
+            }
+            return;
+        }
+
+        // Values are laid out row-major, so a channel spans the product of the following dims
+        let axis = channel_axis(D);
+        let channels = dims[axis];
+        let stride: usize = dims[axis + 1..].iter().product();
+        let channel = |index: usize| (index / stride) % channels;
+
+        let mut scales = vec![0f32; channels];
+        for (index, value) in values.iter().enumerate() {
+            let scale = &mut scales[channel(index)];
+            *scale = scale.max(value.abs());
+        }
+        scales.iter_mut().for_each(|scale| *scale /= 127.);
+
+        self.bytes.push(INT8);
+        for scale in scales.iter() {
+            self.bytes.extend(scale.to_le_bytes());
+        }
+        for (index, value) in values.iter().enumerate() {
+            let scale = scales[channel(index)];
+            let quantized = match scale > 0. {
+                // Round half away from zero, the cast saturates and truncates towards zero
+                true => (value / scale + 0.5 * value.signum()) as i8,
+                false => 0,
+            };
+            self.bytes.push(quantized as u8);
+        }
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], RecorderError> {
+        if self.bytes.len() < len {
+            return Err(error("The int8 record is truncated"));
+        }
+        let (taken, bytes) = self.bytes.split_at(len);
+        self.bytes = bytes;
+
+        Ok(taken)
+    }
+
+    fn u8(&mut self) -> Result<u8, RecorderError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, RecorderError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn f32s(&mut self, len: usize) -> Result<Vec<f32>, RecorderError> {
+        Ok(self
+            .take(len * 4)?
+            .chunks_exact(4)
+            .map(|bytes| f32::from_le_bytes(bytes.try_into().unwrap()))
+            .collect())
+    }
+}
+
+struct Decoder<'a> {
+    reader: Reader<'a>,
+    remaining: usize,
+    error: Option<RecorderError>,
+}
+
+impl Decoder<'_> {
+    /// Reads the values of the next tensor, which must have the given dimensions.
+    fn read(&mut self, dims: &[usize]) -> Result<Vec<f32>, RecorderError> {
+        if self.remaining == 0 {
+            return Err(error("The record has fewer tensors than the model"));
+        }
+        self.remaining -= 1;
+
+        let rank = self.reader.u8()? as usize;
+        let record_dims = (0..rank)
+            .map(|_| self.reader.u32().map(|dim| dim as usize))
+            .collect::<Result<Vec<_>, _>>()?;
+        if record_dims != dims {
+            return Err(error(format!(
+                "The record has a tensor of shape {record_dims:?} where the model has {dims:?}"
+            )));
+        }
+        let len = dims.iter().product();
+
+        match self.reader.u8()? {
+            RAW => self.reader.f32s(len),
+            INT8 => {
+                let axis = channel_axis(rank);
+                let channels = dims[axis];
+                let stride: usize = dims[axis + 1..].iter().product();
+                let scales = self.reader.f32s(channels)?;
+                let values = self.reader.take(len)?;
+
+                Ok(values
+                    .iter()
+                    .enumerate()
+                    .map(|(index, value)| *value as i8 as f32 * scales[(index / stride) % channels])
+                    .collect())
+            }
+            encoding => Err(error(format!("Unknown tensor encoding {encoding}"))),
+        }
+    }
+}
+
+impl<B: Backend> ModuleMapper<B> for Decoder<'_> {
+    fn map_float<const D: usize>(&mut self, _id: &ParamId, tensor: Tensor<B, D>) -> Tensor<B, D> {
+        if self.error.is_some() {
+            return tensor;
+        }
+
+        let dims = tensor.dims();
+        match self.read(&dims) {
+            Ok(values) => Tensor::from_data(TensorData::new(values, dims), &tensor.device()),
+            Err(err) => {
+                self.error = Some(err);
+                tensor
+            }
+        }
+    }
+}
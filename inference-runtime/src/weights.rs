@@ -0,0 +1,162 @@
+use alloc::{format, string::String, vec::Vec};
+use core::fmt::Write;
+
+use js_sys::Function;
+use sha2::{Digest, Sha256};
+use wasm_bindgen::JsValue;
+
+#[cfg(target_family = "wasm")]
+use wasm_bindgen::prelude::*;
+
+/// Largest size reserved for a record before it is received. Larger records grow as their chunks
+/// are pushed, so that a bogus expected size does not allocate the memory of the module at once.
+const MAX_RESERVED: usize = 64 << 20;
+
+/// Hex encoded SHA-256 checksum of a record, as printed by `sha256sum` and verified by [Weights].
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    hex(&Sha256::digest(bytes))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let mut hex = String::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        write!(hex, "{byte:02x}").unwrap();
+    }
+
+    hex
+}
+
+/// Weights structure that corresponds to JavaScript class, holding a record received from
+/// JavaScript instead of being embedded in the wasm module.
+///
+/// The record is received at once with [from_bytes](Weights::from_bytes), or in chunks with
+/// [push](Weights::push), e.g. while reading the body of a `fetch` response. Its size and SHA-256
+/// checksum are verified before the model is built, when given.
+///
+/// ```js
+/// const response = await fetch("model.bin");
+/// // A missing header gives 0 and an invalid one NaN, both unknown sizes
+/// const length = Number(response.headers.get("Content-Length"));
+/// const total = Number.isSafeInteger(length) && length > 0 ? length : undefined;
+/// const weights = new Weights(total, checksum);
+/// weights.on_progress((loaded, total) => console.log(`${loaded} / ${total} bytes`));
+/// const reader = response.body.getReader();
+/// for (let chunk = await reader.read(); !chunk.done; chunk = await reader.read()) {
+///     weights.push(chunk.value);
+/// }
+/// const mnist = Mnist.with_weights(weights);
+/// ```
+///
+/// The `Content-Length` of compressed responses is not the size of the record.
+#[cfg_attr(target_family = "wasm", wasm_bindgen)]
+pub struct Weights {
+    bytes: Vec<u8>,
+    total: Option<usize>,
+    /// Expected checksum, hex encoded in lowercase.
+    checksum: Option<String>,
+    hasher: Sha256,
+    progress: Option<Function>,
+}
+
+#[cfg_attr(target_family = "wasm", wasm_bindgen)]
+impl Weights {
+    /// Constructor called by JavaScripts with the new keyword.
+    ///
+    /// # Arguments
+    ///
+    /// * `total` - Expected size of the record in bytes, if known. 0, as `Number` returns for a
+    ///   missing header, is unknown.
+    /// * `checksum` - Expected SHA-256 checksum of the record, hex encoded, if known.
+    #[cfg_attr(target_family = "wasm", wasm_bindgen(constructor))]
+    pub fn new(total: Option<usize>, checksum: Option<String>) -> Result<Weights, String> {
+        let checksum = checksum.map(|checksum| checksum.trim().to_ascii_lowercase());
+        if let Some(checksum) = &checksum {
+            if checksum.len() != 64 || !checksum.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(format!("Invalid SHA-256 checksum {checksum}"));
+            }
+        }
+
+        let total = total.filter(|total| *total > 0);
+        Ok(Self {
+            bytes: Vec::with_capacity(total.unwrap_or_default().min(MAX_RESERVED)),
+            total,
+            checksum,
+            hasher: Sha256::new(),
+            progress: None,
+        })
+    }
+
+    /// Returns the weights of a record received at once, e.g. the `ArrayBuffer` of a `fetch`
+    /// response wrapped in a `Uint8Array`.
+    pub fn from_bytes(bytes: Vec<u8>, checksum: Option<String>) -> Result<Weights, String> {
+        let mut weights = Self::new(Some(bytes.len()), checksum)?;
+        weights.hasher.update(&bytes);
+        weights.bytes = bytes;
+
+        Ok(weights)
+    }
+
+    /// Sets the callback called after each [pushed](Weights::push) chunk, with the number of bytes
+    /// received so far and the expected total, `undefined` when unknown.
+    pub fn on_progress(&mut self, callback: Function) {
+        self.progress = Some(callback);
+    }
+
+    /// Appends the next chunk of the record.
+    ///
+    /// # Returns
+    ///
+    /// An error if more bytes than the expected total are received.
+    pub fn push(&mut self, chunk: &[u8]) -> Result<(), String> {
+        let loaded = self.bytes.len() + chunk.len();
+        if let Some(total) = self.total.filter(|total| loaded > *total) {
+            return Err(format!(
+                "Received {loaded} bytes of weights, more than the expected {total}"
+            ));
+        }
+        self.hasher.update(chunk);
+        self.bytes.extend_from_slice(chunk);
+
+        if let Some(callback) = &self.progress {
+            let total = self.total.map_or(JsValue::UNDEFINED, JsValue::from);
+            callback
+                .call2(&JsValue::NULL, &JsValue::from(loaded), &total)
+                .map_err(|err| format!("The progress callback failed: {err:?}"))?;
+        }
+
+        Ok(())
+    }
+
+    /// Number of bytes received so far.
+    pub fn loaded(&self) -> usize {
+        self.bytes.len()
+    }
+
+    /// Expected size of the record, if known.
+    pub fn total(&self) -> Option<usize> {
+        self.total
+    }
+}
+
+impl Weights {
+    /// Returns the received record, once verified to be complete and to match the checksum.
+    pub fn into_bytes(self) -> Result<Vec<u8>, String> {
+        let loaded = self.bytes.len();
+        if let Some(total) = self.total.filter(|total| loaded != *total) {
+            return Err(format!(
+                "Received {loaded} bytes of weights out of the expected {total}"
+            ));
+        }
+
+        if let Some(expected) = self.checksum {
+            let checksum = hex(&self.hasher.finalize());
+            if checksum != expected {
+                return Err(format!(
+                    "The weights have the SHA-256 checksum {checksum} instead of {expected}"
+                ));
+            }
+        }
+
+        Ok(self.bytes)
+    }
+}
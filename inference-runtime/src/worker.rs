@@ -0,0 +1,274 @@
+use alloc::{collections::VecDeque, format, string::String, vec::Vec};
+use core::{fmt, str::FromStr};
+
+use js_sys::{Array, ArrayBuffer, Float32Array, Object, Reflect, Uint8Array};
+use wasm_bindgen::{JsCast, JsValue};
+
+/// What a worker does with a frame received while it is busy with another one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum DropPolicy {
+    /// The frame replaces the pending one, which is dropped, so that the most recent frame is
+    /// processed next. Suits live video.
+    #[default]
+    Latest,
+    /// The frame is dropped while another one is pending or processed, so that no frame waits.
+    Skip,
+    /// Every frame is processed in order, e.g. for recorded videos.
+    Queue,
+}
+
+impl DropPolicy {
+    /// All the policies.
+    pub const ALL: [DropPolicy; 3] = [Self::Latest, Self::Skip, Self::Queue];
+
+    /// Name of the policy, as parsed by [from_str](DropPolicy::from_str).
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Latest => "latest",
+            Self::Skip => "skip",
+            Self::Queue => "queue",
+        }
+    }
+}
+
+impl fmt::Display for DropPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for DropPolicy {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|policy| policy.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|policy| policy.name()).collect();
+                format!(
+                    "Unknown drop policy {name}, expected one of {}",
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Frames waiting to be processed by a worker, one at a time, following a [DropPolicy].
+///
+/// With the [Frame]s posted to a Web Worker, the queue also implements its messages, see
+/// [receive](FrameQueue::receive).
+pub struct FrameQueue<T> {
+    policy: DropPolicy,
+    pending: VecDeque<(u32, T)>,
+    in_flight: Option<u32>,
+    cancelled: bool,
+}
+
+impl<T> FrameQueue<T> {
+    pub fn new(policy: DropPolicy) -> Self {
+        Self {
+            policy,
+            pending: VecDeque::new(),
+            in_flight: None,
+            cancelled: false,
+        }
+    }
+
+    pub fn policy(&self) -> DropPolicy {
+        self.policy
+    }
+
+    /// Number of pending frames, not counting the one processed.
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Adds a frame.
+    ///
+    /// # Returns
+    ///
+    /// The frame dropped by the policy, which is either a pending frame or the added one.
+    pub fn push(&mut self, id: u32, frame: T) -> Option<(u32, T)> {
+        match self.policy {
+            DropPolicy::Latest => {
+                let dropped = self.pending.pop_front();
+                self.pending.push_back((id, frame));
+                dropped
+            }
+            DropPolicy::Skip if self.in_flight.is_some() || !self.pending.is_empty() => {
+                Some((id, frame))
+            }
+            DropPolicy::Skip | DropPolicy::Queue => {
+                self.pending.push_back((id, frame));
+                None
+            }
+        }
+    }
+
+    /// Cancels a frame. A pending frame is removed, and the result of the processed one is
+    /// discarded once [finished](FrameQueue::finish).
+    ///
+    /// # Returns
+    ///
+    /// The removed pending frame.
+    pub fn cancel(&mut self, id: u32) -> Option<T> {
+        if self.in_flight == Some(id) {
+            self.cancelled = true;
+            return None;
+        }
+
+        let index = self
+            .pending
+            .iter()
+            .position(|(pending, _)| *pending == id)?;
+        self.pending.remove(index).map(|(_, frame)| frame)
+    }
+
+    /// Takes the next frame to process, unless one is already processed.
+    pub fn start(&mut self) -> Option<(u32, T)> {
+        if self.in_flight.is_some() {
+            return None;
+        }
+
+        let (id, frame) = self.pending.pop_front()?;
+        self.in_flight = Some(id);
+        self.cancelled = false;
+
+        Some((id, frame))
+    }
+
+    /// Marks the processed frame as finished.
+    ///
+    /// # Returns
+    ///
+    /// Whether the frame was cancelled while processed.
+    pub fn finish(&mut self) -> bool {
+        self.in_flight = None;
+        core::mem::take(&mut self.cancelled)
+    }
+}
+
+/// Frame posted to a worker, with its RGBA pixels in a transferred `ArrayBuffer`, e.g. the buffer
+/// of a canvas `ImageData`.
+pub struct Frame {
+    pub pixels: ArrayBuffer,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Frame {
+    /// Copies the pixels into wasm memory.
+    pub fn rgba(&self) -> Vec<u8> {
+        Uint8Array::new(&self.pixels).to_vec()
+    }
+}
+
+impl FrameQueue<Frame> {
+    /// Handles the data of a message event received by a worker:
+    /// * `{id, type: "frame", pixels, width, height}` - Adds a frame.
+    /// * `{id, type: "cancel"}` - [Cancels](FrameQueue::cancel) a frame.
+    ///
+    /// Each frame is answered by one response, with the pixels of the frame so that their buffer
+    /// can be transferred back and reused:
+    /// * `{id, type: "result", output, latency_ms, pixels}` - The `Float32Array` output of the
+    ///   model and the processing time, see [respond](FrameQueue::respond).
+    /// * `{id, type: "dropped", pixels}` - The frame was dropped by the [DropPolicy].
+    /// * `{id, type: "cancelled", pixels}` - The frame was cancelled.
+    /// * `{id, type: "error", message, pixels}` - The frame could not be processed.
+    ///
+    /// # Returns
+    ///
+    /// The responses of the frames dropped or cancelled by the message, to post right away.
+    pub fn receive(&mut self, data: &JsValue) -> Result<Array, String> {
+        let get = |key: &str| {
+            Reflect::get(data, &key.into()).map_err(|_| format!("The message has no {key}"))
+        };
+        let number = |key: &str| {
+            get(key)?
+                .as_f64()
+                .map(|value| value as u32)
+                .ok_or_else(|| format!("The {key} of the message is not a number"))
+        };
+        let id = number("id")?;
+
+        let responses = Array::new();
+        match get("type")?.as_string().as_deref() {
+            Some("frame") => {
+                let pixels = get("pixels")?
+                    .dyn_into::<ArrayBuffer>()
+                    .map_err(|_| "The pixels of the frame are not an ArrayBuffer")?;
+                let frame = Frame {
+                    pixels,
+                    width: number("width")?,
+                    height: number("height")?,
+                };
+                if let Some((id, frame)) = self.push(id, frame) {
+                    responses.push(&response(id, "dropped", &frame.pixels));
+                }
+            }
+            Some("cancel") => {
+                if let Some(frame) = self.cancel(id) {
+                    responses.push(&response(id, "cancelled", &frame.pixels));
+                }
+            }
+            kind => return Err(format!("Unknown message type {kind:?}")),
+        }
+
+        Ok(responses)
+    }
+
+    /// [Finishes](FrameQueue::finish) the processed frame and returns its response, see
+    /// [receive](FrameQueue::receive).
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - Frame ID, as [started](FrameQueue::start).
+    /// * `frame` - The processed frame.
+    /// * `output` - Output of the model, or the error message.
+    /// * `latency_ms` - Processing time.
+    pub fn respond(
+        &mut self,
+        id: u32,
+        frame: Frame,
+        output: Result<Vec<f32>, String>,
+        latency_ms: f64,
+    ) -> JsValue {
+        if self.finish() {
+            return response(id, "cancelled", &frame.pixels).into();
+        }
+
+        let response = response(id, "result", &frame.pixels);
+        match output {
+            Ok(output) => set(
+                &response,
+                "output",
+                Float32Array::from(output.as_slice()).into(),
+            ),
+            Err(message) => {
+                set(&response, "type", "error".into());
+                set(&response, "message", message.into());
+            }
+        }
+        set(&response, "latency_ms", latency_ms.into());
+
+        response.into()
+    }
+}
+
+fn response(id: u32, kind: &str, pixels: &ArrayBuffer) -> Object {
+    let response = Object::new();
+    set(&response, "id", id.into());
+    set(&response, "type", kind.into());
+    set(&response, "pixels", pixels.into());
+
+    response
+}
+
+fn set(object: &Object, key: &str, value: JsValue) {
+    Reflect::set(object, &key.into(), &value).unwrap();
+}
@@ -0,0 +1,166 @@
+use inference_runtime::{
+    mnist::{
+        drawing_to_ink, drawing_to_input, ink, top_k, MnistOptions, INPUT_SIZE, MARGIN, PIXEL_MEAN,
+        PIXEL_STD,
+    },
+    Backend,
+};
+
+/// White RGBA drawing with a black rectangle `[left, top, right, bottom]`.
+fn drawing(width: usize, height: usize, [left, top, right, bottom]: [usize; 4]) -> Vec<u8> {
+    (0..width * height)
+        .flat_map(|index| {
+            let (x, y) = (index % width, index / width);
+            let inked = (left..right).contains(&x) && (top..bottom).contains(&y);
+            if inked {
+                [0, 0, 0, 255]
+            } else {
+                [255, 255, 255, 255]
+            }
+        })
+        .collect()
+}
+
+fn to_ink(rgba: &[u8], width: usize, height: usize, margin: Option<f32>) -> Vec<f32> {
+    drawing_to_ink::<Backend>(rgba, width, height, margin, &Default::default())
+        .unwrap()
+        .into_data()
+        .to_vec()
+        .unwrap()
+}
+
+#[test]
+fn ink_is_zero_on_white_and_transparent_pixels() {
+    let ink = ink(&[255, 255, 255, 255, 0, 0, 0, 0, 0, 0, 0, 255, 255, 0, 0, 255]);
+
+    assert_eq!(ink[0], 0.);
+    assert_eq!(ink[1], 0.);
+    assert!((ink[2] - 255.).abs() < 1e-3);
+    assert!((ink[3] - (0.587 + 0.114) * 255.).abs() < 1e-3);
+}
+
+#[test]
+fn drawings_are_cropped_and_centered() {
+    // The same digit drawn at different places and sizes gives the same input, up to the edges
+    // of the pooling windows
+    let small = to_ink(&drawing(100, 80, [10, 20, 30, 60]), 100, 80, Some(MARGIN));
+    let large = to_ink(
+        &drawing(300, 300, [150, 100, 200, 200]),
+        300,
+        300,
+        Some(MARGIN),
+    );
+
+    assert_eq!(small.len(), INPUT_SIZE * INPUT_SIZE);
+    let difference = small
+        .iter()
+        .zip(&large)
+        .map(|(small, large)| (small - large).abs())
+        .sum::<f32>()
+        / small.len() as f32;
+    assert!(difference < 4., "{difference}");
+
+    // The margins stay black and the center is inked
+    let center = INPUT_SIZE / 2 * INPUT_SIZE + INPUT_SIZE / 2;
+    assert_eq!(small[0], 0.);
+    assert_eq!(small[INPUT_SIZE * INPUT_SIZE - 1], 0.);
+    assert!((small[center] - 255.).abs() < 1e-3);
+
+    // The 2:1 rectangle keeps its ratio
+    let inked_columns = (0..INPUT_SIZE)
+        .filter(|x| small[INPUT_SIZE / 2 * INPUT_SIZE + x] > 127.)
+        .count();
+    assert!((11..=13).contains(&inked_columns), "{inked_columns}");
+}
+
+#[test]
+fn uncropped_drawings_are_scaled_whole() {
+    // Without a margin, the ink of a 56x56 drawing is pooled by 2x2 windows where it is drawn
+    let ink = to_ink(&drawing(56, 56, [0, 0, 28, 56]), 56, 56, None);
+
+    for (index, value) in ink.iter().enumerate() {
+        let expected = if index % INPUT_SIZE < INPUT_SIZE / 2 {
+            255.
+        } else {
+            0.
+        };
+        assert!((value - expected).abs() < 1e-3, "{index}: {value}");
+    }
+}
+
+#[test]
+fn blank_drawings_are_normalized_black() {
+    let options = MnistOptions::new();
+    let input = drawing_to_input::<Backend>(
+        &drawing(40, 30, [0; 4]),
+        40,
+        30,
+        &options,
+        &Default::default(),
+    )
+    .unwrap();
+
+    assert_eq!(input.dims(), [1, INPUT_SIZE, INPUT_SIZE]);
+    for value in input.into_data().iter::<f32>() {
+        assert!((value + PIXEL_MEAN / PIXEL_STD).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn drawings_must_match_their_size() {
+    let options = MnistOptions::new();
+    let error =
+        drawing_to_input::<Backend>(&[0; 12], 2, 2, &options, &Default::default()).unwrap_err();
+
+    assert!(error.contains("16 bytes"), "{error}");
+}
+
+#[test]
+fn default_options_match_the_preprocessing() {
+    let options = MnistOptions::new();
+
+    assert!(options.crop);
+    assert_eq!(options.margin, MARGIN);
+    assert_eq!(options.mean, PIXEL_MEAN);
+    assert_eq!(options.std, PIXEL_STD);
+    assert_eq!(options.top_k, None);
+    assert!(!options.logits);
+    assert_eq!(options.validate(), Ok(()));
+}
+
+#[test]
+fn invalid_options_are_rejected() {
+    let errors = [
+        MnistOptions::new().with_margin(0.5),
+        MnistOptions::new().with_std(0.),
+        MnistOptions::new().with_top_k(Some(0)),
+        MnistOptions::new().with_top_k(Some(11)),
+        MnistOptions::new().with_labels(Some(vec!["zero".into()])),
+    ]
+    .map(|options| options.validate().unwrap_err());
+
+    assert!(errors[0].contains("margin"), "{}", errors[0]);
+    assert!(errors[1].contains("standard deviation"), "{}", errors[1]);
+    assert!(errors[2].contains("between 1 and 10"), "{}", errors[2]);
+    assert!(errors[3].contains("got 11"), "{}", errors[3]);
+    assert!(errors[4].contains("10 labels"), "{}", errors[4]);
+}
+
+#[test]
+fn labels_default_to_the_digits() {
+    let labels = (0..10).map(|digit| format!("label {digit}")).collect();
+
+    assert_eq!(MnistOptions::new().label(7), "7");
+    assert_eq!(
+        MnistOptions::new().with_labels(Some(labels)).label(7),
+        "label 7"
+    );
+}
+
+#[test]
+fn top_k_returns_the_best_classes_first() {
+    let scores = [0.1, 0.4, 0.05, 0.4, 0.05];
+
+    assert_eq!(top_k(&scores, 3), [(1, 0.4), (3, 0.4), (0, 0.1)]);
+    assert_eq!(top_k(&scores, 10).len(), scores.len());
+}
@@ -0,0 +1,355 @@
+use burn::{
+    module::{Module, Param, RunningState},
+    nn::{
+        conv::{Conv2d, Conv2dConfig},
+        BatchNorm, BatchNormConfig, Linear, LinearConfig, PaddingConfig2d,
+    },
+    tensor::{backend::Backend as BackendTrait, Distribution, Tensor, TensorData},
+};
+use inference_runtime::{
+    fold_batch_norm, load_model, record_bytes, sha256_hex, Backend, BackendKind, BackendVisitor,
+    ConvNorm, DropPolicy, FrameQueue, ModelLoader, Precision, Weights,
+};
+
+#[derive(Module, Debug)]
+struct Affine<B: BackendTrait> {
+    linear: Linear<B>,
+}
+
+impl<B: BackendTrait> ModelLoader<B> for Affine<B> {
+    type Config = [usize; 2];
+
+    fn init_model(config: &Self::Config, device: &B::Device) -> Self {
+        Self {
+            linear: LinearConfig::new(config[0], config[1]).init(device),
+        }
+    }
+}
+
+fn forward<B: BackendTrait>(model: &Affine<B>, device: &B::Device) -> Vec<f32> {
+    let input = Tensor::<B, 2>::from_floats([[1., 2., 3.]], device);
+    model
+        .linear
+        .forward(input)
+        .into_data()
+        .to_vec::<f32>()
+        .unwrap()
+}
+
+#[test]
+fn backend_kinds_parse_and_display() {
+    for kind in BackendKind::ALL {
+        assert_eq!(kind.to_string().parse::<BackendKind>(), Ok(kind));
+    }
+    assert_eq!("NdArray".parse::<BackendKind>(), Ok(BackendKind::NdArray));
+    let err = "cuda".parse::<BackendKind>().unwrap_err();
+    assert!(err.contains("ndarray, wgpu, candle, tch"), "{err}");
+}
+
+#[test]
+fn default_features_select_ndarray() {
+    assert_eq!(BackendKind::selected(), BackendKind::NdArray);
+    assert!(BackendKind::available().contains(&BackendKind::NdArray));
+    assert!(BackendKind::selected().is_available());
+}
+
+#[test]
+fn visit_runs_with_the_selected_backend() {
+    struct Weights;
+
+    impl BackendVisitor for Weights {
+        type Output = usize;
+
+        fn visit<B: BackendTrait>(self, device: B::Device) -> usize {
+            Affine::<B>::init_model(&[3, 2], &device).num_params()
+        }
+    }
+
+    assert_eq!(BackendKind::NdArray.visit(Weights), Ok(8));
+    for kind in BackendKind::ALL {
+        if !kind.is_available() {
+            let err = kind.visit(Weights).unwrap_err();
+            assert!(err.contains(&format!("`{kind}` feature")), "{err}");
+        }
+    }
+}
+
+#[test]
+fn models_load_from_record_bytes() {
+    let device = Default::default();
+    let model = Affine::<Backend>::init_model(&[3, 2], &device);
+    let expected = forward(&model, &device);
+    let bytes = record_bytes(model, Precision::Full).unwrap();
+
+    let loaded = Affine::<Backend>::from_bytes(&[3, 2], bytes.clone(), &device).unwrap();
+    assert_eq!(forward(&loaded, &device), expected);
+
+    let loaded: Affine<Backend> = block_on(load_model(&[3, 2], &bytes)).unwrap();
+    assert_eq!(forward(&loaded, &device), expected);
+}
+
+#[test]
+fn models_load_from_records_of_every_precision() {
+    let device = Default::default();
+    let model = Affine::<Backend>::init_model(&[3, 2], &device);
+    let expected = forward(&model, &device);
+
+    // Rounding errors of f16 and of 8 bit weights
+    for (precision, decimals) in [
+        (Precision::Full, 6),
+        (Precision::Half, 2),
+        (Precision::Int8, 1),
+    ] {
+        let bytes = record_bytes(model.clone(), precision).unwrap();
+        assert_eq!(Precision::of_record(&bytes).unwrap(), precision);
+
+        let loaded = Affine::<Backend>::from_bytes(&[3, 2], bytes, &device).unwrap();
+        let output = TensorData::from(forward(&loaded, &device).as_slice());
+        output.assert_approx_eq(&TensorData::from(expected.as_slice()), decimals);
+    }
+}
+
+#[test]
+fn reduced_precision_records_are_smaller() {
+    let device = Default::default();
+    let model = Affine::<Backend>::init_model(&[256, 128], &device);
+    let size = |precision| record_bytes(model.clone(), precision).unwrap().len();
+
+    let full = size(Precision::Full);
+    assert!(size(Precision::Half) * 10 < full * 6);
+    assert!(size(Precision::Int8) * 10 < full * 3);
+}
+
+#[test]
+fn int8_weights_are_quantized_per_output_channel() {
+    let device = Default::default();
+    // Output features of very different scales, a single scale would round the small one to zero
+    let linear = LinearConfig::new(2, 2).init(&device);
+    let model = Affine::<Backend> {
+        linear: Linear {
+            weight: Param::from_tensor(Tensor::from_floats([[127., 0.02], [-127., 0.01]], &device)),
+            ..linear
+        },
+    };
+
+    let bytes = record_bytes(model, Precision::Int8).unwrap();
+    let loaded = Affine::<Backend>::from_bytes(&[2, 2], bytes, &device).unwrap();
+
+    loaded
+        .linear
+        .weight
+        .val()
+        .into_data()
+        .assert_approx_eq(&TensorData::from([[127., 0.02], [-127., 0.01]]), 3);
+}
+
+#[test]
+fn int8_records_must_match_the_architecture() {
+    let device = Default::default();
+    let model = Affine::<Backend>::init_model(&[3, 2], &device);
+    let bytes = record_bytes(model, Precision::Int8).unwrap();
+
+    let err = Affine::<Backend>::from_bytes(&[2, 3], bytes.clone(), &device).unwrap_err();
+    assert!(format!("{err:?}").contains("shape [3, 2]"), "{err:?}");
+    let err = Affine::<Backend>::from_bytes(&[3, 2], bytes[..bytes.len() - 1].to_vec(), &device)
+        .unwrap_err();
+    assert!(format!("{err:?}").contains("truncated"), "{err:?}");
+}
+
+#[test]
+fn invalid_records_are_errors() {
+    let device = Default::default();
+    let model = Affine::<Backend>::init_model(&[3, 2], &device);
+    let bytes = record_bytes(model, Precision::Half).unwrap();
+
+    for garbage in [&b""[..], b"not a record", &[0xff; 64]] {
+        let err = Precision::of_record(garbage).unwrap_err();
+        assert!(format!("{err:?}").contains("Invalid record"), "{err:?}");
+        assert!(Affine::<Backend>::from_bytes(&[3, 2], garbage.to_vec(), &device).is_err());
+    }
+
+    // The metadata is intact, the weights are not
+    let truncated = bytes[..bytes.len() - 1].to_vec();
+    assert_eq!(Precision::of_record(&truncated).unwrap(), Precision::Half);
+    let err = Affine::<Backend>::from_bytes(&[3, 2], truncated, &device).unwrap_err();
+    assert!(format!("{err:?}").contains("Invalid record"), "{err:?}");
+}
+
+#[test]
+fn batch_norms_fold_into_convolutions() {
+    let device = Default::default();
+    let random = |low, high| {
+        Param::from_tensor(Tensor::<Backend, 1>::random(
+            [4],
+            Distribution::Uniform(low, high),
+            &device,
+        ))
+    };
+    let norm = BatchNormConfig::new(4).with_epsilon(1e-3).init(&device);
+    let norm = BatchNorm {
+        gamma: random(0.5, 1.5),
+        beta: random(-1., 1.),
+        running_mean: RunningState::new(random(-1., 1.).val()),
+        running_var: RunningState::new(random(0.5, 2.).val()),
+        ..norm
+    };
+    let input = Tensor::<Backend, 4>::random([2, 4, 8, 8], Distribution::Default, &device);
+
+    for (groups, bias) in [(1, false), (1, true), (4, false)] {
+        let conv: Conv2d<Backend> = Conv2dConfig::new([4, 4], [3, 3])
+            .with_padding(PaddingConfig2d::Explicit(1, 1))
+            .with_groups(groups)
+            .with_bias(bias)
+            .init(&device);
+        let expected = norm.forward(conv.forward(input.clone()));
+
+        let (conv, folded) = fold_batch_norm(conv, norm.clone().into());
+        assert!(conv.bias.is_some());
+        assert!(matches!(folded, ConvNorm::Folded(_)));
+        let output = folded.forward(conv.forward(input.clone()));
+        output.to_data().assert_approx_eq(&expected.to_data(), 4);
+
+        // Folding twice is a no-op
+        let (conv, _) = fold_batch_norm(conv, folded);
+        conv.forward(input.clone())
+            .to_data()
+            .assert_approx_eq(&expected.to_data(), 4);
+    }
+}
+
+#[test]
+fn streamed_weights_are_verified() {
+    let device = Default::default();
+    let model = Affine::<Backend>::init_model(&[3, 2], &device);
+    let bytes = record_bytes(model.clone(), Precision::Full).unwrap();
+    let checksum = sha256_hex(&bytes);
+    assert_eq!(
+        sha256_hex(b"abc"),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+
+    let mut weights = Weights::new(Some(bytes.len()), Some(checksum.to_uppercase())).unwrap();
+    for chunk in bytes.chunks(7) {
+        weights.push(chunk).unwrap();
+    }
+    assert_eq!(weights.loaded(), bytes.len());
+    let loaded = Affine::<Backend>::from_bytes(&[3, 2], weights.into_bytes().unwrap(), &device);
+    assert_eq!(forward(&loaded.unwrap(), &device), forward(&model, &device));
+
+    let weights = Weights::from_bytes(bytes.clone(), Some(checksum.clone())).unwrap();
+    assert_eq!(weights.into_bytes().unwrap(), bytes);
+    let weights = Weights::from_bytes(bytes.clone(), None).unwrap();
+    assert_eq!(weights.into_bytes().unwrap(), bytes);
+}
+
+#[test]
+fn weights_of_unknown_or_bogus_size_are_received() {
+    let bytes = [1u8, 2, 3, 4];
+
+    // A missing Content-Length header is 0 in JavaScript
+    let mut weights = Weights::new(Some(0), None).unwrap();
+    assert_eq!(weights.total(), None);
+    weights.push(&bytes).unwrap();
+    assert_eq!(weights.into_bytes().unwrap(), bytes);
+
+    // The memory is not reserved for sizes which cannot be allocated
+    let mut weights = Weights::new(Some(usize::MAX), None).unwrap();
+    weights.push(&bytes).unwrap();
+    let err = weights.into_bytes().unwrap_err();
+    assert!(err.contains("out of the expected"), "{err}");
+}
+
+#[test]
+fn corrupted_weights_are_rejected() {
+    let bytes = vec![1u8, 2, 3, 4];
+    let checksum = sha256_hex(&bytes);
+
+    let err = Weights::from_bytes(vec![1, 2, 3, 5], Some(checksum.clone()))
+        .unwrap()
+        .into_bytes()
+        .unwrap_err();
+    assert!(err.contains("checksum"), "{err}");
+
+    let mut weights = Weights::new(Some(4), None).unwrap();
+    weights.push(&bytes[..3]).unwrap();
+    let err = weights.into_bytes().unwrap_err();
+    assert!(
+        err.contains("3 bytes of weights out of the expected 4"),
+        "{err}"
+    );
+
+    let mut weights = Weights::new(Some(3), None).unwrap();
+    let err = weights.push(&bytes).unwrap_err();
+    assert!(err.contains("more than the expected 3"), "{err}");
+
+    assert!(Weights::new(None, Some("not a checksum".into())).is_err());
+}
+
+#[test]
+fn drop_policies_parse_and_display() {
+    for policy in DropPolicy::ALL {
+        assert_eq!(policy.to_string().parse::<DropPolicy>(), Ok(policy));
+    }
+    assert_eq!("Latest".parse::<DropPolicy>(), Ok(DropPolicy::Latest));
+    assert!("newest".parse::<DropPolicy>().is_err());
+}
+
+#[test]
+fn frame_queues_drop_frames_by_policy() {
+    // Frames 1 to 3 arrive while frame 0 is processed
+    let run = |policy| {
+        let mut queue = FrameQueue::new(policy);
+        assert_eq!(queue.push(0, ()), None);
+        assert_eq!(queue.start(), Some((0, ())));
+        let dropped: Vec<_> = (1..4)
+            .filter_map(|id| queue.push(id, ()))
+            .map(|(id, _)| id)
+            .collect();
+        assert!(!queue.finish());
+
+        let processed: Vec<_> = std::iter::from_fn(|| {
+            let started = queue.start().map(|(id, _)| id);
+            queue.finish();
+            started
+        })
+        .collect();
+        (dropped, processed)
+    };
+
+    assert_eq!(run(DropPolicy::Latest), (vec![1, 2], vec![3]));
+    assert_eq!(run(DropPolicy::Skip), (vec![1, 2, 3], vec![]));
+    assert_eq!(run(DropPolicy::Queue), (vec![], vec![1, 2, 3]));
+}
+
+#[test]
+fn frame_queues_cancel_pending_and_processed_frames() {
+    let mut queue = FrameQueue::new(DropPolicy::Queue);
+    for id in 0..3 {
+        queue.push(id, id * 10);
+    }
+    assert_eq!(queue.start(), Some((0, 0)));
+    // Only one frame is processed at a time
+    assert_eq!(queue.start(), None);
+
+    assert_eq!(queue.cancel(2), Some(20));
+    assert_eq!(queue.cancel(0), None);
+    assert_eq!(queue.cancel(7), None);
+    assert_eq!(queue.len(), 1);
+    assert!(queue.finish());
+
+    assert_eq!(queue.start(), Some((1, 10)));
+    assert!(!queue.finish());
+    assert!(queue.is_empty());
+}
+
+/// Polls a future which completes without waiting, as the backends other than wgpu do not.
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    use std::task::{Context, Poll, Waker};
+
+    let mut future = std::pin::pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+    match future.as_mut().poll(&mut context) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("The future is not ready"),
+    }
+}
@@ -1,29 +1,21 @@
 use crate::model::Model;
-use burn::{
-    module::Module,
-    record::{BinBytesRecorder, FullPrecisionSettings, Recorder},
-};
+use burn::record::RecorderError;
+use inference_runtime::load_model;
 
-#[cfg(feature = "wgpu")]
-use burn::backend::wgpu::{init_async, AutoGraphicsApi, Wgpu, WgpuDevice};
-
-#[cfg(feature = "wgpu")]
-pub type Backend = Wgpu<f32, i32>;
-
-#[cfg(all(feature = "ndarray", not(feature = "wgpu")))]
-pub type Backend = burn::backend::ndarray::NdArray<f32>;
+pub use inference_runtime::Backend;
 
+#[cfg(feature = "embedded")]
 static STATE_ENCODED: &[u8] = include_bytes!("../model.bin");
 
 /// Builds and loads trained parameters into the model.
+#[cfg(feature = "embedded")]
 pub async fn build_and_load_model() -> Model<Backend> {
-    #[cfg(feature = "wgpu")]
-    init_async::<AutoGraphicsApi>(&WgpuDevice::default(), Default::default()).await;
-
-    let model: Model<Backend> = Model::new(&Default::default());
-    let record = BinBytesRecorder::<FullPrecisionSettings>::default()
-        .load(STATE_ENCODED.to_vec(), &Default::default())
-        .expect("Failed to decode state");
+    load_model(&(), STATE_ENCODED)
+        .await
+        .expect("Failed to decode state")
+}
 
-    model.load_record(record)
+/// Builds the model and loads the parameters of a record received from JavaScript.
+pub async fn build_and_load_model_from(bytes: &[u8]) -> Result<Model<Backend>, RecorderError> {
+    load_model(&(), bytes).await
 }
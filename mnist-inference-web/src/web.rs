@@ -2,47 +2,110 @@
 
 use alloc::string::String;
 use alloc::format;
-use js_sys::Array;
+use alloc::vec::Vec;
+use inference_metrics::{now_ms, Metrics};
+use inference_runtime::mnist::{
+    drawing_to_ink, drawing_to_input, top_k, MnistOptions, NUM_CLASSES,
+};
+use inference_runtime::Weights;
+use js_sys::{Array, Object, Reflect, Uint8Array, JSON};
+use wasm_bindgen::JsValue;
 
 #[cfg(target_family = "wasm")]
 use wasm_bindgen::prelude::*;
 
 use crate::model::Model;
-use crate::state::{build_and_load_model, Backend};
+#[cfg(feature = "embedded")]
+use crate::state::build_and_load_model;
+use crate::state::{build_and_load_model_from, Backend};
 
-use burn::tensor::Tensor;
+use burn::config::{config_to_json, Config};
+use burn::tensor::{activation::softmax, Tensor};
 
 #[cfg_attr(target_family = "wasm", wasm_bindgen(start))]
 pub fn start() {
     console_error_panic_hook::set_once();
 }
 
+#[cfg(target_family = "wasm")]
 #[wasm_bindgen]
 extern "C" {
     #[wasm_bindgen(js_namespace = console)]
     pub fn log(s: &str);
 }
 
+/// There is no console outside of the browser, e.g. in native checks.
+#[cfg(not(target_family = "wasm"))]
+pub fn log(_s: &str) {}
+
 /// Mnist structure that corresponds to JavaScript class.
 /// See:[exporting-rust-struct](https://rustwasm.github.io/wasm-bindgen/contributing/design/exporting-rust-struct.html)
 #[cfg_attr(target_family = "wasm", wasm_bindgen)]
 pub struct Mnist {
+    /// Weights received from JavaScript, loaded on the first inference instead of the embedded
+    /// ones.
+    weights: Option<Vec<u8>>,
     model: Option<Model<Backend>>,
-    last_fps_timestamp: f64,
-    inference_count: u32,
+    options: MnistOptions,
+    metrics: Metrics,
+    /// Time of the last frame rate log.
+    last_log_ms: f64,
 }
 
 #[cfg_attr(target_family = "wasm", wasm_bindgen)]
 impl Mnist {
-    /// Constructor called by JavaScripts with the new keyword.
+    /// Constructor called by JavaScripts with the new keyword, using the weights embedded in the
+    /// wasm module.
+    #[cfg(feature = "embedded")]
     #[cfg_attr(target_family = "wasm", wasm_bindgen(constructor))]
     pub fn new() -> Self {
-        console_error_panic_hook::set_once();
-        Self {
-            model: None,
-            last_fps_timestamp: 0.0,
-            inference_count: 0,
-        }
+        Self::init(None)
+    }
+
+    /// Returns a model using weights received from JavaScript, e.g. with `fetchWeights`.
+    ///
+    /// # Arguments
+    ///
+    /// * `weights` - MNIST record, loaded on the first inference.
+    ///
+    /// # Returns
+    ///
+    /// An error if the weights are incomplete or do not match their checksum.
+    pub fn with_weights(weights: Weights) -> Result<Mnist, String> {
+        Ok(Self::init(Some(weights.into_bytes()?)))
+    }
+
+    /// Returns the inference metrics, as `{fps, frames, stages, counters}`, where `stages` are the
+    /// `preprocess`, `forward` and `postprocess` latencies, each as
+    /// `{count, mean_ms, p50_ms, p95_ms, p99_ms, max_ms}`.
+    pub fn metrics(&self) -> Object {
+        self.metrics.to_js(now_ms())
+    }
+
+    /// Sets the inference options, see [MnistOptions]. `undefined` restores the default options.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Plain object with the snake case names of the options, e.g.
+    ///   `{top_k: 3, logits: true}`. The missing options keep their default value.
+    pub fn set_options(&mut self, options: JsValue) -> Result<(), String> {
+        let options = if options.is_undefined() || options.is_null() {
+            MnistOptions::new()
+        } else {
+            // The missing options keep their default value
+            let defaults = JSON::parse(&config_to_json(&MnistOptions::new()))
+                .map_err(|_| "Failed to serialize the default options")?;
+            let options = Object::assign(&defaults.into(), &options.into());
+            let json: String = JSON::stringify(&options)
+                .map_err(|_| "The options cannot be serialized to JSON")?
+                .into();
+            MnistOptions::load_binary(json.as_bytes())
+                .map_err(|err| format!("Invalid options: {err}"))?
+        };
+        options.validate()?;
+        self.options = options;
+
+        Ok(())
     }
 
     /// Returns the inference results.
@@ -51,70 +114,227 @@ impl Mnist {
     ///
     /// # Arguments
     ///
-    /// * `input` - A f32 slice of input 28x28 image
+    /// * `rgba` - RGBA pixels of the drawing, of any size, e.g. the `data` of a canvas
+    ///   `ImageData`. The digit is cropped, scaled and normalized, see [drawing_to_input].
+    /// * `width` - Drawing width.
+    /// * `height` - Drawing height.
+    ///
+    /// # Returns
+    ///
+    /// The probabilities of the digits, or their logits with the `logits` option. With the
+    /// `top_k` option, the best digits as `{index, label, score}` objects, by decreasing score.
     ///
     /// See bindgen support types for passing and returning arrays:
     /// * [number-slices](https://rustwasm.github.io/wasm-bindgen/reference/types/number-slices.html)
     /// * [boxed-number-slices](https://rustwasm.github.io/wasm-bindgen/reference/types/boxed-number-slices.html)
     ///
-    pub async fn inference(&mut self, input: &[f32]) -> Result<Array, String> {
-        if self.model.is_none() {
-            self.model = Some(build_and_load_model().await);
+    pub async fn inference(
+        &mut self,
+        rgba: &[u8],
+        width: u32,
+        height: u32,
+    ) -> Result<Array, String> {
+        self.load_model().await?;
+
+        let device = Default::default();
+
+        let start_time = now_ms();
+
+        // Crop, scale and normalize the drawing into a 3d tensor [batch, height, width]
+        let input = drawing_to_input::<Backend>(
+            rgba,
+            width as usize,
+            height as usize,
+            &self.options,
+            &device,
+        )?;
+        let preprocessed_time = now_ms();
+
+        let scores = self.forward(input).await;
+        let forwarded_time = now_ms();
+
+        let array = self.output(&scores[0]);
+        self.record(start_time, preprocessed_time, forwarded_time);
+
+        Ok(array)
+    }
+
+    /// Returns the inference results of several drawings, classified with a single forward pass.
+    ///
+    /// # Arguments
+    ///
+    /// * `images` - Drawings as `{data, width, height}` objects, e.g. canvas `ImageData`s.
+    ///
+    /// # Returns
+    ///
+    /// The results of each drawing, see [inference](Mnist::inference).
+    pub async fn inference_batch(&mut self, images: Array) -> Result<Array, String> {
+        self.load_model().await?;
+
+        let device = Default::default();
+
+        let start_time = now_ms();
+
+        let inputs = images
+            .iter()
+            .enumerate()
+            .map(|(index, image)| {
+                let get = |key: &str| {
+                    Reflect::get(&image, &key.into())
+                        .ok()
+                        .filter(|value| !value.is_undefined())
+                        .ok_or_else(|| format!("The image {index} has no {key}"))
+                };
+                let size = |key: &str| {
+                    get(key)?
+                        .as_f64()
+                        .map(|value| value as usize)
+                        .ok_or_else(|| format!("The {key} of the image {index} is not a number"))
+                };
+
+                let rgba = Uint8Array::new(&get("data")?).to_vec();
+                drawing_to_input::<Backend>(
+                    &rgba,
+                    size("width")?,
+                    size("height")?,
+                    &self.options,
+                    &device,
+                )
+                .map_err(|err| format!("Invalid image {index}: {err}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if inputs.is_empty() {
+            return Ok(Array::new());
         }
+        let preprocessed_time = now_ms();
 
-        let model = self.model.as_ref().unwrap();
+        let scores = self.forward(Tensor::cat(inputs, 0)).await;
+        let forwarded_time = now_ms();
 
+        let array = scores.iter().map(|scores| self.output(scores)).collect();
+        self.record(start_time, preprocessed_time, forwarded_time);
+
+        Ok(array)
+    }
+
+    /// Returns the 28x28 RGBA pixels of the digit given to the model for a drawing, white on
+    /// black as the MNIST digits, e.g. to show them in a canvas.
+    ///
+    /// # Arguments
+    ///
+    /// * `rgba` - RGBA pixels of the drawing, as given to [inference](Mnist::inference).
+    /// * `width` - Drawing width.
+    /// * `height` - Drawing height.
+    pub async fn preview(&self, rgba: &[u8], width: u32, height: u32) -> Result<Vec<u8>, String> {
         let device = Default::default();
+        let margin = self.options.crop.then_some(self.options.margin);
+        let ink =
+            drawing_to_ink::<Backend>(rgba, width as usize, height as usize, margin, &device)?;
+        let ink = ink.into_data_async().await;
 
-        // Start timestamp
-        let start_time = js_sys::Date::now();
+        Ok(ink
+            .iter::<f32>()
+            .flat_map(|value| {
+                // Casts saturate, so only rounding is needed
+                let value = (value + 0.5) as u8;
+                [value, value, value, 255]
+            })
+            .collect())
+    }
+}
 
-        // Reshape from the 1D array to 3d tensor [batch, height, width]
-        let input = Tensor::<Backend, 1>::from_floats(input, &device).reshape([1, 28, 28]);
+impl Mnist {
+    fn init(weights: Option<Vec<u8>>) -> Self {
+        console_error_panic_hook::set_once();
+        Self {
+            weights,
+            model: None,
+            options: MnistOptions::new(),
+            metrics: Metrics::new("mnist"),
+            last_log_ms: now_ms(),
+        }
+    }
 
-        // Normalize input: make between [0,1] and make the mean=0 and std=1
-        // values mean=0.1307,std=0.3081 were copied from Pytorch Mist Example
-        // https://github.com/pytorch/examples/blob/54f4572509891883a947411fd7239237dd2a39c3/mnist/main.py#L122
+    /// Builds the model on the first inference.
+    async fn load_model(&mut self) -> Result<(), String> {
+        if self.model.is_none() {
+            let model = match self.weights.as_deref() {
+                Some(weights) => build_and_load_model_from(weights)
+                    .await
+                    .map_err(|err| format!("Failed to load the weights: {err}"))?,
+                #[cfg(feature = "embedded")]
+                None => build_and_load_model().await,
+                #[cfg(not(feature = "embedded"))]
+                None => unreachable!("Without embedded weights, models are built with weights"),
+            };
+            self.weights = None;
+            self.model = Some(model);
+        }
 
-        let input = ((input / 255) - 0.1307) / 0.3081;
+        Ok(())
+    }
+
+    /// Runs the model on a `[batch, height, width]` input, and returns the scores of each digit
+    /// of the batch, following the `logits` option.
+    async fn forward(&self, input: Tensor<Backend, 3>) -> Vec<Vec<f32>> {
+        let model = self.model.as_ref().unwrap();
 
         // Run the tensor input through the model
         let output: Tensor<Backend, 2> = model.forward(input);
 
         // Convert the model output into probability distribution using softmax formula
-        let output = burn::tensor::activation::softmax(output, 1);
+        let output = if self.options.logits {
+            output
+        } else {
+            softmax(output, 1)
+        };
 
-        // Flatten output tensor with [1, 10] shape into boxed slice of [f32]
+        // Split the output tensor with [batch, 10] shape into the scores of each digit
         let output = output.into_data_async().await;
+        let scores: Vec<f32> = output.iter::<f32>().collect();
 
-        // End timestamp
-        let end_time = js_sys::Date::now();
-
-        // Display average inference time
-        let duration = end_time - start_time;
-        log(&format!("Inference time: {} ms", duration));
+        scores.chunks(NUM_CLASSES).map(<[f32]>::to_vec).collect()
+    }
 
-        // Track FPS over the last 5 seconds
-        self.inference_count += 1;
-        if end_time - self.last_fps_timestamp >= 5000.0 {
-            if self.last_fps_timestamp == 0.0 {
-                self.last_fps_timestamp = start_time
+    /// Converts the scores of a digit into its result, see [inference](Mnist::inference).
+    fn output(&self, scores: &[f32]) -> Array {
+        let Some(k) = self.options.top_k else {
+            return scores.iter().map(|score| JsValue::from(*score)).collect();
+        };
 
-            } else {
-                let fps = self.inference_count as f64 / 5.0;
-                log(&format!("FPS over last 5 seconds: {:.2}", fps));
+        top_k(scores, k)
+            .into_iter()
+            .map(|(class, score)| {
+                let result = Object::new();
+                for (key, value) in [
+                    ("index", JsValue::from(class as u32)),
+                    ("label", self.options.label(class).into()),
+                    ("score", score.into()),
+                ] {
+                    Reflect::set(&result, &key.into(), &value).unwrap();
+                }
+                result
+            })
+            .collect()
+    }
 
-                // Reset FPS tracking
-                self.inference_count = 0;
-                self.last_fps_timestamp = js_sys::Date::now();
-            }
-        }
+    /// Records the metrics of an inference, from the given start, preprocessed and forwarded
+    /// times to now.
+    fn record(&mut self, start_time: f64, preprocessed_time: f64, forwarded_time: f64) {
+        let end_time = now_ms();
+        self.metrics.observe("preprocess", preprocessed_time - start_time);
+        self.metrics.observe("forward", forwarded_time - preprocessed_time);
+        self.metrics.observe("postprocess", end_time - forwarded_time);
+        self.metrics.record_frame(end_time);
 
-        let array = Array::new();
-        for value in output.iter::<f32>() {
-            array.push(&value.into());
+        // Display the inference time, and the frame rate every 5 seconds
+        log(&format!("Inference time: {} ms", end_time - start_time));
+        if end_time - self.last_log_ms >= 5000.0 {
+            log(&format!(
+                "FPS over last 5 seconds: {:.2}",
+                self.metrics.fps(end_time)
+            ));
+            self.last_log_ms = end_time;
         }
-
-        Ok(array)
     }
 }
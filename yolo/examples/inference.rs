@@ -1,7 +1,9 @@
 use std::path::Path;
 
-use image::{DynamicImage, ImageBuffer};
-use yolo::yolox_model::{boxes::nms, yolox::Yolox, BoundingBox};
+use yolo::{
+    render::{Annotation, RendererConfig},
+    yolox_model::{boxes::nms, yolox::Yolox},
+};
 
 use burn::{
     backend::NdArray,
@@ -24,75 +26,6 @@ fn to_tensor<B: Backend, T: Element>(
     .permute([2, 0, 1])
 }
 
-/// Draws bounding boxes on the given image.
-///
-/// # Arguments
-///
-/// * `image`: Original input image.
-/// * `boxes` - Bounding boxes, grouped per class.
-/// * `color` - [R, G, B] color values to draw the boxes.
-/// * `ratio` - [x, y] aspect ratio to scale the predicted boxes.
-///
-/// # Returns
-///
-/// The image annotated with bounding boxes.
-fn draw_boxes(
-    image: DynamicImage,
-    boxes: &[Vec<BoundingBox>],
-    color: &[u8; 3],
-    ratio: &[f32; 2], // (x, y) ratio
-) -> DynamicImage {
-    // Assumes x1 <= x2 and y1 <= y2
-    fn draw_rect(
-        image: &mut ImageBuffer<image::Rgb<u8>, Vec<u8>>,
-        x1: u32,
-        x2: u32,
-        y1: u32,
-        y2: u32,
-        color: &[u8; 3],
-    ) {
-        for x in x1..=x2 {
-            let pixel = image.get_pixel_mut(x, y1);
-            *pixel = image::Rgb(*color);
-            let pixel = image.get_pixel_mut(x, y2);
-            *pixel = image::Rgb(*color);
-        }
-        for y in y1..=y2 {
-            let pixel = image.get_pixel_mut(x1, y);
-            *pixel = image::Rgb(*color);
-            let pixel = image.get_pixel_mut(x2, y);
-            *pixel = image::Rgb(*color);
-        }
-    }
-
-    // Annotate the original image and print boxes information.
-    let (image_h, image_w) = (image.height(), image.width());
-    let mut image = image.to_rgb8();
-    for (class_index, bboxes_for_class) in boxes.iter().enumerate() {
-        for b in bboxes_for_class.iter() {
-            let xmin = (b.xmin * ratio[0]).clamp(0., image_w as f32 - 1.);
-            let ymin = (b.ymin * ratio[1]).clamp(0., image_h as f32 - 1.);
-            let xmax = (b.xmax * ratio[0]).clamp(0., image_w as f32 - 1.);
-            let ymax = (b.ymax * ratio[1]).clamp(0., image_h as f32 - 1.);
-
-            println!(
-                "Predicted {} ({:.2}) at [{:.2}, {:.2}, {:.2}, {:.2}]",
-                class_index, b.confidence, xmin, ymin, xmax, ymax,
-            );
-
-            draw_rect(
-                &mut image,
-                xmin as u32,
-                xmax as u32,
-                ymin as u32,
-                ymax as u32,
-                color,
-            );
-        }
-    }
-    DynamicImage::ImageRgb8(image)
-}
-
 pub fn main() {
     // Parse arguments
     let img_path = std::env::args().nth(1).expect("No image path provided");
@@ -134,14 +67,22 @@ pub fn main() {
     let scores = cls_scores * obj_scores;
     let boxes = nms(boxes, scores, 0.65, 0.5);
 
-    // Draw outputs and save results
+    // Scale the boxes back to the original image
     let (h, w) = (img.height(), img.width());
-    let img_out = draw_boxes(
-        img,
-        &boxes[0],
-        &[239u8, 62u8, 5u8],
-        &[w as f32 / WIDTH as f32, h as f32 / HEIGHT as f32],
-    );
+    let ratio = [w as f32 / WIDTH as f32, h as f32 / HEIGHT as f32];
+    let mut annotations = Annotation::from_class_boxes(&boxes[0]);
+    for annotation in annotations.iter_mut() {
+        let b = &mut annotation.bbox;
+        (b.xmin, b.xmax) = (b.xmin * ratio[0], b.xmax * ratio[0]);
+        (b.ymin, b.ymax) = (b.ymin * ratio[1], b.ymax * ratio[1]);
+        println!(
+            "Predicted {} ({:.2}) at [{:.2}, {:.2}, {:.2}, {:.2}]",
+            annotation.class, b.confidence, b.xmin, b.ymin, b.xmax, b.ymax,
+        );
+    }
+
+    // Draw outputs and save results
+    let img_out = RendererConfig::new().init().render(&img, &annotations);
 
     let img_path = Path::new(&img_path);
     let _ = img_out.save(img_path.with_extension("output.png"));
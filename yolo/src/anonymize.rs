@@ -0,0 +1,138 @@
+use burn::config::Config;
+use image::{
+    imageops::{self, FilterType},
+    DynamicImage, GenericImage, GenericImageView, Rgba, RgbaImage,
+};
+
+use crate::yolox_model::BoundingBox;
+
+/// How the detected regions are redacted.
+#[derive(Config, Debug, PartialEq, Eq)]
+pub enum Redaction {
+    /// Gaussian blur, with a strength relative to the region size.
+    Blur,
+    /// Mosaic of uniform blocks.
+    Pixelate,
+    /// Solid color.
+    Fill,
+}
+
+/// [Anonymizer](Anonymizer) configuration.
+#[derive(Config, Debug)]
+pub struct AnonymizerConfig {
+    /// How the regions are redacted.
+    #[config(default = "Redaction::Blur")]
+    pub redaction: Redaction,
+    /// Padding added on each side of the boxes, as a fraction of their width and height.
+    #[config(default = "0.1")]
+    pub padding: f32,
+    /// Whether only the ellipse inscribed in each (padded) box is redacted, which better fits
+    /// faces, rather than the whole box.
+    #[config(default = "true")]
+    pub elliptical: bool,
+    /// Standard deviation of the [blur](Redaction::Blur), as a fraction of the smaller side of
+    /// the region.
+    #[config(default = "0.2")]
+    pub blur_strength: f32,
+    /// Number of [pixelation](Redaction::Pixelate) blocks along the smaller side of the region.
+    #[config(default = "8")]
+    pub pixel_blocks: u32,
+    /// Color of the [fill](Redaction::Fill).
+    #[config(default = "[0, 0, 0]")]
+    pub fill_color: [u8; 3],
+}
+
+impl AnonymizerConfig {
+    /// Initialize a new [anonymizer](Anonymizer).
+    pub fn init(&self) -> Anonymizer {
+        Anonymizer {
+            config: self.clone(),
+        }
+    }
+}
+
+/// Redacts detected regions, typically faces, of images.
+#[derive(Debug, Clone)]
+pub struct Anonymizer {
+    config: AnonymizerConfig,
+}
+
+impl Anonymizer {
+    /// Returns a copy of the image with the given boxes redacted.
+    pub fn anonymize(&self, image: &DynamicImage, boxes: &[BoundingBox]) -> RgbaImage {
+        let mut image = image.to_rgba8();
+        self.apply(&mut image, boxes);
+        image
+    }
+
+    /// Redacts the given boxes of the image, in place.
+    ///
+    /// Boxes may be partially or entirely outside of the image, and have inverted coordinates.
+    pub fn apply(&self, image: &mut RgbaImage, boxes: &[BoundingBox]) {
+        for bbox in boxes {
+            self.redact(image, bbox);
+        }
+    }
+
+    /// The anonymizer configuration.
+    pub fn config(&self) -> &AnonymizerConfig {
+        &self.config
+    }
+
+    fn redact(&self, image: &mut RgbaImage, bbox: &BoundingBox) {
+        // Padded region, possibly outside of the image
+        let (xmin, xmax) = (bbox.xmin.min(bbox.xmax), bbox.xmin.max(bbox.xmax));
+        let (ymin, ymax) = (bbox.ymin.min(bbox.ymax), bbox.ymin.max(bbox.ymax));
+        let pad_x = (xmax - xmin) * self.config.padding;
+        let pad_y = (ymax - ymin) * self.config.padding;
+        let (xmin, xmax) = (xmin - pad_x, xmax + pad_x);
+        let (ymin, ymax) = (ymin - pad_y, ymax + pad_y);
+
+        // Visible pixels of the region
+        let x0 = xmin.floor().max(0.) as u32;
+        let y0 = ymin.floor().max(0.) as u32;
+        let x1 = (xmax.ceil().max(0.) as u32).min(image.width());
+        let y1 = (ymax.ceil().max(0.) as u32).min(image.height());
+        if x0 >= x1 || y0 >= y1 {
+            return;
+        }
+        let (width, height) = (x1 - x0, y1 - y0);
+
+        let region = image.view(x0, y0, width, height).to_image();
+        let redacted = match self.config.redaction {
+            Redaction::Blur => {
+                let sigma = self.config.blur_strength * width.min(height) as f32;
+                imageops::blur(&region, sigma.max(0.1))
+            }
+            Redaction::Pixelate => {
+                // Blocks are square, so the number of blocks along the larger side is scaled
+                let blocks = self.config.pixel_blocks.max(1) as f32;
+                let block_size = (width.min(height) as f32 / blocks).max(1.);
+                let columns = (width as f32 / block_size).round().max(1.) as u32;
+                let rows = (height as f32 / block_size).round().max(1.) as u32;
+                let small = imageops::resize(&region, columns, rows, FilterType::Triangle);
+                imageops::resize(&small, width, height, FilterType::Nearest)
+            }
+            Redaction::Fill => {
+                let [r, g, b] = self.config.fill_color;
+                RgbaImage::from_pixel(width, height, Rgba([r, g, b, 255]))
+            }
+        };
+
+        if !self.config.elliptical {
+            image.copy_from(&redacted, x0, y0).unwrap();
+            return;
+        }
+
+        // Only copy the pixels whose center is inside the ellipse
+        let (cx, cy) = ((xmin + xmax) / 2., (ymin + ymax) / 2.);
+        let (rx, ry) = (((xmax - xmin) / 2.).max(0.5), ((ymax - ymin) / 2.).max(0.5));
+        for (x, y, pixel) in redacted.enumerate_pixels() {
+            let dx = (x0 + x) as f32 + 0.5 - cx;
+            let dy = (y0 + y) as f32 + 0.5 - cy;
+            if (dx / rx).powi(2) + (dy / ry).powi(2) <= 1. {
+                image.put_pixel(x0 + x, y0 + y, *pixel);
+            }
+        }
+    }
+}
@@ -0,0 +1,213 @@
+//! Benchmark of the YOLOX-Tiny and MobileFaceNet forward passes across backends and input sizes,
+//! to pick the fastest backend for deployment. Run it in release mode, with the features of the
+//! backends to compare, e.g.
+//! `cargo run --release --features candle,wgpu --bin backend-bench -- --backends ndarray,candle`.
+//!
+//! Usage: `backend-bench [--backends ndarray,wgpu,candle,tch] [--yolox-sizes 416,640]
+//! [--face-sizes 112] [--batch-size 1] [--warmup 2] [--iterations 10]`
+//!
+//! Options:
+//! * `--backends <names>` - Backends to benchmark (default: all the enabled ones).
+//! * `--yolox-sizes <sizes>` - Square YOLOX input sizes, multiples of 32 (default: `416,640`).
+//!   Empty to skip YOLOX.
+//! * `--face-sizes <sizes>` - Square MobileFaceNet input sizes (default: `112`). Empty to skip
+//!   MobileFaceNet.
+//! * `--batch-size <n>` - Number of images per forward pass (default: 1).
+//! * `--warmup <n>` - Untimed forward passes before the timed ones, e.g. for autotuning
+//!   (default: 2).
+//! * `--iterations <n>` - Timed forward passes (default: 10).
+//!
+//! The models have random weights, which does not change their speed, and their batch norms are
+//! folded into the convolutions as when deployed. Each forward pass reads its output, so that
+//! asynchronous backends are timed until completion.
+//!
+//! The statistics are printed to stdout as CSV, one line per backend, model and input size. The
+//! wgpu kernels are fused and autotuned, which also works on CPU adapters (e.g. lavapipe).
+
+use std::{
+    panic::{catch_unwind, AssertUnwindSafe},
+    time::Instant,
+};
+
+use burn::tensor::{backend::Backend, Tensor};
+use facenet_burn::mobilefacenet::MobileFaceNetConfig;
+use inference_runtime::{BackendKind, BackendVisitor};
+use yolo::yolox_model::yolox::YoloxConfig;
+
+const USAGE: &str = "Usage: backend-bench [--backends ndarray,wgpu,candle,tch] \
+[--yolox-sizes 416,640] [--face-sizes 112] [--batch-size 1] [--warmup 2] [--iterations 10]";
+
+/// Models and settings to benchmark on a backend.
+#[derive(Clone)]
+struct Bench {
+    yolox_sizes: Vec<usize>,
+    face_sizes: Vec<usize>,
+    batch_size: usize,
+    warmup: usize,
+    iterations: usize,
+}
+
+/// Forward pass timings of a model.
+struct Timings {
+    model: &'static str,
+    input_size: usize,
+    /// Duration of each timed forward pass, in milliseconds, sorted.
+    durations_ms: Vec<f64>,
+}
+
+impl Bench {
+    /// Times `forward`, which runs a forward pass and reads its output.
+    ///
+    /// # Returns
+    ///
+    /// The timings, or `None` if the backend does not support an operation of the model, which
+    /// makes it panic (e.g. padded max pooling with Candle).
+    fn time(
+        &self,
+        model: &'static str,
+        input_size: usize,
+        mut forward: impl FnMut(),
+    ) -> Option<Timings> {
+        let durations_ms = catch_unwind(AssertUnwindSafe(|| {
+            for _ in 0..self.warmup {
+                forward();
+            }
+            (0..self.iterations)
+                .map(|_| {
+                    let start = Instant::now();
+                    forward();
+                    start.elapsed().as_secs_f64() * 1000.
+                })
+                .collect::<Vec<_>>()
+        }));
+
+        match durations_ms {
+            Ok(mut durations_ms) => {
+                durations_ms.sort_by(f64::total_cmp);
+                Some(Timings {
+                    model,
+                    input_size,
+                    durations_ms,
+                })
+            }
+            Err(_) => {
+                eprintln!("Skipping {model}, which the backend does not support");
+                None
+            }
+        }
+    }
+}
+
+impl BackendVisitor for Bench {
+    type Output = Vec<Timings>;
+
+    fn visit<B: Backend>(self, device: B::Device) -> Vec<Timings> {
+        let mut timings = Vec::new();
+
+        let yolox = YoloxConfig::yolox_tiny().init::<B>(&device).fuse();
+        for &size in self.yolox_sizes.iter() {
+            eprintln!("YOLOX-Tiny {size}x{size}");
+            let input = Tensor::<B, 4>::ones([self.batch_size, 3, size, size], &device) * 114;
+            timings.extend(self.time("yolox_tiny", size, || {
+                yolox.forward(input.clone()).into_data();
+            }));
+        }
+
+        for &size in self.face_sizes.iter() {
+            eprintln!("MobileFaceNet {size}x{size}");
+            let model = MobileFaceNetConfig::new()
+                .with_input_size(size)
+                .init::<B>(&device)
+                .fuse();
+            let input = Tensor::<B, 4>::zeros([self.batch_size, 3, size, size], &device);
+            timings.extend(self.time("mobilefacenet", size, || {
+                model.forward(input.clone()).into_data();
+            }));
+        }
+
+        timings
+    }
+}
+
+/// Parses a comma-separated list, empty for no values.
+fn parse_list<T: std::str::FromStr>(value: &str, name: &str) -> Vec<T>
+where
+    T::Err: std::fmt::Display,
+{
+    value
+        .split(',')
+        .filter(|item| !item.trim().is_empty())
+        .map(|item| {
+            item.trim()
+                .parse()
+                .map_err(|err| format!("Invalid {name} {item}.\nError: {err}"))
+                .unwrap()
+        })
+        .collect()
+}
+
+pub fn main() {
+    // Parse arguments
+    let mut backends = BackendKind::available();
+    let mut bench = Bench {
+        yolox_sizes: vec![416, 640],
+        face_sizes: vec![112],
+        batch_size: 1,
+        warmup: 2,
+        iterations: 10,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .unwrap_or_else(|| panic!("No value provided for {arg}\n{USAGE}"))
+        };
+        match arg.as_str() {
+            "--backends" => backends = parse_list(&value(), "backend"),
+            "--yolox-sizes" => bench.yolox_sizes = parse_list(&value(), "input size"),
+            "--face-sizes" => bench.face_sizes = parse_list(&value(), "input size"),
+            "--batch-size" => bench.batch_size = value().parse().expect("Invalid batch size"),
+            "--warmup" => bench.warmup = value().parse().expect("Invalid number of iterations"),
+            "--iterations" => {
+                bench.iterations = value().parse().expect("Invalid number of iterations")
+            }
+            _ => panic!("Unknown argument {arg}\n{USAGE}"),
+        }
+    }
+    assert!(bench.batch_size > 0, "The batch size must be positive");
+    assert!(
+        bench.iterations > 0,
+        "The number of iterations must be positive"
+    );
+    if let Some(size) = bench.yolox_sizes.iter().find(|size| *size % 32 != 0) {
+        panic!("YOLOX input sizes must be multiples of 32, got {size}");
+    }
+
+    println!(
+        "backend,model,input_size,batch_size,iterations,mean_ms,median_ms,min_ms,max_ms,\
+         images_per_second"
+    );
+    for backend in backends {
+        eprintln!("Benchmarking {backend}");
+        let timings = backend
+            .visit(bench.clone())
+            .map_err(|err| format!("Failed to benchmark {backend}.\nError: {err}"))
+            .unwrap();
+
+        for timing in timings {
+            let durations = &timing.durations_ms;
+            let mean = durations.iter().sum::<f64>() / durations.len() as f64;
+            let median = durations[durations.len() / 2];
+            println!(
+                "{backend},{},{},{},{},{mean:.3},{median:.3},{:.3},{:.3},{:.2}",
+                timing.model,
+                timing.input_size,
+                bench.batch_size,
+                durations.len(),
+                durations[0],
+                durations[durations.len() - 1],
+                bench.batch_size as f64 * 1000. / mean,
+            );
+        }
+    }
+}
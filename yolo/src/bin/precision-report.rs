@@ -0,0 +1,228 @@
+//! Reports the accuracy lost by saving the weights of the models in half precision or int8, to
+//! pick the smallest record which is still accurate enough for deployment.
+//!
+//! Usage: `precision-report [--mnist-weights model.bin] [--yolox-weights yolox_tiny.bin]
+//! [--face-weights mobilefacenet.bin] [--inputs 8] [--seed 0]`
+//!
+//! Options:
+//! * `--mnist-weights <file>` - Full precision MNIST record (default: `model.bin`).
+//! * `--yolox-weights <file>` - Full precision YOLOX-Tiny record written by `yolo convert`
+//!   (default: `yolox_tiny.bin`).
+//! * `--face-weights <file>` - Full precision MobileFaceNet record written by facenet's
+//!   `convert_weights`, with its config next to it (default: `mobilefacenet.bin`).
+//! * `--inputs <n>` - Number of random inputs, in the input range of each model (default: 8).
+//! * `--seed <n>` - Seed of the random inputs (default: 0).
+//!
+//! The models whose record does not exist are skipped. Each reduced precision record is loaded
+//! like a deployed model, and its outputs are compared with those of the full precision model on
+//! the same inputs: the MNIST logits, the YOLOX detection scores and the MobileFaceNet embeddings.
+//!
+//! The deltas are printed to stdout as CSV, one line per model and precision. The agreement is
+//! the fraction of inputs with the same top class for MNIST, of anchors with the same top class
+//! for YOLOX, and the lowest cosine similarity of the embeddings for MobileFaceNet.
+
+use std::path::{Path, PathBuf};
+
+use burn::{
+    backend::NdArray,
+    config::Config,
+    tensor::{backend::Backend as _, Distribution, Tensor},
+};
+use facenet_burn::{
+    embedding::l2_normalize,
+    mobilefacenet::{MobileFaceNet, MobileFaceNetConfig},
+};
+use inference_runtime::{record_bytes, ModelLoader, Precision};
+use yolo::{
+    model::Model,
+    yolox_model::yolox::{Yolox, YoloxConfig},
+};
+
+type Backend = NdArray<f32>;
+
+const USAGE: &str = "Usage: precision-report [--mnist-weights model.bin] \
+[--yolox-weights yolox_tiny.bin] [--face-weights mobilefacenet.bin] [--inputs 8] [--seed 0]";
+
+/// Measure of the agreement between the outputs of the full and reduced precision models.
+#[derive(Clone, Copy)]
+enum Agreement {
+    /// Fraction of the rows with the same largest value.
+    TopClass,
+    /// Lowest cosine similarity of the rows.
+    CosineSimilarity,
+}
+
+impl Agreement {
+    fn measure(&self, expected: Tensor<Backend, 2>, output: Tensor<Backend, 2>) -> f32 {
+        match self {
+            Self::TopClass => {
+                let [rows, _] = expected.dims();
+                let same = expected.argmax(1).equal(output.argmax(1));
+                same.int().sum().into_scalar() as f32 / rows as f32
+            }
+            Self::CosineSimilarity => (l2_normalize(expected) * l2_normalize(output))
+                .sum_dim(1)
+                .min()
+                .into_scalar(),
+        }
+    }
+}
+
+/// Compares the outputs of the model loaded from reduced precision records with its outputs.
+///
+/// `forward` returns one row of outputs per input, or per YOLOX anchor.
+fn report<M: ModelLoader<Backend> + Clone>(
+    name: &str,
+    model: M,
+    config: &M::Config,
+    forward: impl Fn(&M) -> Tensor<Backend, 2>,
+    agreement: Agreement,
+) {
+    let device = Default::default();
+    let full_size = record_bytes(model.clone(), Precision::Full).unwrap().len();
+    let expected = forward(&model);
+
+    for precision in Precision::ALL {
+        let bytes = record_bytes(model.clone(), precision)
+            .map_err(|err| {
+                format!("Failed to encode {name} in {precision} precision.\nError: {err}")
+            })
+            .unwrap();
+        let size = bytes.len();
+        let loaded = M::from_bytes(config, bytes, &device)
+            .map_err(|err| {
+                format!("Failed to decode {name} in {precision} precision.\nError: {err}")
+            })
+            .unwrap();
+
+        let output = forward(&loaded);
+        let delta = (output.clone() - expected.clone()).abs();
+        println!(
+            "{name},{precision},{size},{:.3},{:.6},{:.6},{:.4}",
+            size as f64 / full_size as f64,
+            delta.clone().max().into_scalar(),
+            delta.mean().into_scalar(),
+            agreement.measure(expected.clone(), output),
+        );
+    }
+}
+
+/// Reads a record, or returns `None` if it does not exist.
+fn read_weights(name: &str, path: &Path) -> Option<Vec<u8>> {
+    if !path.exists() {
+        eprintln!("Skipping {name}, {} does not exist", path.display());
+        return None;
+    }
+
+    let bytes = std::fs::read(path)
+        .map_err(|err| format!("Failed to read {}.\nError: {err}", path.display()))
+        .unwrap();
+    Some(bytes)
+}
+
+pub fn main() {
+    // Parse arguments
+    let mut mnist_weights = PathBuf::from("model.bin");
+    let mut yolox_weights = PathBuf::from("yolox_tiny.bin");
+    let mut face_weights = PathBuf::from("mobilefacenet.bin");
+    let mut inputs = 8;
+    let mut seed = 0;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .unwrap_or_else(|| panic!("No value provided for {arg}\n{USAGE}"))
+        };
+        match arg.as_str() {
+            "--mnist-weights" => mnist_weights = value().into(),
+            "--yolox-weights" => yolox_weights = value().into(),
+            "--face-weights" => face_weights = value().into(),
+            "--inputs" => inputs = value().parse().expect("Invalid number of inputs"),
+            "--seed" => seed = value().parse().expect("Invalid seed"),
+            _ => panic!("Unknown argument {arg}\n{USAGE}"),
+        }
+    }
+    assert!(inputs > 0, "The number of inputs must be positive");
+
+    let device = Default::default();
+    let decode_error = |name: &str, err| format!("Failed to decode {name}.\nError: {err}");
+    Backend::seed(seed);
+
+    println!("model,precision,bytes,size_ratio,max_abs_delta,mean_abs_delta,agreement");
+    if let Some(bytes) = read_weights("mnist", &mnist_weights) {
+        let model = Model::<Backend>::from_bytes(&(), bytes, &device)
+            .map_err(|err| decode_error("mnist", err))
+            .unwrap();
+        // Normalized pixels, as in the MNIST demo
+        let pixels = Tensor::<Backend, 3>::random(
+            [inputs, 28, 28],
+            Distribution::Uniform(0., 255.),
+            &device,
+        );
+        let input = (pixels / 255 - 0.1307) / 0.3081;
+
+        report(
+            "mnist",
+            model,
+            &(),
+            |model| model.forward(input.clone()),
+            Agreement::TopClass,
+        );
+    }
+
+    if let Some(bytes) = read_weights("yolox_tiny", &yolox_weights) {
+        let config = YoloxConfig::yolox_tiny();
+        let model = Yolox::<Backend>::from_bytes(&config, bytes, &device)
+            .map_err(|err| decode_error("yolox_tiny", err))
+            .unwrap();
+        // Un-normalized RGB values
+        let input = Tensor::<Backend, 4>::random(
+            [inputs, 3, 416, 416],
+            Distribution::Uniform(0., 255.),
+            &device,
+        );
+
+        // Detection scores, the objectness times the class probabilities
+        report(
+            "yolox_tiny",
+            model,
+            &config,
+            |model| {
+                let output = model.forward(input.clone());
+                let [batch, anchors, outputs] = output.dims();
+                let output = output.reshape([batch * anchors, outputs]);
+                let objectness = output.clone().slice([0..batch * anchors, 4..5]);
+                output.slice([0..batch * anchors, 5..outputs]) * objectness
+            },
+            Agreement::TopClass,
+        );
+    }
+
+    if let Some(bytes) = read_weights("mobilefacenet", &face_weights) {
+        let config_path = face_weights.with_extension("json");
+        let config = match config_path.exists() {
+            true => MobileFaceNetConfig::load(&config_path)
+                .map_err(|err| format!("Failed to load {}.\nError: {err}", config_path.display()))
+                .unwrap(),
+            false => MobileFaceNetConfig::new(),
+        };
+        let model = MobileFaceNet::<Backend>::from_bytes(&config, bytes, &device)
+            .map_err(|err| decode_error("mobilefacenet", err))
+            .unwrap();
+        // Normalized pixels
+        let size = config.input_size;
+        let input = Tensor::<Backend, 4>::random(
+            [inputs, 3, size, size],
+            Distribution::Uniform(-1., 1.),
+            &device,
+        );
+
+        report(
+            "mobilefacenet",
+            model,
+            &config,
+            |model| model.forward(input.clone()),
+            Agreement::CosineSimilarity,
+        );
+    }
+}
@@ -0,0 +1,134 @@
+//! HTTP inference server for the YOLOX detector and the MobileFaceNet recognizer. Requires the
+//! `server` feature: `cargo run --release --features server --bin server`.
+//!
+//! Usage: `server [options]`
+//!
+//! See the [server module](yolo::server) for the endpoints, e.g.
+//! `curl --data-binary @image.jpg http://127.0.0.1:8080/detect`.
+//!
+//! Options:
+//! * `--address <host:port>` - Listening address (default: `127.0.0.1:8080`).
+//! * `--weights <file>` - YOLOX-Tiny weights written by `yolo convert` (default: the
+//!   `yolox_tiny.pth` PyTorch weights of the working directory).
+//! * `--score-threshold <score>` - Minimum detection score (default: 0.5).
+//! * `--face-weights <file>` - MobileFaceNet weights written by facenet's `convert_weights`,
+//!   which enable the `/embed` and `/identify` endpoints.
+//! * `--gallery <dir>` - Images of the known identities for `/identify`, named after the identity
+//!   (e.g. `alice.jpg`).
+//! * `--identity-threshold <similarity>` - Minimum cosine similarity of a recognized identity
+//!   (default: 0.5).
+//! * `--workers <n>` - Number of threads handling the requests (default: 4).
+//! * `--max-batch-size <n>` - Maximum number of images per detection forward pass (default: 8).
+//! * `--max-wait-ms <ms>` - Maximum time a detection request waits for other requests to batch
+//!   with (default: 5).
+//! * `--max-queue-size <n>` - Maximum number of waiting detection requests, beyond which requests
+//!   are rejected with a 503 status (default: 64).
+
+use std::path::PathBuf;
+
+use burn::backend::NdArray;
+use facenet_burn::state::load_model_file;
+use tiny_http::Server;
+use yolo::{
+    detector::DetectorConfig, recognition::Recognizer, server::InferenceServerConfig,
+    yolox_model::yolox::Yolox,
+};
+
+type Backend = NdArray<f32>;
+
+const USAGE: &str = "Usage: server [--address host:port] [--weights file] \
+[--score-threshold score] [--face-weights file] [--gallery dir] \
+[--identity-threshold similarity] [--workers n] [--max-batch-size n] [--max-wait-ms ms] \
+[--max-queue-size n]";
+
+pub fn main() {
+    // Parse arguments
+    let mut address = String::from("127.0.0.1:8080");
+    let mut weights: Option<PathBuf> = None;
+    let mut score_threshold = 0.5;
+    let mut face_weights: Option<PathBuf> = None;
+    let mut gallery: Option<PathBuf> = None;
+    let mut identity_threshold = 0.5;
+    let mut config = InferenceServerConfig::new();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .unwrap_or_else(|| panic!("No value provided for {arg}\n{USAGE}"))
+        };
+        match arg.as_str() {
+            "--address" => address = value(),
+            "--weights" => weights = Some(value().into()),
+            "--score-threshold" => {
+                score_threshold = value().parse().expect("Invalid score threshold")
+            }
+            "--face-weights" => face_weights = Some(value().into()),
+            "--gallery" => gallery = Some(value().into()),
+            "--identity-threshold" => {
+                identity_threshold = value().parse().expect("Invalid identity threshold")
+            }
+            "--workers" => config.workers = value().parse().expect("Invalid number of workers"),
+            "--max-batch-size" => {
+                config.batcher.max_batch_size = value().parse().expect("Invalid batch size")
+            }
+            "--max-wait-ms" => {
+                config.batcher.max_wait_ms = value().parse().expect("Invalid wait time")
+            }
+            "--max-queue-size" => {
+                config.batcher.max_queue_size = value().parse().expect("Invalid queue size")
+            }
+            _ => panic!("Unknown argument {arg}\n{USAGE}"),
+        }
+    }
+    assert!(
+        gallery.is_none() || face_weights.is_some(),
+        "--gallery requires --face-weights"
+    );
+    config
+        .batcher
+        .validate()
+        .map_err(|err| format!("Invalid batcher options.\nError: {err}\n{USAGE}"))
+        .unwrap();
+
+    // Create the models
+    let device = Default::default();
+    let model = match &weights {
+        Some(path) => std::fs::read(path)
+            .map_err(|err| err.to_string())
+            .and_then(|bytes| {
+                Yolox::<Backend>::yolox_tiny_from_bytes(bytes, &device)
+                    .map_err(|err| err.to_string())
+            }),
+        None => Yolox::yolox_tiny(&device).map_err(|err| err.to_string()),
+    }
+    .map_err(|err| format!("Failed to load YOLOX weights.\nError: {err}"))
+    .unwrap()
+    .fuse();
+    let detector = DetectorConfig::new()
+        .with_score_threshold(score_threshold)
+        .init(model);
+
+    let recognizer = face_weights.map(|face_weights| {
+        let (model, config) = load_model_file(&face_weights, None, &device).unwrap();
+        let mut recognizer = Recognizer::new(model, config.input_size, identity_threshold, &device);
+        if let Some(gallery) = &gallery {
+            let count = recognizer
+                .load_gallery(gallery)
+                .map_err(|err| {
+                    format!(
+                        "Failed to load gallery {}.\nError: {err}",
+                        gallery.display()
+                    )
+                })
+                .unwrap();
+            eprintln!("Loaded {count} gallery identities");
+        }
+        recognizer
+    });
+
+    let server = Server::http(&address)
+        .map_err(|err| format!("Failed to listen on {address}.\nError: {err}"))
+        .unwrap();
+    eprintln!("Listening on http://{address}");
+    config.init(detector, recognizer).serve(&server);
+}
@@ -0,0 +1,508 @@
+//! Command line interface of the YOLOX detector.
+//!
+//! Usage:
+//! * `yolo video <frames directory | -> [options]`
+//! * `yolo convert [--precision full|half|int8] [output.bin]`
+//!
+//! ## video
+//!
+//! Runs detection on every frame of a directory of images, or of a Y4M or MJPEG stream read from
+//! stdin (`-`), e.g. `ffmpeg -i video.mp4 -f yuv4mpegpipe - | yolo video -`. Annotated frames and
+//! the detections of each frame are written to the output directory, and the throughput is
+//! reported on stderr.
+//!
+//! Options:
+//! * `--output <dir>` - Output directory (default: `output`).
+//! * `--format <jsonl|coco|csv|yolo|voc>` - Format of the results (default: `jsonl`): JSON
+//!   lines, COCO detection results JSON or CSV in a single file, or one YOLO `.txt` or Pascal VOC
+//!   `.xml` file per frame.
+//! * `--results <path>` - Results file, or directory for the per-frame formats (default:
+//!   `<output>/results.{jsonl,json,csv}`, `<output>/labels` or `<output>/annotations`).
+//! * `--no-frames` - Do not write the annotated frames.
+//! * `--classes <i,j,...>` - Only keep the given class indices.
+//! * `--score-threshold <score>` - Minimum detection score (default: 0.5).
+//! * `--track` - Track the detections across frames and report track IDs. Detections scoring
+//!   below the score threshold, down to the tracker low confidence threshold, only keep existing
+//!   tracks alive.
+//! * `--gallery <dir>` - Recognize the detections against the images of a directory, named after
+//!   the identity (e.g. `alice.jpg`), with MobileFaceNet embeddings.
+//! * `--face-weights <file>` - MobileFaceNet weights written by facenet's `convert_weights`
+//!   (default: `mobilefacenet.bin`).
+//! * `--identity-threshold <similarity>` - Minimum cosine similarity of a recognized identity
+//!   (default: 0.5).
+//! * `--anonymize <blur|pixelate|fill>` - Write the frames with the detections redacted rather
+//!   than annotated, e.g. with `--classes 0` to redact people, as the COCO weights have no face
+//!   class. Frames of a directory keep their file name and format, so that a whole directory of
+//!   photos can be anonymized at once into another directory. Cannot be used with `--no-frames`.
+//! * `--padding <fraction>` - Padding of the redacted regions, as a fraction of the box size
+//!   (default: 0.1).
+//! * `--rectangle` - Redact the whole boxes rather than the ellipses inscribed in them.
+//!
+//! ## convert
+//!
+//! Converts the `yolox_tiny.pth` PyTorch weights of the working directory into a Burn record
+//! (default: `yolox_tiny.bin`), which the wasm `Detector` can load. Its SHA-256 checksum, verified
+//! by the `Weights` streamed from JavaScript, is printed on stderr.
+//!
+//! Options:
+//! * `--precision <full|half|int8>` - Precision of the saved weights (default: `full`). Half
+//!   precision halves the record and int8 quantization divides it by about four, at the cost of
+//!   the accuracy reported by `precision-report`.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use burn::backend::NdArray;
+use facenet_burn::state::load_model_file;
+use image::DynamicImage;
+use inference_runtime::{record_bytes, sha256_hex, Precision};
+use yolo::{
+    anonymize::{AnonymizerConfig, Redaction},
+    detector::{Detector, DetectorConfig},
+    recognition::Recognizer,
+    render::{Annotation, RendererConfig},
+    results::{DetectionResult, FrameResult, ResultFormat, ResultWriter},
+    tracking::{TrackIds, Tracker, TrackerConfig},
+    video::{Frame, FrameReader},
+    yolox_model::{yolox::Yolox, BoundingBox, COCO_CLASSES},
+};
+
+type Backend = NdArray<f32>;
+
+/// Number of frames between two throughput reports.
+const REPORT_INTERVAL: usize = 30;
+
+const USAGE: &str =
+    "Usage: yolo video <frames directory | -> [--output dir] [--format format] [--results path] \
+[--no-frames] [--classes i,j] [--score-threshold score] [--track] [--gallery dir] \
+[--face-weights file] [--identity-threshold similarity] [--anonymize blur|pixelate|fill] \
+[--padding fraction] [--rectangle]
+       yolo convert [--precision full|half|int8] [output.bin]";
+
+struct VideoArgs {
+    input: String,
+    output: PathBuf,
+    format: ResultFormat,
+    results: Option<PathBuf>,
+    write_frames: bool,
+    classes: Option<Vec<usize>>,
+    score_threshold: f32,
+    track: bool,
+    gallery: Option<PathBuf>,
+    face_weights: PathBuf,
+    identity_threshold: f32,
+    anonymize: Option<Redaction>,
+    padding: f32,
+    elliptical: bool,
+}
+
+impl VideoArgs {
+    fn parse(mut args: impl Iterator<Item = String>) -> Self {
+        let mut input = None;
+        let mut parsed = Self {
+            input: String::new(),
+            output: PathBuf::from("output"),
+            format: ResultFormat::JsonLines,
+            results: None,
+            write_frames: true,
+            classes: None,
+            score_threshold: 0.5,
+            track: false,
+            gallery: None,
+            face_weights: PathBuf::from("mobilefacenet.bin"),
+            identity_threshold: 0.5,
+            anonymize: None,
+            padding: 0.1,
+            elliptical: true,
+        };
+        let value = |args: &mut dyn Iterator<Item = String>, name: &str| {
+            args.next()
+                .unwrap_or_else(|| panic!("No value provided for {name}\n{USAGE}"))
+        };
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--output" => parsed.output = value(&mut args, &arg).into(),
+                "--format" => {
+                    parsed.format = match value(&mut args, &arg).as_str() {
+                        "jsonl" => ResultFormat::JsonLines,
+                        "coco" => ResultFormat::Coco,
+                        "csv" => ResultFormat::Csv,
+                        "yolo" => ResultFormat::Yolo,
+                        "voc" => ResultFormat::Voc,
+                        other => panic!("Unknown format {other}\n{USAGE}"),
+                    }
+                }
+                "--results" => parsed.results = Some(value(&mut args, &arg).into()),
+                "--no-frames" => parsed.write_frames = false,
+                "--classes" => {
+                    let classes = value(&mut args, &arg)
+                        .split(',')
+                        .map(|class| class.trim().parse().expect("Invalid class index"))
+                        .collect();
+                    parsed.classes = Some(classes);
+                }
+                "--score-threshold" => {
+                    parsed.score_threshold = value(&mut args, &arg)
+                        .parse()
+                        .expect("Invalid score threshold")
+                }
+                "--track" => parsed.track = true,
+                "--gallery" => parsed.gallery = Some(value(&mut args, &arg).into()),
+                "--face-weights" => parsed.face_weights = value(&mut args, &arg).into(),
+                "--identity-threshold" => {
+                    parsed.identity_threshold = value(&mut args, &arg)
+                        .parse()
+                        .expect("Invalid identity threshold")
+                }
+                "--anonymize" => {
+                    parsed.anonymize = Some(match value(&mut args, &arg).as_str() {
+                        "blur" => Redaction::Blur,
+                        "pixelate" => Redaction::Pixelate,
+                        "fill" => Redaction::Fill,
+                        other => panic!("Unknown redaction {other}\n{USAGE}"),
+                    })
+                }
+                "--padding" => {
+                    parsed.padding = value(&mut args, &arg).parse().expect("Invalid padding")
+                }
+                "--rectangle" => parsed.elliptical = false,
+                _ if arg.starts_with("--") => panic!("Unknown option {arg}\n{USAGE}"),
+                _ => input = Some(arg),
+            }
+        }
+        parsed.input = input.unwrap_or_else(|| panic!("No input provided\n{USAGE}"));
+
+        if parsed.anonymize.is_some() && !parsed.write_frames {
+            panic!("--anonymize writes the frames and cannot be used with --no-frames\n{USAGE}");
+        }
+        // The written frames keep the name of the input frames, anonymized ones their format too
+        let same_directory = fs::canonicalize(&parsed.input)
+            .ok()
+            .is_some_and(|input| fs::canonicalize(&parsed.output).ok() == Some(input));
+        if parsed.write_frames && same_directory {
+            panic!(
+                "The output directory must differ from the input directory, whose frames would \
+be overwritten\n{USAGE}"
+            );
+        }
+
+        parsed
+    }
+}
+
+/// Time spent in each processing stage.
+#[derive(Default)]
+struct Timings {
+    decode: Duration,
+    detect: Duration,
+    recognize: Duration,
+    write: Duration,
+}
+
+pub fn main() {
+    let mut args = std::env::args().skip(1);
+    match args.next().as_deref() {
+        Some("video") => video(VideoArgs::parse(args)),
+        Some("convert") => convert(args),
+        _ => panic!("{USAGE}"),
+    }
+}
+
+fn convert(mut args: impl Iterator<Item = String>) {
+    let mut output = PathBuf::from("yolox_tiny.bin");
+    let mut precision = Precision::Full;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--precision" => {
+                precision = args
+                    .next()
+                    .unwrap_or_else(|| panic!("No value provided for {arg}\n{USAGE}"))
+                    .parse()
+                    .unwrap_or_else(|err| panic!("{err}\n{USAGE}"))
+            }
+            _ if arg.starts_with("--") => panic!("Unknown option {arg}\n{USAGE}"),
+            _ => output = arg.into(),
+        }
+    }
+
+    let device = Default::default();
+    let model: Yolox<Backend> = Yolox::yolox_tiny(&device)
+        .map_err(|err| format!("Failed to load pre-trained weights.\nError: {err}"))
+        .unwrap();
+
+    let bytes = record_bytes(model, precision).unwrap();
+    fs::write(&output, &bytes)
+        .map_err(|err| format!("Failed to write {}.\nError: {err}", output.display()))
+        .unwrap();
+    eprintln!("Weights written to {}", output.display());
+    eprintln!("SHA-256: {}", sha256_hex(&bytes));
+}
+
+fn video(args: VideoArgs) {
+    let device = Default::default();
+
+    // Create YOLOX-Tiny
+    let model: Yolox<Backend> = Yolox::yolox_tiny(&device)
+        .map_err(|err| format!("Failed to load pre-trained weights.\nError: {err}"))
+        .unwrap()
+        .fuse();
+    // New tracks start from the detections above the score threshold, and lower ones (ByteTrack
+    // second association) keep the existing tracks alive
+    let tracker_config = TrackerConfig::new().with_high_confidence(args.score_threshold);
+    let score_threshold = match args.track {
+        true => args.score_threshold.min(tracker_config.low_confidence),
+        false => args.score_threshold,
+    };
+    let detector = DetectorConfig::new()
+        .with_score_threshold(score_threshold)
+        .init(model);
+    let recognizer = args.gallery.as_ref().map(|gallery| {
+        let (model, config) = load_model_file(&args.face_weights, None, &device).unwrap();
+        let mut recognizer =
+            Recognizer::new(model, config.input_size, args.identity_threshold, &device);
+        let count = recognizer
+            .load_gallery(gallery)
+            .map_err(|err| {
+                format!(
+                    "Failed to load gallery {}.\nError: {err}",
+                    gallery.display()
+                )
+            })
+            .unwrap();
+        eprintln!("Loaded {count} gallery identities");
+        recognizer
+    });
+    let renderer = RendererConfig::new().with_color_by_track(args.track).init();
+    let anonymizer = args.anonymize.clone().map(|redaction| {
+        AnonymizerConfig::new()
+            .with_redaction(redaction)
+            .with_padding(args.padding)
+            .with_elliptical(args.elliptical)
+            .init()
+    });
+    // The tracker is class-agnostic, so keep one per class, sharing the track IDs
+    let mut trackers: Option<Vec<Tracker>> = args.track.then(|| {
+        let ids = TrackIds::default();
+        (0..COCO_CLASSES.len())
+            .map(|_| tracker_config.init_with_ids(&ids))
+            .collect()
+    });
+
+    let frames = if args.input == "-" {
+        FrameReader::stream(std::io::stdin())
+    } else {
+        FrameReader::directory(Path::new(&args.input))
+    }
+    .map_err(|err| format!("Failed to open input {}.\nError: {err}", args.input))
+    .unwrap();
+
+    fs::create_dir_all(&args.output)
+        .map_err(|err| format!("Failed to create {}.\nError: {err}", args.output.display()))
+        .unwrap();
+    let results_path = args
+        .results
+        .clone()
+        .unwrap_or_else(|| args.format.default_path(&args.output));
+    let class_names: Vec<_> = COCO_CLASSES.iter().map(|name| name.to_string()).collect();
+    let mut results = ResultWriter::create(args.format, &results_path, &class_names)
+        .map_err(|err| format!("Failed to create {}.\nError: {err}", results_path.display()))
+        .unwrap();
+
+    let mut timings = Timings::default();
+    let start = Instant::now();
+    let mut report_start = start;
+    let mut num_frames = 0;
+    let mut decode_start = Instant::now();
+
+    for frame in frames {
+        let frame = frame
+            .map_err(|err| format!("Failed to read frame {num_frames}.\nError: {err}"))
+            .unwrap();
+        timings.decode += decode_start.elapsed();
+
+        let (result, boxes) = process_frame(
+            &frame,
+            &detector,
+            recognizer.as_ref(),
+            trackers.as_mut(),
+            args.classes.as_deref(),
+            args.score_threshold,
+            &mut timings,
+        );
+
+        let write_start = Instant::now();
+        if args.write_frames {
+            let (path, image) = match &anonymizer {
+                // Redact every detection, including the ones not confirmed by the tracker yet
+                Some(anonymizer) => {
+                    let boxes: Vec<_> = boxes.into_iter().flatten().collect();
+                    let image = anonymizer.anonymize(&frame.image, &boxes);
+                    let image = if frame.image.color().has_alpha() {
+                        DynamicImage::ImageRgba8(image)
+                    } else {
+                        DynamicImage::ImageRgb8(DynamicImage::ImageRgba8(image).into_rgb8())
+                    };
+                    let path = Path::new(&frame.name);
+                    let path = match image::ImageFormat::from_path(path) {
+                        Ok(_) => path.to_path_buf(),
+                        Err(_) => path.with_extension("png"),
+                    };
+                    (args.output.join(path), image)
+                }
+                None => {
+                    let annotations: Vec<_> = result.detections.iter().map(annotation).collect();
+                    let image = renderer.render(&frame.image, &annotations);
+                    let path = Path::new(&frame.name).with_extension("png");
+                    (args.output.join(path), DynamicImage::ImageRgba8(image))
+                }
+            };
+            image
+                .save(&path)
+                .map_err(|err| format!("Failed to save {}.\nError: {err}", path.display()))
+                .unwrap();
+        }
+        results
+            .write(&result)
+            .map_err(|err| format!("Failed to write results.\nError: {err}"))
+            .unwrap();
+        timings.write += write_start.elapsed();
+
+        num_frames += 1;
+        if num_frames % REPORT_INTERVAL == 0 {
+            let fps = REPORT_INTERVAL as f64 / report_start.elapsed().as_secs_f64();
+            eprintln!("Processed {num_frames} frames ({fps:.2} FPS)");
+            report_start = Instant::now();
+        }
+        decode_start = Instant::now();
+    }
+    results
+        .finish()
+        .map_err(|err| format!("Failed to write results.\nError: {err}"))
+        .unwrap();
+
+    let elapsed = start.elapsed().as_secs_f64();
+    let per_frame = |duration: Duration| duration.as_secs_f64() * 1000. / num_frames.max(1) as f64;
+    eprintln!(
+        "Processed {num_frames} frames in {elapsed:.2}s ({:.2} FPS)",
+        num_frames as f64 / elapsed
+    );
+    eprintln!(
+        "Per frame: decode {:.1}ms, detect {:.1}ms, recognize/track {:.1}ms, write {:.1}ms",
+        per_frame(timings.decode),
+        per_frame(timings.detect),
+        per_frame(timings.recognize),
+        per_frame(timings.write),
+    );
+    eprintln!("Results written to {}", results_path.display());
+}
+
+/// Runs detection, and optionally recognition and tracking, on a frame.
+///
+/// # Returns
+///
+/// The frame results, and the detected boxes of the kept classes scoring at least
+/// `score_threshold`. The detector may keep lower scores for the trackers.
+fn process_frame(
+    frame: &Frame,
+    detector: &Detector<Backend>,
+    recognizer: Option<&Recognizer<Backend>>,
+    mut trackers: Option<&mut Vec<Tracker>>,
+    classes: Option<&[usize]>,
+    score_threshold: f32,
+    timings: &mut Timings,
+) -> (FrameResult, Vec<Vec<BoundingBox>>) {
+    let detect_start = Instant::now();
+    let mut boxes = detector.detect(&frame.image);
+    for (class, boxes) in boxes.iter_mut().enumerate() {
+        if classes.is_some_and(|classes| !classes.contains(&class)) {
+            boxes.clear();
+        }
+    }
+    timings.detect += detect_start.elapsed();
+
+    let recognize_start = Instant::now();
+    let identify = |embedding: Option<&[f32]>| {
+        recognizer
+            .zip(embedding)
+            .and_then(|(recognizer, embedding)| recognizer.identify(embedding))
+            .unzip()
+    };
+
+    let mut detections = Vec::new();
+    for (class, boxes) in boxes.iter().enumerate() {
+        let embeddings = recognizer.map(|recognizer| {
+            let crops: Vec<_> = boxes.iter().map(|b| crop(&frame.image, b)).collect();
+            recognizer.embed(&crops)
+        });
+
+        match trackers.as_deref_mut() {
+            // Report the confirmed tracks, identified from their running embedding
+            Some(trackers) => {
+                let tracker = &mut trackers[class];
+                match &embeddings {
+                    Some(embeddings) => tracker.update_with_embeddings(boxes, embeddings),
+                    None => tracker.update(boxes),
+                };
+                for track in tracker.confirmed() {
+                    let (identity, similarity) = identify(track.embedding());
+                    let bbox = track.bbox();
+                    detections.push(
+                        DetectionResult::new(class, &bbox, Some(track.id()))
+                            .with_identity(identity, similarity),
+                    );
+                }
+            }
+            None => {
+                for (i, bbox) in boxes.iter().enumerate() {
+                    let (identity, similarity) =
+                        identify(embeddings.as_ref().map(|e| e[i].as_slice()));
+                    detections.push(
+                        DetectionResult::new(class, bbox, None).with_identity(identity, similarity),
+                    );
+                }
+            }
+        }
+    }
+    timings.recognize += recognize_start.elapsed();
+
+    for boxes in boxes.iter_mut() {
+        boxes.retain(|bbox| bbox.confidence >= score_threshold);
+    }
+    let result = FrameResult {
+        frame: frame.index,
+        name: frame.name.clone(),
+        width: frame.image.width(),
+        height: frame.image.height(),
+        detections,
+    };
+
+    (result, boxes)
+}
+
+/// Crops a box out of an image, clamped to the image bounds.
+fn crop(image: &DynamicImage, bbox: &BoundingBox) -> DynamicImage {
+    let (w, h) = (image.width() as f32, image.height() as f32);
+    let xmin = bbox.xmin.clamp(0., w - 1.);
+    let ymin = bbox.ymin.clamp(0., h - 1.);
+    let xmax = bbox.xmax.clamp(xmin + 1., w);
+    let ymax = bbox.ymax.clamp(ymin + 1., h);
+
+    image.crop_imm(
+        xmin as u32,
+        ymin as u32,
+        (xmax - xmin) as u32,
+        (ymax - ymin) as u32,
+    )
+}
+
+/// Converts a detection result into an annotation to draw.
+fn annotation(detection: &DetectionResult) -> Annotation {
+    let mut annotation = Annotation::new(detection.class, detection.bounding_box());
+    annotation.track_id = detection.track_id;
+    annotation.identity = detection.identity.clone();
+    annotation
+}
@@ -0,0 +1,237 @@
+use alloc::{string::ToString, sync::Arc, vec::Vec};
+use burn::{
+    config::Config,
+    module::Module,
+    tensor::{backend::Backend, Device, Tensor, TensorData},
+};
+use image::DynamicImage;
+use inference_metrics::{now_ms, Metrics};
+
+use crate::yolox_model::{
+    boxes::{nms, nms_async},
+    yolox::Yolox,
+    BoundingBox, COCO_CLASSES,
+};
+
+/// [Detector](Detector) configuration.
+#[derive(Config, Debug)]
+pub struct DetectorConfig {
+    /// Height of the model input. Images are resized to this height.
+    #[config(default = "640")]
+    pub input_height: usize,
+    /// Width of the model input. Images are resized to this width.
+    #[config(default = "640")]
+    pub input_width: usize,
+    /// IoU threshold of the non-maximum suppression.
+    #[config(default = "0.65")]
+    pub iou_threshold: f32,
+    /// Minimum score of the reported boxes.
+    #[config(default = "0.5")]
+    pub score_threshold: f32,
+}
+
+impl DetectorConfig {
+    /// Initialize a new [detector](Detector) running the given model.
+    pub fn init<B: Backend>(&self, model: Yolox<B>) -> Detector<B> {
+        Detector {
+            model,
+            config: self.clone(),
+            metrics: None,
+        }
+    }
+}
+
+/// Runs a [YOLOX](Yolox) model on images, from pre-processing to non-maximum suppression.
+#[derive(Debug)]
+pub struct Detector<B: Backend> {
+    model: Yolox<B>,
+    config: DetectorConfig,
+    metrics: Option<Arc<Metrics>>,
+}
+
+impl<B: Backend> Detector<B> {
+    /// Records the latency of the `preprocess`, `forward` and `nms` stages, and the number of
+    /// `detections` per class, in the given metrics.
+    ///
+    /// With asynchronous backends, the forward pass completes when the boxes are read, so most of
+    /// its latency is attributed to the `nms` stage.
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Detects objects in an image.
+    ///
+    /// # Returns
+    ///
+    /// Bounding boxes grouped per class, in the coordinates of the original image.
+    pub fn detect(&self, image: &DynamicImage) -> Vec<Vec<BoundingBox>> {
+        self.detect_batch(core::slice::from_ref(image))
+            .pop()
+            .unwrap_or_default()
+    }
+
+    /// Detects objects in a batch of images, with a single forward pass.
+    ///
+    /// # Returns
+    ///
+    /// Bounding boxes grouped per class for each image, in the coordinates of the original
+    /// images.
+    pub fn detect_batch(&self, images: &[DynamicImage]) -> Vec<Vec<Vec<BoundingBox>>> {
+        if images.is_empty() {
+            return Vec::new();
+        }
+
+        let (boxes, scores, forwarded) = self.forward(images);
+        let boxes = nms(
+            boxes,
+            scores,
+            self.config.iou_threshold,
+            self.config.score_threshold,
+        );
+
+        self.finish(images, boxes, forwarded)
+    }
+
+    /// Detects objects in an image like [detect](Detector::detect), reading the outputs of the
+    /// model asynchronously so that the `wgpu` backend can run in the browser.
+    pub async fn detect_async(&self, image: &DynamicImage) -> Vec<Vec<BoundingBox>> {
+        let images = core::slice::from_ref(image);
+        let (boxes, scores, forwarded) = self.forward(images);
+        let boxes = nms_async(
+            boxes,
+            scores,
+            self.config.iou_threshold,
+            self.config.score_threshold,
+        )
+        .await;
+
+        self.finish(images, boxes, forwarded)
+            .pop()
+            .unwrap_or_default()
+    }
+
+    /// The detector configuration.
+    pub fn config(&self) -> &DetectorConfig {
+        &self.config
+    }
+
+    /// Changes the detector configuration, e.g. the input size or the thresholds.
+    pub fn set_config(&mut self, config: DetectorConfig) {
+        self.config = config;
+    }
+
+    /// The metrics the detector records, if any.
+    pub fn metrics(&self) -> Option<&Arc<Metrics>> {
+        self.metrics.as_ref()
+    }
+
+    /// Pre-processes a non-empty batch of images and runs the forward pass.
+    ///
+    /// # Returns
+    ///
+    /// The `[batch_size, num_boxes, 4]` boxes, the `[batch_size, num_boxes, num_classes]` scores
+    /// and the end time of the forward pass.
+    fn forward(&self, images: &[DynamicImage]) -> (Tensor<B, 3>, Tensor<B, 3>, f64) {
+        let device = self.device();
+        let (height, width) = (self.config.input_height, self.config.input_width);
+
+        let start = now_ms();
+        let x = Tensor::stack(
+            images
+                .iter()
+                .map(|image| image_to_tensor::<B>(image, height, width, &device))
+                .collect(),
+            0,
+        );
+        let preprocessed = now_ms();
+
+        // Forward pass
+        let out = self.model.forward(x);
+        let forwarded = now_ms();
+        if let Some(metrics) = &self.metrics {
+            metrics.observe("preprocess", preprocessed - start);
+            metrics.observe("forward", forwarded - preprocessed);
+        }
+
+        // Post-processing
+        let [batch_size, num_boxes, num_outputs] = out.dims();
+        let boxes = out.clone().slice([0..batch_size, 0..num_boxes, 0..4]);
+        let obj_scores = out.clone().slice([0..batch_size, 0..num_boxes, 4..5]);
+        let cls_scores = out.slice([0..batch_size, 0..num_boxes, 5..num_outputs]);
+
+        (boxes, cls_scores * obj_scores, forwarded)
+    }
+
+    /// Records the metrics of the boxes selected by the non-maximum suppression, and scales them
+    /// back to the original images.
+    fn finish(
+        &self,
+        images: &[DynamicImage],
+        mut boxes: Vec<Vec<Vec<BoundingBox>>>,
+        forwarded: f64,
+    ) -> Vec<Vec<Vec<BoundingBox>>> {
+        if let Some(metrics) = &self.metrics {
+            metrics.observe("nms", now_ms() - forwarded);
+            for image_boxes in boxes.iter() {
+                for (class, class_boxes) in image_boxes.iter().enumerate() {
+                    if !class_boxes.is_empty() {
+                        let name = COCO_CLASSES
+                            .get(class)
+                            .map_or_else(|| class.to_string(), |name| name.to_string());
+                        metrics.increment_labeled(
+                            "detections",
+                            ("class", &name),
+                            class_boxes.len() as u64,
+                        );
+                    }
+                }
+            }
+        }
+
+        // Scale the boxes back to the original images
+        let (height, width) = (self.config.input_height, self.config.input_width);
+        for (image, boxes) in images.iter().zip(boxes.iter_mut()) {
+            let ratio = [
+                image.width() as f32 / width as f32,
+                image.height() as f32 / height as f32,
+            ];
+            for b in boxes.iter_mut().flatten() {
+                b.xmin *= ratio[0];
+                b.xmax *= ratio[0];
+                b.ymin *= ratio[1];
+                b.ymax *= ratio[1];
+            }
+        }
+
+        boxes
+    }
+
+    fn device(&self) -> Device<B> {
+        self.model.devices().into_iter().next().unwrap_or_default()
+    }
+}
+
+/// Resizes an image to the model input size and converts it into a `[3, height, width]` tensor.
+///
+/// YOLOX takes un-normalized RGB values in the `[0, 255]` range.
+pub fn image_to_tensor<B: Backend>(
+    image: &DynamicImage,
+    height: usize,
+    width: usize,
+    device: &Device<B>,
+) -> Tensor<B, 3> {
+    let resized = image.resize_exact(
+        width as u32,
+        height as u32,
+        image::imageops::FilterType::Triangle, // also known as bilinear in 2D
+    );
+
+    Tensor::<B, 3>::from_data(
+        TensorData::new(resized.into_rgb8().into_raw(), [height, width, 3])
+            .convert::<B::FloatElem>(),
+        device,
+    )
+    // [H, W, C] -> [C, H, W]
+    .permute([2, 0, 1])
+}
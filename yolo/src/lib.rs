@@ -1,6 +1,20 @@
+pub mod anonymize;
+pub mod detector;
 pub mod model;
+#[cfg(not(target_family = "wasm"))]
+pub mod recognition;
+pub mod render;
+pub mod scheduler;
+#[cfg(all(feature = "server", not(target_family = "wasm")))]
+pub mod server;
 pub mod yolox_model;
 pub mod state;
+pub mod tracking;
+#[cfg(not(target_family = "wasm"))]
+pub mod results;
+#[cfg(not(target_family = "wasm"))]
+pub mod video;
 pub mod web;
+pub mod worker;
 
-extern crate alloc;
\ No newline at end of file
+extern crate alloc;
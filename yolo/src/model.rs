@@ -6,6 +6,7 @@ use burn::{
     nn::{BatchNorm, PaddingConfig2d},
     prelude::*,
 };
+use inference_runtime::ModelLoader;
 
 #[derive(Module, Debug)]
 pub struct Model<B: Backend> {
@@ -20,6 +21,14 @@ pub struct Model<B: Backend> {
 
 const NUM_CLASSES: usize = 10;
 
+impl<B: Backend> ModelLoader<B> for Model<B> {
+    type Config = ();
+
+    fn init_model(_config: &Self::Config, device: &B::Device) -> Self {
+        Self::new(device)
+    }
+}
+
 impl<B: Backend> Model<B> {
     pub fn new(device: &B::Device) -> Self {
         let conv1 = ConvBlock::new([1, 8], [3, 3], device); // out: [Batch,8,26,26]
@@ -0,0 +1,156 @@
+//! Face recognition of the detections with [MobileFaceNet] embeddings.
+
+use std::{fs, io, path::Path, sync::Arc};
+
+use burn::tensor::{backend::Backend, Tensor};
+use facenet_burn::{
+    embedding::{image_to_tensor, l2_normalize},
+    mobilefacenet::MobileFaceNet,
+};
+use image::DynamicImage;
+use inference_metrics::Metrics;
+
+/// MobileFaceNet model and the embeddings of the known identities.
+#[derive(Debug)]
+pub struct Recognizer<B: Backend> {
+    model: MobileFaceNet<B>,
+    input_size: usize,
+    threshold: f32,
+    names: Vec<String>,
+    gallery: Vec<Vec<f32>>,
+    device: B::Device,
+    metrics: Option<Arc<Metrics>>,
+}
+
+impl<B: Backend> Recognizer<B> {
+    /// Creates a recognizer with an empty gallery.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - Embedding model.
+    /// * `input_size` - Size of the square model input, to which face crops are resized.
+    /// * `threshold` - Minimum cosine similarity of a recognized identity.
+    /// * `device` - Device of the model.
+    pub fn new(
+        model: MobileFaceNet<B>,
+        input_size: usize,
+        threshold: f32,
+        device: &B::Device,
+    ) -> Self {
+        Self {
+            model,
+            input_size,
+            threshold,
+            names: Vec::new(),
+            gallery: Vec::new(),
+            device: device.clone(),
+            metrics: None,
+        }
+    }
+
+    /// Records the latency of the `embed` stage in the given metrics.
+    pub fn with_metrics(mut self, metrics: Arc<Metrics>) -> Self {
+        self.metrics = Some(metrics);
+        self
+    }
+
+    /// Adds the images of a directory to the gallery, named after their file name (e.g.
+    /// `alice.jpg`), in file name order.
+    ///
+    /// # Returns
+    ///
+    /// The number of added identities.
+    pub fn load_gallery(&mut self, directory: &Path) -> io::Result<usize> {
+        let mut files = fs::read_dir(directory)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        files.retain(|path| image::ImageFormat::from_path(path).is_ok());
+        files.sort();
+
+        let images = files
+            .iter()
+            .map(|path| {
+                image::open(path).map_err(|err| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Failed to load image {}: {err}", path.display()),
+                    )
+                })
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        let names = files
+            .iter()
+            .map(|path| path.file_stem().unwrap().to_string_lossy().into_owned());
+
+        self.add_identities(names, &images);
+
+        Ok(files.len())
+    }
+
+    /// Adds identities to the gallery, from one face crop each.
+    pub fn add_identities(
+        &mut self,
+        names: impl IntoIterator<Item = String>,
+        images: &[DynamicImage],
+    ) {
+        let names: Vec<_> = names.into_iter().collect();
+        assert_eq!(names.len(), images.len(), "Expected one image per identity");
+
+        self.gallery.extend(self.embed(images));
+        self.names.extend(names);
+    }
+
+    /// Unit norm embeddings of the given face crops.
+    pub fn embed(&self, images: &[DynamicImage]) -> Vec<Vec<f32>> {
+        if images.is_empty() {
+            return Vec::new();
+        }
+        let embed = || {
+            let batch = images
+                .iter()
+                .map(|image| image_to_tensor::<B>(image, self.input_size, &self.device))
+                .collect();
+            let embeddings = l2_normalize(self.model.forward(Tensor::stack(batch, 0)));
+            let [_, embedding_size] = embeddings.dims();
+
+            embeddings
+                .into_data()
+                .to_vec::<f32>()
+                .unwrap()
+                .chunks(embedding_size)
+                .map(|embedding| embedding.to_vec())
+                .collect()
+        };
+
+        match &self.metrics {
+            Some(metrics) => metrics.time("embed", embed),
+            None => embed(),
+        }
+    }
+
+    /// Best matching identity and its similarity, if above the threshold.
+    pub fn identify(&self, embedding: &[f32]) -> Option<(String, f32)> {
+        self.gallery
+            .iter()
+            .map(|known| known.iter().zip(embedding).map(|(a, b)| a * b).sum::<f32>())
+            .zip(&self.names)
+            .filter(|(similarity, _)| *similarity >= self.threshold)
+            .max_by(|(a, _), (b, _)| a.total_cmp(b))
+            .map(|(similarity, name)| (name.clone(), similarity))
+    }
+
+    /// Names of the known identities.
+    pub fn identities(&self) -> &[String] {
+        &self.names
+    }
+
+    /// Size of the square model input.
+    pub fn input_size(&self) -> usize {
+        self.input_size
+    }
+
+    /// Minimum cosine similarity of a recognized identity.
+    pub fn threshold(&self) -> f32 {
+        self.threshold
+    }
+}
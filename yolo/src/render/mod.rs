@@ -0,0 +1,319 @@
+mod font;
+
+use alloc::{
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+use burn::config::Config;
+use image::{DynamicImage, Rgba, RgbaImage};
+
+use crate::yolox_model::{BoundingBox, COCO_CLASSES};
+
+use font::{glyph, GLYPH_HEIGHT, GLYPH_WIDTH};
+
+/// Default colors, by class index (Okabe-Ito palette, which is colorblind friendly).
+pub const DEFAULT_PALETTE: [[u8; 3]; 8] = [
+    [239, 62, 5],
+    [0, 114, 178],
+    [0, 158, 115],
+    [230, 159, 0],
+    [204, 121, 167],
+    [86, 180, 233],
+    [213, 94, 0],
+    [240, 228, 66],
+];
+
+/// [Renderer](Renderer) configuration.
+#[derive(Config, Debug)]
+pub struct RendererConfig {
+    /// Thickness of the box outlines, in pixels.
+    #[config(default = "2")]
+    pub thickness: u32,
+    /// Scale of the 5x7 pixels font of the labels. `0` disables the labels.
+    #[config(default = "2")]
+    pub font_scale: u32,
+    /// Whether the confidence is displayed in the labels.
+    #[config(default = "true")]
+    pub show_confidence: bool,
+    /// Radius of the landmark dots, in pixels.
+    #[config(default = "2")]
+    pub landmark_radius: u32,
+    /// Whether boxes are colored by track ID when available, rather than by class.
+    #[config(default = "false")]
+    pub color_by_track: bool,
+    /// Box colors, indexed by class (or track ID) modulo the palette size.
+    #[config(default = "DEFAULT_PALETTE.to_vec()")]
+    pub palette: Vec<[u8; 3]>,
+    /// Class names displayed in the labels. Classes without a name are displayed by index.
+    #[config(default = "COCO_CLASSES.iter().map(|name| name.to_string()).collect()")]
+    pub class_names: Vec<String>,
+}
+
+impl RendererConfig {
+    /// Initialize a new [renderer](Renderer).
+    pub fn init(&self) -> Renderer {
+        Renderer {
+            config: self.clone(),
+        }
+    }
+}
+
+/// An object to draw: a box, with optional tracking, identity and landmarks information.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Annotation {
+    pub class: usize,
+    pub bbox: BoundingBox,
+    pub track_id: Option<u64>,
+    pub identity: Option<String>,
+    /// Landmark `[x, y]` positions, in pixels.
+    pub landmarks: Vec<[f32; 2]>,
+}
+
+impl Annotation {
+    pub fn new(class: usize, bbox: BoundingBox) -> Self {
+        Self {
+            class,
+            bbox,
+            track_id: None,
+            identity: None,
+            landmarks: Vec::new(),
+        }
+    }
+
+    pub fn with_track_id(mut self, track_id: u64) -> Self {
+        self.track_id = Some(track_id);
+        self
+    }
+
+    pub fn with_identity(mut self, identity: impl Into<String>) -> Self {
+        self.identity = Some(identity.into());
+        self
+    }
+
+    pub fn with_landmarks(mut self, landmarks: Vec<[f32; 2]>) -> Self {
+        self.landmarks = landmarks;
+        self
+    }
+
+    /// Annotations of the boxes returned by [nms](crate::yolox_model::boxes::nms) for an image,
+    /// which are grouped per class.
+    pub fn from_class_boxes(boxes: &[Vec<BoundingBox>]) -> Vec<Self> {
+        boxes
+            .iter()
+            .enumerate()
+            .flat_map(|(class, boxes)| boxes.iter().map(move |bbox| Self::new(class, *bbox)))
+            .collect()
+    }
+}
+
+/// Draws boxes, labels and landmarks on images.
+#[derive(Debug, Clone)]
+pub struct Renderer {
+    config: RendererConfig,
+}
+
+impl Renderer {
+    /// Returns a copy of the image annotated with the given objects.
+    pub fn render(&self, image: &DynamicImage, annotations: &[Annotation]) -> RgbaImage {
+        let mut image = image.to_rgba8();
+        self.draw(&mut image, annotations);
+        image
+    }
+
+    /// Draws the annotations on the image, in place.
+    ///
+    /// Boxes may be partially or entirely outside of the image, and have inverted coordinates.
+    pub fn draw(&self, image: &mut RgbaImage, annotations: &[Annotation]) {
+        // Labels are drawn last so that they are not covered by other boxes
+        for annotation in annotations {
+            let color = self.color(annotation);
+            let rect = Rect::from_bbox(&annotation.bbox);
+            for t in 0..self.config.thickness {
+                draw_outline(image, &rect.shrink(t as i64), color);
+            }
+            for [x, y] in annotation.landmarks.iter() {
+                let radius = self.config.landmark_radius as i64;
+                fill_circle(image, *x as i64, *y as i64, radius, color);
+            }
+        }
+        if self.config.font_scale > 0 {
+            for annotation in annotations {
+                self.draw_label(image, annotation);
+            }
+        }
+    }
+
+    /// Text of the label of an annotation, e.g. `person 0.87 #3 alice`.
+    pub fn label(&self, annotation: &Annotation) -> String {
+        let mut label = match self.config.class_names.get(annotation.class) {
+            Some(name) => name.clone(),
+            None => annotation.class.to_string(),
+        };
+        if self.config.show_confidence {
+            label += &format!(" {:.2}", annotation.bbox.confidence);
+        }
+        if let Some(track_id) = annotation.track_id {
+            label += &format!(" #{track_id}");
+        }
+        if let Some(identity) = &annotation.identity {
+            label += &format!(" {identity}");
+        }
+        label
+    }
+
+    /// The renderer configuration.
+    pub fn config(&self) -> &RendererConfig {
+        &self.config
+    }
+
+    fn color(&self, annotation: &Annotation) -> Rgba<u8> {
+        let palette = if self.config.palette.is_empty() {
+            &DEFAULT_PALETTE[..]
+        } else {
+            &self.config.palette[..]
+        };
+        let key = match annotation.track_id {
+            Some(track_id) if self.config.color_by_track => track_id as usize,
+            _ => annotation.class,
+        };
+        let [r, g, b] = palette[key % palette.len()];
+        Rgba([r, g, b, 255])
+    }
+
+    /// Draws the label on a filled background above the box, or inside it at the top of the
+    /// image.
+    fn draw_label(&self, image: &mut RgbaImage, annotation: &Annotation) {
+        let label = self.label(annotation);
+        let scale = self.config.font_scale as i64;
+        let padding = scale;
+        let width = label.chars().count() as i64 * (GLYPH_WIDTH as i64 + 1) * scale + padding;
+        let height = GLYPH_HEIGHT as i64 * scale + 2 * padding;
+
+        let rect = Rect::from_bbox(&annotation.bbox);
+        let x = rect.xmin.clamp(0, (image.width() as i64 - width).max(0));
+        let y = if rect.ymin - height >= 0 {
+            rect.ymin - height
+        } else {
+            rect.ymin.max(0)
+        };
+
+        let background = self.color(annotation);
+        fill_rect(
+            image,
+            &Rect {
+                xmin: x,
+                ymin: y,
+                xmax: x + width - 1,
+                ymax: y + height - 1,
+            },
+            background,
+        );
+
+        // Black or white text, whichever contrasts most with the background
+        let [r, g, b, _] = background.0;
+        let luminance = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+        let foreground = if luminance > 140. {
+            Rgba([0, 0, 0, 255])
+        } else {
+            Rgba([255, 255, 255, 255])
+        };
+
+        for (i, c) in label.chars().enumerate() {
+            let origin_x = x + padding + i as i64 * (GLYPH_WIDTH as i64 + 1) * scale;
+            let origin_y = y + padding;
+            for (col, bits) in glyph(c).iter().enumerate() {
+                for row in 0..GLYPH_HEIGHT as i64 {
+                    if bits & (1 << row) == 0 {
+                        continue;
+                    }
+                    let px = origin_x + col as i64 * scale;
+                    let py = origin_y + row * scale;
+                    fill_rect(
+                        image,
+                        &Rect {
+                            xmin: px,
+                            ymin: py,
+                            xmax: px + scale - 1,
+                            ymax: py + scale - 1,
+                        },
+                        foreground,
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// Rectangle with inclusive integer pixel coordinates, possibly outside of the image.
+#[derive(Debug, Clone, Copy)]
+struct Rect {
+    xmin: i64,
+    ymin: i64,
+    xmax: i64,
+    ymax: i64,
+}
+
+impl Rect {
+    /// Rounds a box to pixels, ordering its coordinates.
+    fn from_bbox(bbox: &BoundingBox) -> Self {
+        let (x1, x2) = (bbox.xmin.round() as i64, bbox.xmax.round() as i64);
+        let (y1, y2) = (bbox.ymin.round() as i64, bbox.ymax.round() as i64);
+        Self {
+            xmin: x1.min(x2),
+            ymin: y1.min(y2),
+            xmax: x1.max(x2),
+            ymax: y1.max(y2),
+        }
+    }
+
+    fn shrink(&self, amount: i64) -> Self {
+        Self {
+            xmin: self.xmin + amount,
+            ymin: self.ymin + amount,
+            xmax: self.xmax - amount,
+            ymax: self.ymax - amount,
+        }
+    }
+}
+
+fn put_pixel(image: &mut RgbaImage, x: i64, y: i64, color: Rgba<u8>) {
+    if x >= 0 && y >= 0 && x < image.width() as i64 && y < image.height() as i64 {
+        image.put_pixel(x as u32, y as u32, color);
+    }
+}
+
+fn draw_outline(image: &mut RgbaImage, rect: &Rect, color: Rgba<u8>) {
+    if rect.xmin > rect.xmax || rect.ymin > rect.ymax {
+        return;
+    }
+    // Only iterate over the visible part of the outline
+    let (w, h) = (image.width() as i64, image.height() as i64);
+    for x in rect.xmin.max(0)..=rect.xmax.min(w - 1) {
+        put_pixel(image, x, rect.ymin, color);
+        put_pixel(image, x, rect.ymax, color);
+    }
+    for y in rect.ymin.max(0)..=rect.ymax.min(h - 1) {
+        put_pixel(image, rect.xmin, y, color);
+        put_pixel(image, rect.xmax, y, color);
+    }
+}
+
+fn fill_rect(image: &mut RgbaImage, rect: &Rect, color: Rgba<u8>) {
+    let (w, h) = (image.width() as i64, image.height() as i64);
+    for y in rect.ymin.max(0)..=rect.ymax.min(h - 1) {
+        for x in rect.xmin.max(0)..=rect.xmax.min(w - 1) {
+            image.put_pixel(x as u32, y as u32, color);
+        }
+    }
+}
+
+fn fill_circle(image: &mut RgbaImage, cx: i64, cy: i64, radius: i64, color: Rgba<u8>) {
+    for y in cy - radius..=cy + radius {
+        for x in cx - radius..=cx + radius {
+            if (x - cx).pow(2) + (y - cy).pow(2) <= radius.pow(2) {
+                put_pixel(image, x, y, color);
+            }
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use std::{
+    collections::BTreeMap,
+    io::{self, Read, Write},
+};
+
+use serde::{Deserialize, Serialize};
+
+use super::{invalid_data, DetectionResult, FrameResult};
+
+/// COCO category IDs of the 80 classes, which are not contiguous.
+pub const COCO_CATEGORY_IDS: [usize; 80] = [
+    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 27, 28,
+    31, 32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 46, 47, 48, 49, 50, 51, 52, 53, 54, 55,
+    56, 57, 58, 59, 60, 61, 62, 63, 64, 65, 67, 70, 72, 73, 74, 75, 76, 77, 78, 79, 80, 81, 82, 84,
+    85, 86, 87, 88, 89, 90,
+];
+
+#[derive(Serialize, Deserialize)]
+struct CocoDetection {
+    image_id: usize,
+    category_id: usize,
+    /// `[x, y, width, height]` in pixels.
+    bbox: [f32; 4],
+    score: f32,
+}
+
+/// Writes [COCO detection results](https://cocodataset.org/#format-results), with the frame
+/// index as image ID, e.g. for evaluation with `pycocotools`.
+///
+/// Only the 80 COCO classes can be written.
+pub struct CocoWriter<W: Write> {
+    writer: W,
+    empty: bool,
+}
+
+impl<W: Write> CocoWriter<W> {
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        writer.write_all(b"[")?;
+        Ok(Self {
+            writer,
+            empty: true,
+        })
+    }
+
+    /// Writes the detections of a frame.
+    pub fn write(&mut self, frame: &FrameResult) -> io::Result<()> {
+        for detection in frame.detections.iter() {
+            let category_id = *COCO_CATEGORY_IDS.get(detection.class).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("Class {} is not a COCO class", detection.class),
+                )
+            })?;
+            let [xmin, ymin, xmax, ymax] = detection.bbox;
+            let coco = CocoDetection {
+                image_id: frame.frame,
+                category_id,
+                bbox: [xmin, ymin, xmax - xmin, ymax - ymin],
+                score: detection.confidence,
+            };
+
+            self.writer
+                .write_all(if self.empty { b"\n" } else { b",\n" })?;
+            serde_json::to_writer(&mut self.writer, &coco)?;
+            self.empty = false;
+        }
+
+        Ok(())
+    }
+
+    /// Closes the JSON array, and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.writer.write_all(b"\n]\n")?;
+        Ok(self.writer)
+    }
+}
+
+/// Reads COCO detection results.
+///
+/// # Returns
+///
+/// The detections grouped per image ID, in increasing order. The format has no image names or
+/// sizes, so they are left empty.
+pub fn read_coco<R: Read>(reader: R) -> io::Result<Vec<FrameResult>> {
+    let detections: Vec<CocoDetection> = serde_json::from_reader(reader)?;
+
+    let mut frames = BTreeMap::new();
+    for coco in detections {
+        let class = COCO_CATEGORY_IDS
+            .iter()
+            .position(|id| *id == coco.category_id)
+            .ok_or_else(|| invalid_data(format!("Unknown category ID {}", coco.category_id)))?;
+        let [x, y, width, height] = coco.bbox;
+        let detection = DetectionResult {
+            class,
+            confidence: coco.score,
+            bbox: [x, y, x + width, y + height],
+            track_id: None,
+            identity: None,
+            similarity: None,
+        };
+
+        frames
+            .entry(coco.image_id)
+            .or_insert_with(|| FrameResult {
+                frame: coco.image_id,
+                name: String::new(),
+                width: 0,
+                height: 0,
+                detections: Vec::new(),
+            })
+            .detections
+            .push(detection);
+    }
+
+    Ok(frames.into_values().collect())
+}
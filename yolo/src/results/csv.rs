@@ -0,0 +1,99 @@
+use std::io::{self, Read, Write};
+
+use serde::{Deserialize, Serialize};
+
+use super::{DetectionResult, FrameResult};
+
+/// A detection with the information of its frame.
+#[derive(Serialize, Deserialize)]
+struct Row {
+    frame: usize,
+    name: String,
+    width: u32,
+    height: u32,
+    class: usize,
+    confidence: f32,
+    xmin: f32,
+    ymin: f32,
+    xmax: f32,
+    ymax: f32,
+    track_id: Option<u64>,
+    identity: Option<String>,
+    similarity: Option<f32>,
+}
+
+/// Writes detections as CSV, with a header and one row per detection. Missing track IDs and
+/// identities are written as empty fields.
+pub struct CsvWriter<W: Write> {
+    writer: ::csv::Writer<W>,
+}
+
+impl<W: Write> CsvWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: ::csv::Writer::from_writer(writer),
+        }
+    }
+
+    /// Writes the detections of a frame.
+    pub fn write(&mut self, frame: &FrameResult) -> io::Result<()> {
+        for detection in frame.detections.iter() {
+            let [xmin, ymin, xmax, ymax] = detection.bbox;
+            self.writer.serialize(Row {
+                frame: frame.frame,
+                name: frame.name.clone(),
+                width: frame.width,
+                height: frame.height,
+                class: detection.class,
+                confidence: detection.confidence,
+                xmin,
+                ymin,
+                xmax,
+                ymax,
+                track_id: detection.track_id,
+                identity: detection.identity.clone(),
+                similarity: detection.similarity,
+            })?;
+        }
+
+        Ok(())
+    }
+
+    /// Flushes the rows, and returns the underlying writer.
+    pub fn finish(self) -> io::Result<W> {
+        self.writer.into_inner().map_err(|err| err.into_error())
+    }
+}
+
+/// Reads detections written as CSV, grouped per frame in order of appearance. Frames without
+/// detections have no rows, so they are not returned.
+pub fn read_csv<R: Read>(reader: R) -> io::Result<Vec<FrameResult>> {
+    let mut frames: Vec<FrameResult> = Vec::new();
+
+    for row in ::csv::Reader::from_reader(reader).deserialize() {
+        let row: Row = row?;
+        let detection = DetectionResult {
+            class: row.class,
+            confidence: row.confidence,
+            bbox: [row.xmin, row.ymin, row.xmax, row.ymax],
+            track_id: row.track_id,
+            identity: row.identity,
+            similarity: row.similarity,
+        };
+
+        match frames.last_mut() {
+            Some(frame) if frame.frame == row.frame && frame.name == row.name => {
+                frame.detections.push(detection)
+            }
+            _ => frames.push(FrameResult {
+                frame: row.frame,
+                name: row.name,
+                width: row.width,
+                height: row.height,
+                detections: vec![detection],
+            }),
+        }
+    }
+
+    Ok(frames)
+}
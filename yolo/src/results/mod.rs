@@ -0,0 +1,210 @@
+//! Serializable detection results, and writers and readers for common annotation formats.
+
+mod coco;
+mod csv;
+mod voc;
+mod yolo;
+
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::yolox_model::BoundingBox;
+
+pub use self::csv::{read_csv, CsvWriter};
+pub use coco::{read_coco, CocoWriter, COCO_CATEGORY_IDS};
+pub use voc::{read_voc, write_voc};
+pub use yolo::{read_yolo, write_yolo};
+
+/// Detections of a frame or an image.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FrameResult {
+    /// Index of the frame, used as the image ID of the COCO format.
+    pub frame: usize,
+    /// File name of the frame.
+    pub name: String,
+    pub width: u32,
+    pub height: u32,
+    pub detections: Vec<DetectionResult>,
+}
+
+/// A detected object.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DetectionResult {
+    pub class: usize,
+    pub confidence: f32,
+    /// `[xmin, ymin, xmax, ymax]` in pixels.
+    pub bbox: [f32; 4],
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub track_id: Option<u64>,
+    /// Recognized identity.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub identity: Option<String>,
+    /// Similarity of the recognized identity.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub similarity: Option<f32>,
+}
+
+impl DetectionResult {
+    pub fn new(class: usize, bbox: &BoundingBox, track_id: Option<u64>) -> Self {
+        Self {
+            class,
+            confidence: bbox.confidence,
+            bbox: [bbox.xmin, bbox.ymin, bbox.xmax, bbox.ymax],
+            track_id,
+            identity: None,
+            similarity: None,
+        }
+    }
+
+    pub fn with_identity(mut self, identity: Option<String>, similarity: Option<f32>) -> Self {
+        self.identity = identity;
+        self.similarity = similarity;
+        self
+    }
+
+    /// The detected box, with its confidence.
+    pub fn bounding_box(&self) -> BoundingBox {
+        let [xmin, ymin, xmax, ymax] = self.bbox;
+        BoundingBox {
+            xmin,
+            ymin,
+            xmax,
+            ymax,
+            confidence: self.confidence,
+        }
+    }
+}
+
+/// Format of the detection results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResultFormat {
+    /// One JSON serialized [FrameResult] per line, in a single file.
+    JsonLines,
+    /// [COCO detection results](https://cocodataset.org/#format-results), a JSON array of
+    /// detections in a single file.
+    Coco,
+    /// One `<class> <x center> <y center> <width> <height> <confidence>` line per detection, with
+    /// coordinates normalized by the image size, in one `.txt` file per frame.
+    Yolo,
+    /// Pascal VOC annotations, in one `.xml` file per frame.
+    Voc,
+    /// One row per detection, in a single file.
+    Csv,
+}
+
+impl ResultFormat {
+    /// Whether the results are written to one file per frame, in a directory.
+    pub fn is_per_frame(&self) -> bool {
+        matches!(self, Self::Yolo | Self::Voc)
+    }
+
+    /// Default path of the results in the output directory.
+    pub fn default_path(&self, output: &Path) -> PathBuf {
+        output.join(match self {
+            Self::JsonLines => "results.jsonl",
+            Self::Coco => "results.json",
+            Self::Yolo => "labels",
+            Self::Voc => "annotations",
+            Self::Csv => "results.csv",
+        })
+    }
+}
+
+/// Writes the frame results in a [format](ResultFormat), to a file or to a directory of
+/// per-frame files.
+pub enum ResultWriter {
+    JsonLines(BufWriter<File>),
+    Coco(CocoWriter<BufWriter<File>>),
+    Yolo(PathBuf),
+    Voc {
+        directory: PathBuf,
+        class_names: Vec<String>,
+    },
+    Csv(Box<CsvWriter<BufWriter<File>>>),
+}
+
+impl ResultWriter {
+    /// Creates the results file, or directory for [per-frame](ResultFormat::is_per_frame)
+    /// formats.
+    ///
+    /// # Arguments
+    ///
+    /// * `format` - Format of the results.
+    /// * `path` - Path of the results file or directory.
+    /// * `class_names` - Class names, written as the object names of the VOC format.
+    pub fn create(format: ResultFormat, path: &Path, class_names: &[String]) -> io::Result<Self> {
+        if format.is_per_frame() {
+            fs::create_dir_all(path)?;
+        }
+        let file = || File::create(path).map(BufWriter::new);
+
+        Ok(match format {
+            ResultFormat::JsonLines => Self::JsonLines(file()?),
+            ResultFormat::Coco => Self::Coco(CocoWriter::new(file()?)?),
+            ResultFormat::Yolo => Self::Yolo(path.to_path_buf()),
+            ResultFormat::Voc => Self::Voc {
+                directory: path.to_path_buf(),
+                class_names: class_names.to_vec(),
+            },
+            ResultFormat::Csv => Self::Csv(Box::new(CsvWriter::new(file()?))),
+        })
+    }
+
+    /// Writes the results of a frame.
+    pub fn write(&mut self, frame: &FrameResult) -> io::Result<()> {
+        let per_frame_file = |directory: &Path, extension: &str| {
+            let path = directory.join(Path::new(&frame.name).with_extension(extension));
+            File::create(path).map(BufWriter::new)
+        };
+
+        match self {
+            Self::JsonLines(writer) => {
+                serde_json::to_writer(&mut *writer, frame)?;
+                writeln!(writer)
+            }
+            Self::Coco(writer) => writer.write(frame),
+            Self::Yolo(directory) => {
+                let mut writer = per_frame_file(directory, "txt")?;
+                write_yolo(&mut writer, frame)?;
+                writer.flush()
+            }
+            Self::Voc {
+                directory,
+                class_names,
+            } => {
+                let mut writer = per_frame_file(directory, "xml")?;
+                write_voc(&mut writer, frame, class_names)?;
+                writer.flush()
+            }
+            Self::Csv(writer) => writer.write(frame),
+        }
+    }
+
+    /// Completes and flushes the results file.
+    pub fn finish(self) -> io::Result<()> {
+        match self {
+            Self::JsonLines(mut writer) => writer.flush(),
+            Self::Coco(writer) => writer.finish()?.flush(),
+            Self::Yolo(_) | Self::Voc { .. } => Ok(()),
+            Self::Csv(writer) => writer.finish()?.flush(),
+        }
+    }
+}
+
+/// Reads results written in the [JSON lines](ResultFormat::JsonLines) format.
+pub fn read_json_lines<R: BufRead>(reader: R) -> io::Result<Vec<FrameResult>> {
+    reader
+        .lines()
+        .filter(|line| !line.as_ref().is_ok_and(|line| line.trim().is_empty()))
+        .map(|line| Ok(serde_json::from_str(&line?)?))
+        .collect()
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
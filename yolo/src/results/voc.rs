@@ -0,0 +1,133 @@
+use std::io::{self, Read, Write};
+
+use super::{invalid_data, DetectionResult, FrameResult};
+
+/// Writes the detections of a frame as a Pascal VOC annotation. The confidence is written in a
+/// non-standard `confidence` element of the objects, which VOC tools ignore.
+///
+/// As in VOC, boxes are written as the 1-based indices of their first and last pixels, rounded.
+///
+/// Classes without a name are written by index.
+pub fn write_voc<W: Write>(
+    writer: &mut W,
+    frame: &FrameResult,
+    class_names: &[String],
+) -> io::Result<()> {
+    writeln!(writer, "<annotation>")?;
+    writeln!(writer, "  <filename>{}</filename>", escape(&frame.name))?;
+    writeln!(writer, "  <size>")?;
+    writeln!(writer, "    <width>{}</width>", frame.width)?;
+    writeln!(writer, "    <height>{}</height>", frame.height)?;
+    writeln!(writer, "    <depth>3</depth>")?;
+    writeln!(writer, "  </size>")?;
+    for detection in frame.detections.iter() {
+        let name = match class_names.get(detection.class) {
+            Some(name) => escape(name),
+            None => detection.class.to_string(),
+        };
+        // A box from 0 to 10 covers the 1st to the 10th pixel
+        let [xmin, ymin, xmax, ymax] = detection.bbox.map(|value| value.round() as i64);
+        let (xmin, ymin) = (xmin + 1, ymin + 1);
+        let (xmax, ymax) = (xmax.max(xmin), ymax.max(ymin));
+        writeln!(writer, "  <object>")?;
+        writeln!(writer, "    <name>{name}</name>")?;
+        writeln!(writer, "    <pose>Unspecified</pose>")?;
+        writeln!(writer, "    <truncated>0</truncated>")?;
+        writeln!(writer, "    <difficult>0</difficult>")?;
+        writeln!(
+            writer,
+            "    <confidence>{:.6}</confidence>",
+            detection.confidence
+        )?;
+        writeln!(writer, "    <bndbox>")?;
+        writeln!(writer, "      <xmin>{xmin}</xmin>")?;
+        writeln!(writer, "      <ymin>{ymin}</ymin>")?;
+        writeln!(writer, "      <xmax>{xmax}</xmax>")?;
+        writeln!(writer, "      <ymax>{ymax}</ymax>")?;
+        writeln!(writer, "    </bndbox>")?;
+        writeln!(writer, "  </object>")?;
+    }
+    writeln!(writer, "</annotation>")
+}
+
+/// Reads a Pascal VOC annotation. Object names are looked up in `class_names`, or parsed as
+/// class indices. Objects without confidence have a confidence of 1.
+///
+/// Boxes are converted back from 1-based pixel indices, which may also be fractional.
+pub fn read_voc<R: Read>(mut reader: R, class_names: &[String]) -> io::Result<FrameResult> {
+    let mut xml = String::new();
+    reader.read_to_string(&mut xml)?;
+    let document = roxmltree::Document::parse(&xml).map_err(|err| invalid_data(err.to_string()))?;
+    let annotation = document.root_element();
+
+    let mut frame = FrameResult {
+        frame: 0,
+        name: text(&annotation, "filename")
+            .unwrap_or_default()
+            .to_string(),
+        width: 0,
+        height: 0,
+        detections: Vec::new(),
+    };
+    if let Some(size) = child(&annotation, "size") {
+        frame.width = parse(&size, "width")?;
+        frame.height = parse(&size, "height")?;
+    }
+
+    for object in annotation
+        .children()
+        .filter(|node| node.has_tag_name("object"))
+    {
+        let name = text(&object, "name").ok_or_else(|| invalid_data("Missing object name"))?;
+        let class = class_names
+            .iter()
+            .position(|class| class == name)
+            .or_else(|| name.parse().ok())
+            .ok_or_else(|| invalid_data(format!("Unknown class {name}")))?;
+        let confidence = match text(&object, "confidence") {
+            Some(_) => parse(&object, "confidence")?,
+            None => 1.,
+        };
+        let bndbox =
+            child(&object, "bndbox").ok_or_else(|| invalid_data("Missing object bndbox"))?;
+
+        frame.detections.push(DetectionResult {
+            class,
+            confidence,
+            bbox: [
+                parse::<f32>(&bndbox, "xmin")? - 1.,
+                parse::<f32>(&bndbox, "ymin")? - 1.,
+                parse(&bndbox, "xmax")?,
+                parse(&bndbox, "ymax")?,
+            ],
+            track_id: None,
+            identity: None,
+            similarity: None,
+        });
+    }
+
+    Ok(frame)
+}
+
+fn child<'a, 'input>(
+    node: &roxmltree::Node<'a, 'input>,
+    tag: &str,
+) -> Option<roxmltree::Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(tag))
+}
+
+fn text<'a>(node: &roxmltree::Node<'a, '_>, tag: &str) -> Option<&'a str> {
+    child(node, tag).map(|child| child.text().unwrap_or_default().trim())
+}
+
+fn parse<T: std::str::FromStr>(node: &roxmltree::Node, tag: &str) -> io::Result<T> {
+    text(node, tag)
+        .and_then(|value| value.parse().ok())
+        .ok_or_else(|| invalid_data(format!("Missing or invalid {tag}")))
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,82 @@
+use std::io::{self, BufRead, Write};
+
+use super::{invalid_data, DetectionResult, FrameResult};
+
+/// Writes the detections of a frame in the YOLO format: one
+/// `<class> <x center> <y center> <width> <height> <confidence>` line per detection, with
+/// coordinates normalized by the frame size, which must not be empty.
+pub fn write_yolo<W: Write>(writer: &mut W, frame: &FrameResult) -> io::Result<()> {
+    if frame.width == 0 || frame.height == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!(
+                "Cannot normalize the boxes of the {}x{} frame {}",
+                frame.width, frame.height, frame.name
+            ),
+        ));
+    }
+    let (width, height) = (frame.width as f32, frame.height as f32);
+    for detection in frame.detections.iter() {
+        let [xmin, ymin, xmax, ymax] = detection.bbox;
+        writeln!(
+            writer,
+            "{} {:.6} {:.6} {:.6} {:.6} {:.6}",
+            detection.class,
+            (xmin + xmax) / 2. / width,
+            (ymin + ymax) / 2. / height,
+            (xmax - xmin) / width,
+            (ymax - ymin) / height,
+            detection.confidence,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Reads detections in the YOLO format, scaled to a frame of the given size. The confidence
+/// column is optional, as in ground truth labels, and defaults to 1.
+pub fn read_yolo<R: BufRead>(
+    reader: R,
+    width: u32,
+    height: u32,
+) -> io::Result<Vec<DetectionResult>> {
+    let (width, height) = (width as f32, height as f32);
+    let mut detections = Vec::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let invalid_line = || invalid_data(format!("Invalid YOLO label line: {line}"));
+        let mut columns = line.split_whitespace();
+        let class = columns
+            .next()
+            .and_then(|class| class.parse().ok())
+            .ok_or_else(invalid_line)?;
+        let values = columns
+            .map(|value| value.parse::<f32>().map_err(|_| invalid_line()))
+            .collect::<io::Result<Vec<_>>>()?;
+        let (&[x, y, w, h], confidence) = match values.as_slice() {
+            [x, y, w, h] => (&[*x, *y, *w, *h], 1.),
+            [x, y, w, h, confidence] => (&[*x, *y, *w, *h], *confidence),
+            _ => return Err(invalid_line()),
+        };
+
+        detections.push(DetectionResult {
+            class,
+            confidence,
+            bbox: [
+                (x - w / 2.) * width,
+                (y - h / 2.) * height,
+                (x + w / 2.) * width,
+                (y + h / 2.) * height,
+            ],
+            track_id: None,
+            identity: None,
+            similarity: None,
+        });
+    }
+
+    Ok(detections)
+}
@@ -0,0 +1,346 @@
+use alloc::{vec, vec::Vec};
+use burn::config::Config;
+use image::{imageops::FilterType, DynamicImage, GrayImage};
+
+use crate::{
+    tracking::{linear_assignment, TrackIds, Tracker, TrackerConfig},
+    yolox_model::{boxes::iou, BoundingBox},
+};
+
+#[cfg(not(target_family = "wasm"))]
+use {crate::detector::Detector, burn::tensor::backend::Backend, std::time::Instant};
+
+/// How boxes are propagated to the frames on which detection is skipped.
+#[derive(Config, Debug, PartialEq, Eq)]
+pub enum Propagation {
+    /// Boxes are tracked with a [tracker](Tracker) and extrapolated with its motion model.
+    Tracker,
+    /// Boxes are smoothed with an exponential moving average over the detection frames and kept
+    /// in place in between.
+    Smoothing,
+}
+
+/// [Scheduler](Scheduler) configuration.
+#[derive(Config, Debug)]
+pub struct SchedulerConfig {
+    /// Detection runs at least once every `detection_interval` frames. `1` runs it on every
+    /// frame.
+    #[config(default = "5")]
+    pub detection_interval: usize,
+    /// Detection also runs when the mean absolute pixel difference (in `[0, 1]`) between the
+    /// frame and the last detected frame exceeds this threshold.
+    #[config(default = "0.05")]
+    pub motion_threshold: f32,
+    /// Size of the grayscale thumbnails compared to detect motion.
+    #[config(default = "32")]
+    pub motion_size: u32,
+    /// Target average processing time per frame, in milliseconds. When set, detection is run
+    /// less often than `detection_interval` if needed, based on its measured latency.
+    #[config(default = "None")]
+    pub latency_budget_ms: Option<f64>,
+    /// How boxes are propagated between detections.
+    #[config(default = "Propagation::Tracker")]
+    pub propagation: Propagation,
+    /// Tracker used with [Propagation::Tracker]. Its IoU threshold is also used to match boxes
+    /// between detections with [Propagation::Smoothing].
+    #[config(default = "TrackerConfig::new().with_min_hits(1)")]
+    pub tracker: TrackerConfig,
+    /// Weight of the new detection in the exponential moving average of
+    /// [Propagation::Smoothing].
+    #[config(default = "0.6")]
+    pub smoothing_factor: f32,
+}
+
+impl SchedulerConfig {
+    /// Initialize a new [scheduler](Scheduler).
+    pub fn init(&self) -> Scheduler {
+        Scheduler {
+            config: self.clone(),
+            frames_since_detection: 0,
+            latency_ms: None,
+            reference: None,
+            pending: None,
+            trackers: Vec::new(),
+            track_ids: TrackIds::default(),
+            smoothed: Vec::new(),
+        }
+    }
+}
+
+/// A box reported by the [scheduler](Scheduler).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    pub class: usize,
+    pub bbox: BoundingBox,
+    /// Track ID, with [Propagation::Tracker].
+    pub track_id: Option<u64>,
+}
+
+/// Boxes of a frame processed by the [scheduler](Scheduler).
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScheduledFrame {
+    pub detections: Vec<Detection>,
+    /// Whether detection was run on the frame, or the boxes were propagated.
+    pub detected: bool,
+}
+
+/// Decides on which frames of a real-time stream the (slow) detector runs, and propagates the
+/// boxes to the other frames.
+///
+/// Detection runs every [detection interval](SchedulerConfig::detection_interval) frames, or
+/// earlier when motion is detected, within the [latency budget](SchedulerConfig::latency_budget_ms).
+///
+/// The scheduler does not run the detector itself, so that it can be driven from any runtime:
+///
+/// ```ignore
+/// let frame = if scheduler.should_detect(&image) {
+///     let start = now();
+///     let boxes = detector.detect(&image);
+///     scheduler.on_detection(boxes, now() - start)
+/// } else {
+///     scheduler.on_skip()
+/// };
+/// ```
+#[derive(Debug, Clone)]
+pub struct Scheduler {
+    config: SchedulerConfig,
+    frames_since_detection: usize,
+    /// Exponential moving average of the detection latency.
+    latency_ms: Option<f64>,
+    /// Thumbnail of the last detected frame.
+    reference: Option<GrayImage>,
+    /// Thumbnail of the frame being detected.
+    pending: Option<GrayImage>,
+    /// One tracker per class, sharing the track IDs.
+    trackers: Vec<Tracker>,
+    track_ids: TrackIds,
+    smoothed: Vec<Vec<BoundingBox>>,
+}
+
+impl Scheduler {
+    /// Whether detection should run on the given frame. Must be followed by a call to
+    /// [on_detection](Scheduler::on_detection) if `true`, [on_skip](Scheduler::on_skip)
+    /// otherwise.
+    pub fn should_detect(&mut self, frame: &DynamicImage) -> bool {
+        let thumbnail = frame
+            .resize_exact(
+                self.config.motion_size,
+                self.config.motion_size,
+                FilterType::Triangle,
+            )
+            .into_luma8();
+        self.frames_since_detection += 1;
+
+        let detect = match &self.reference {
+            None => true,
+            Some(reference) => {
+                let min_interval = self.min_interval();
+                let interval = self.config.detection_interval.max(min_interval);
+                self.frames_since_detection >= interval
+                    || (self.frames_since_detection >= min_interval
+                        && motion(reference, &thumbnail) > self.config.motion_threshold)
+            }
+        };
+        self.pending = Some(thumbnail);
+
+        detect
+    }
+
+    /// Updates the propagated boxes with the detections of the current frame.
+    ///
+    /// # Arguments
+    ///
+    /// * `boxes` - Detected boxes, grouped per class.
+    /// * `latency_ms` - Time the detection took, in milliseconds.
+    pub fn on_detection(
+        &mut self,
+        boxes: Vec<Vec<BoundingBox>>,
+        latency_ms: f64,
+    ) -> ScheduledFrame {
+        self.frames_since_detection = 0;
+        self.reference = self.pending.take();
+        self.latency_ms = Some(match self.latency_ms {
+            Some(average) => 0.8 * average + 0.2 * latency_ms,
+            None => latency_ms,
+        });
+
+        let detections = match self.config.propagation {
+            Propagation::Tracker => {
+                if self.trackers.len() < boxes.len() {
+                    let (config, ids) = (&self.config.tracker, &self.track_ids);
+                    self.trackers
+                        .resize_with(boxes.len(), || config.init_with_ids(ids));
+                }
+                for (class, tracker) in self.trackers.iter_mut().enumerate() {
+                    tracker.update(boxes.get(class).map_or(&[], |boxes| boxes.as_slice()));
+                }
+                self.tracked()
+            }
+            Propagation::Smoothing => {
+                self.smoothed
+                    .resize_with(boxes.len().max(self.smoothed.len()), Vec::new);
+                for (smoothed, boxes) in self
+                    .smoothed
+                    .iter_mut()
+                    .zip(boxes.into_iter().chain(core::iter::repeat_with(Vec::new)))
+                {
+                    *smoothed = smooth(
+                        smoothed,
+                        boxes,
+                        self.config.smoothing_factor,
+                        self.config.tracker.iou_threshold,
+                    );
+                }
+                self.smoothed_detections()
+            }
+        };
+
+        ScheduledFrame {
+            detections,
+            detected: true,
+        }
+    }
+
+    /// Propagates the boxes to a frame on which detection was skipped.
+    pub fn on_skip(&mut self) -> ScheduledFrame {
+        self.pending = None;
+
+        let detections = match self.config.propagation {
+            Propagation::Tracker => {
+                for tracker in self.trackers.iter_mut() {
+                    tracker.predict();
+                }
+                self.tracked()
+            }
+            Propagation::Smoothing => self.smoothed_detections(),
+        };
+
+        ScheduledFrame {
+            detections,
+            detected: false,
+        }
+    }
+
+    /// Runs the detector on the frame if scheduled, timing it with the system clock.
+    #[cfg(not(target_family = "wasm"))]
+    pub fn run<B: Backend>(
+        &mut self,
+        detector: &Detector<B>,
+        frame: &DynamicImage,
+    ) -> ScheduledFrame {
+        if self.should_detect(frame) {
+            let start = Instant::now();
+            let boxes = detector.detect(frame);
+            self.on_detection(boxes, start.elapsed().as_secs_f64() * 1000.)
+        } else {
+            self.on_skip()
+        }
+    }
+
+    /// Average detection latency, in milliseconds.
+    pub fn detection_latency_ms(&self) -> Option<f64> {
+        self.latency_ms
+    }
+
+    /// The scheduler configuration.
+    pub fn config(&self) -> &SchedulerConfig {
+        &self.config
+    }
+
+    /// Forgets the propagated boxes, e.g. when the stream changes. The next frame is detected.
+    pub fn reset(&mut self) {
+        self.frames_since_detection = 0;
+        self.reference = None;
+        self.pending = None;
+        self.trackers.iter_mut().for_each(Tracker::reset);
+        self.smoothed.clear();
+    }
+
+    /// Minimum number of frames between two detections to stay within the latency budget.
+    fn min_interval(&self) -> usize {
+        match (self.config.latency_budget_ms, self.latency_ms) {
+            (Some(budget), Some(latency)) if budget > 0. => (latency / budget).ceil() as usize,
+            _ => 1,
+        }
+        .max(1)
+    }
+
+    fn tracked(&self) -> Vec<Detection> {
+        self.trackers
+            .iter()
+            .enumerate()
+            .flat_map(|(class, tracker)| {
+                tracker.confirmed().map(move |track| Detection {
+                    class,
+                    bbox: track.bbox(),
+                    track_id: Some(track.id()),
+                })
+            })
+            .collect()
+    }
+
+    fn smoothed_detections(&self) -> Vec<Detection> {
+        self.smoothed
+            .iter()
+            .enumerate()
+            .flat_map(|(class, boxes)| {
+                boxes.iter().map(move |bbox| Detection {
+                    class,
+                    bbox: *bbox,
+                    track_id: None,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Mean absolute difference between two thumbnails, in `[0, 1]`.
+fn motion(a: &GrayImage, b: &GrayImage) -> f32 {
+    let total: u64 = a
+        .as_raw()
+        .iter()
+        .zip(b.as_raw())
+        .map(|(a, b)| a.abs_diff(*b) as u64)
+        .sum();
+
+    total as f32 / (a.as_raw().len().max(1) as f32 * 255.)
+}
+
+/// Matches the new boxes with the previous ones by IoU and averages the matched pairs.
+/// Unmatched new boxes are kept as is, unmatched previous boxes are dropped.
+fn smooth(
+    previous: &[BoundingBox],
+    boxes: Vec<BoundingBox>,
+    factor: f32,
+    iou_threshold: f32,
+) -> Vec<BoundingBox> {
+    let cost: Vec<Vec<f32>> = boxes
+        .iter()
+        .map(|new| previous.iter().map(|prev| 1. - iou(new, prev)).collect())
+        .collect();
+    let mut matched = vec![None; boxes.len()];
+    for (new, prev) in linear_assignment(&cost) {
+        if 1. - cost[new][prev] >= iou_threshold {
+            matched[new] = Some(prev);
+        }
+    }
+
+    let mix = |new: f32, prev: f32| factor * new + (1. - factor) * prev;
+    boxes
+        .into_iter()
+        .zip(matched)
+        .map(|(new, prev)| match prev {
+            Some(prev) => {
+                let prev = &previous[prev];
+                BoundingBox {
+                    xmin: mix(new.xmin, prev.xmin),
+                    ymin: mix(new.ymin, prev.ymin),
+                    xmax: mix(new.xmax, prev.xmax),
+                    ymax: mix(new.ymax, prev.ymax),
+                    confidence: new.confidence,
+                }
+            }
+            None => new,
+        })
+        .collect()
+}
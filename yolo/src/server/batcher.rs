@@ -0,0 +1,262 @@
+use std::{
+    fmt,
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, RecvTimeoutError, SyncSender, TrySendError},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use burn::{config::Config, tensor::backend::Backend};
+use image::DynamicImage;
+use serde::Serialize;
+
+use crate::{detector::Detector, yolox_model::BoundingBox};
+
+/// [Batcher](Batcher) configuration.
+#[derive(Config, Debug)]
+pub struct BatcherConfig {
+    /// Maximum number of images per forward pass.
+    #[config(default = "8")]
+    pub max_batch_size: usize,
+    /// Maximum time the first request of a batch waits for other requests, in milliseconds.
+    #[config(default = "5.")]
+    pub max_wait_ms: f64,
+    /// Maximum number of requests waiting for a batch. Requests are rejected with
+    /// [BatchError::QueueFull] beyond it.
+    #[config(default = "64")]
+    pub max_queue_size: usize,
+}
+
+impl BatcherConfig {
+    /// Initialize a new [batcher](Batcher), running the detector on a dedicated thread.
+    ///
+    /// # Panics
+    ///
+    /// If the config is not [valid](BatcherConfig::validate).
+    pub fn init<B: Backend>(&self, detector: Detector<B>) -> Batcher {
+        if let Err(err) = self.validate() {
+            panic!("invalid batcher config: {err}");
+        }
+        let (sender, receiver) = mpsc::sync_channel(self.max_queue_size);
+        let shared = Arc::new(Shared::default());
+
+        let config = self.clone();
+        let worker_shared = shared.clone();
+        thread::Builder::new()
+            .name("detection-batcher".into())
+            .spawn(move || run(detector, receiver, config, worker_shared))
+            .expect("Failed to spawn the batcher thread");
+
+        Batcher {
+            sender,
+            shared,
+            config: self.clone(),
+        }
+    }
+
+    /// Checks that batches can be formed with the config: a zero queue size would reject every
+    /// request.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_batch_size == 0 {
+            return Err("The maximum batch size must be positive".into());
+        }
+        if Duration::try_from_secs_f64(self.max_wait_ms / 1000.).is_err() {
+            return Err(format!(
+                "The maximum wait must be a non-negative number of milliseconds, got {}",
+                self.max_wait_ms
+            ));
+        }
+        if self.max_queue_size == 0 {
+            return Err("The maximum queue size must be positive".into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Error of a batched detection request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchError {
+    /// Too many requests are waiting, the request should be retried later.
+    QueueFull,
+    /// The detector panicked on the batch of the request. Later requests are still served.
+    Failed,
+    /// The batcher thread stopped.
+    Stopped,
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::QueueFull => write!(f, "Detection queue is full, retry later"),
+            Self::Failed => write!(f, "Detection failed"),
+            Self::Stopped => write!(f, "Detection worker stopped"),
+        }
+    }
+}
+
+impl std::error::Error for BatchError {}
+
+/// Statistics of the batches run by a [batcher](Batcher).
+#[derive(Serialize, Debug, Clone, Default, PartialEq)]
+pub struct BatchMetrics {
+    /// Number of forward passes.
+    pub batches: u64,
+    /// Number of detected images.
+    pub requests: u64,
+    /// Number of requests rejected because the queue was full.
+    pub rejected: u64,
+    /// Number of batches of each size, indexed by size.
+    pub batch_sizes: Vec<u64>,
+    /// Total time the requests waited for their batch to start, in milliseconds.
+    pub total_wait_ms: f64,
+    /// Longest time a request waited for its batch to start, in milliseconds.
+    pub max_wait_ms: f64,
+    /// Number of requests currently waiting.
+    pub queue_depth: usize,
+}
+
+impl BatchMetrics {
+    /// Average number of images per forward pass.
+    pub fn mean_batch_size(&self) -> f64 {
+        self.requests as f64 / self.batches.max(1) as f64
+    }
+
+    /// Average time a request waited for its batch to start, in milliseconds.
+    pub fn mean_wait_ms(&self) -> f64 {
+        self.total_wait_ms / self.requests.max(1) as f64
+    }
+}
+
+/// Collects concurrent detection requests into batches, run with a single forward pass.
+///
+/// A batch starts when it is full, or when its first request waited for
+/// [max_wait_ms](BatcherConfig::max_wait_ms).
+#[derive(Debug)]
+pub struct Batcher {
+    sender: SyncSender<Job>,
+    shared: Arc<Shared>,
+    config: BatcherConfig,
+}
+
+impl Batcher {
+    /// Detects objects in an image, blocking until its batch is processed.
+    ///
+    /// # Returns
+    ///
+    /// Bounding boxes grouped per class, in the coordinates of the image.
+    pub fn detect(&self, image: DynamicImage) -> Result<Vec<Vec<BoundingBox>>, BatchError> {
+        let (respond, response) = mpsc::channel();
+        let job = Job {
+            image,
+            enqueued: Instant::now(),
+            respond,
+        };
+
+        // Count the job before sending it, so that the worker never sees a negative depth
+        self.shared.queue_depth.fetch_add(1, Ordering::SeqCst);
+        if let Err(err) = self.sender.try_send(job) {
+            self.shared.queue_depth.fetch_sub(1, Ordering::SeqCst);
+            return Err(match err {
+                TrySendError::Full(_) => {
+                    self.shared.metrics().rejected += 1;
+                    BatchError::QueueFull
+                }
+                TrySendError::Disconnected(_) => BatchError::Stopped,
+            });
+        }
+
+        response.recv().map_err(|_| BatchError::Stopped)?
+    }
+
+    /// Snapshot of the batch statistics.
+    pub fn metrics(&self) -> BatchMetrics {
+        let mut metrics = self.shared.metrics().clone();
+        metrics.queue_depth = self.shared.queue_depth.load(Ordering::SeqCst);
+        metrics
+    }
+
+    /// The batcher configuration.
+    pub fn config(&self) -> &BatcherConfig {
+        &self.config
+    }
+}
+
+struct Job {
+    image: DynamicImage,
+    enqueued: Instant,
+    respond: mpsc::Sender<Result<Vec<Vec<BoundingBox>>, BatchError>>,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    metrics: Mutex<BatchMetrics>,
+    queue_depth: AtomicUsize,
+}
+
+impl Shared {
+    /// Locks the metrics. They stay consistent even if a thread panicked while holding the lock.
+    fn metrics(&self) -> MutexGuard<'_, BatchMetrics> {
+        self.metrics.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+/// Runs the batches until the [batcher](Batcher) is dropped.
+fn run<B: Backend>(
+    detector: Detector<B>,
+    receiver: Receiver<Job>,
+    config: BatcherConfig,
+    shared: Arc<Shared>,
+) {
+    let max_batch_size = config.max_batch_size;
+    let max_wait = Duration::from_secs_f64(config.max_wait_ms / 1000.);
+
+    while let Ok(first) = receiver.recv() {
+        let deadline = first.enqueued + max_wait;
+        let mut jobs = vec![first];
+        while jobs.len() < max_batch_size {
+            let job = match deadline.checked_duration_since(Instant::now()) {
+                Some(timeout) if !timeout.is_zero() => receiver.recv_timeout(timeout),
+                _ => receiver.try_recv().map_err(|_| RecvTimeoutError::Timeout),
+            };
+            match job {
+                Ok(job) => jobs.push(job),
+                Err(_) => break,
+            }
+        }
+        shared.queue_depth.fetch_sub(jobs.len(), Ordering::SeqCst);
+
+        let start = Instant::now();
+        {
+            let mut metrics = shared.metrics();
+            metrics.batches += 1;
+            metrics.requests += jobs.len() as u64;
+            if metrics.batch_sizes.len() <= jobs.len() {
+                metrics.batch_sizes.resize(jobs.len() + 1, 0);
+            }
+            metrics.batch_sizes[jobs.len()] += 1;
+            for job in jobs.iter() {
+                let wait_ms = start.duration_since(job.enqueued).as_secs_f64() * 1000.;
+                metrics.total_wait_ms += wait_ms;
+                metrics.max_wait_ms = metrics.max_wait_ms.max(wait_ms);
+            }
+        }
+
+        let (images, responders): (Vec<_>, Vec<_>) =
+            jobs.into_iter().map(|job| (job.image, job.respond)).unzip();
+        // A panic only fails the requests of its batch, the worker keeps serving the next ones
+        let boxes = panic::catch_unwind(AssertUnwindSafe(|| detector.detect_batch(&images)));
+        let results: Vec<_> = match boxes {
+            Ok(boxes) => boxes.into_iter().map(Ok).collect(),
+            Err(_) => vec![Err(BatchError::Failed); responders.len()],
+        };
+        for (respond, result) in responders.into_iter().zip(results) {
+            // The requester may have given up, which does not concern the others
+            let _ = respond.send(result);
+        }
+    }
+}
@@ -0,0 +1,374 @@
+//! HTTP inference server exposing the detector and the face recognizer.
+//!
+//! Endpoints:
+//! * `GET /health` - `{"status": "ok"}`.
+//! * `GET /model-info` - Configuration of the detector and of the recognizer.
+//! * `GET /metrics` - [Inference metrics](Metrics) and [batch statistics](BatchMetrics), in the
+//!   Prometheus text format.
+//! * `POST /detect` - Detections of the image in the request body, as
+//!   `{"width", "height", "detections": [{"class", "confidence", "bbox"}]}`.
+//! * `POST /embed` - Embedding of the aligned face crop in the request body, as
+//!   `{"embedding": [...]}`.
+//! * `POST /identify` - Best matching gallery identity of the aligned face crop in the request
+//!   body, as `{"identity", "similarity"}`, which are `null` below the identity threshold.
+//!
+//! Detection requests are processed in batches by a [batcher](Batcher). When too many requests are
+//! waiting, new ones are rejected with a 503 status.
+//!
+//! Errors are reported as `{"error": "..."}` with a 4xx or 5xx status.
+
+mod batcher;
+
+use std::{
+    fmt::Write,
+    io::Read,
+    panic::{self, AssertUnwindSafe},
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+    thread,
+};
+
+use burn::{config::Config, tensor::backend::Backend};
+use image::DynamicImage;
+use inference_metrics::{now_ms, Metrics};
+use serde_json::{json, Value};
+use tiny_http::{Header, Request, Response, Server};
+
+use crate::{
+    detector::{Detector, DetectorConfig},
+    recognition::Recognizer,
+    results::DetectionResult,
+    yolox_model::COCO_CLASSES,
+};
+
+pub use batcher::{BatchError, BatchMetrics, Batcher, BatcherConfig};
+
+/// Maximum size of the request bodies, in bytes.
+pub const MAX_BODY_SIZE: u64 = 32 * 1024 * 1024;
+
+/// Content type of the Prometheus text format.
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Status code, content type and body of a response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl HttpResponse {
+    fn ok(body: Value) -> Self {
+        Self::json(200, body)
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Self::json(status, json!({ "error": message.into() }))
+    }
+
+    fn json(status: u16, body: Value) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: body.to_string(),
+        }
+    }
+}
+
+/// [InferenceServer](InferenceServer) configuration.
+#[derive(Config, Debug)]
+pub struct InferenceServerConfig {
+    /// Number of threads handling the HTTP requests.
+    #[config(default = "4")]
+    pub workers: usize,
+    /// Batching of the detection requests.
+    #[config(default = "BatcherConfig::new()")]
+    pub batcher: BatcherConfig,
+}
+
+impl InferenceServerConfig {
+    /// Initialize a new [server](InferenceServer) for the given detector, with COCO class names,
+    /// and optionally a face recognizer for the `/embed` and `/identify` endpoints.
+    ///
+    /// The models record their metrics in the ones of the detector, if any, or in new `yolo`
+    /// metrics.
+    pub fn init<B: Backend>(
+        &self,
+        detector: Detector<B>,
+        recognizer: Option<Recognizer<B>>,
+    ) -> InferenceServer<B> {
+        let metrics = detector
+            .metrics()
+            .cloned()
+            .unwrap_or_else(|| Arc::new(Metrics::new("yolo")));
+
+        InferenceServer {
+            detector_config: detector.config().clone(),
+            batcher: self.batcher.init(detector.with_metrics(metrics.clone())),
+            recognizer: recognizer
+                .map(|recognizer| Mutex::new(recognizer.with_metrics(metrics.clone()))),
+            class_names: COCO_CLASSES.iter().map(|name| name.to_string()).collect(),
+            metrics,
+            config: self.clone(),
+        }
+    }
+}
+
+/// Serves the models over HTTP.
+#[derive(Debug)]
+pub struct InferenceServer<B: Backend> {
+    detector_config: DetectorConfig,
+    batcher: Batcher,
+    recognizer: Option<Mutex<Recognizer<B>>>,
+    class_names: Vec<String>,
+    metrics: Arc<Metrics>,
+    config: InferenceServerConfig,
+}
+
+impl<B: Backend> InferenceServer<B> {
+    /// Sets the class names reported by `/model-info`.
+    pub fn with_class_names(mut self, class_names: Vec<String>) -> Self {
+        self.class_names = class_names;
+        self
+    }
+
+    /// Handles the requests of the HTTP server on [workers](InferenceServerConfig::workers)
+    /// threads, until it is unblocked.
+    pub fn serve(&self, server: &Server) {
+        thread::scope(|scope| {
+            for _ in 0..self.config.workers.max(1) {
+                scope.spawn(|| {
+                    for request in server.incoming_requests() {
+                        self.respond(request);
+                    }
+                });
+            }
+        });
+    }
+
+    /// Latency, frame rate and detection metrics of the models. Each detected image counts as a
+    /// frame.
+    pub fn metrics(&self) -> &Arc<Metrics> {
+        &self.metrics
+    }
+
+    /// Snapshot of the batch statistics of the detection requests.
+    pub fn batch_metrics(&self) -> BatchMetrics {
+        self.batcher.metrics()
+    }
+
+    /// Handles a request, given its method, URL and body.
+    pub fn handle(&self, method: &str, url: &str, body: &[u8]) -> HttpResponse {
+        let path = url.split('?').next().unwrap_or_default();
+        let expected_method = match path {
+            "/health" | "/model-info" | "/metrics" => "GET",
+            "/detect" | "/embed" | "/identify" => "POST",
+            _ => return HttpResponse::error(404, format!("Unknown endpoint {path}")),
+        };
+        if method != expected_method {
+            return HttpResponse::error(405, format!("{path} expects a {expected_method} request"));
+        }
+
+        match path {
+            "/health" => HttpResponse::ok(json!({ "status": "ok" })),
+            "/model-info" => HttpResponse::ok(self.model_info()),
+            "/metrics" => HttpResponse {
+                status: 200,
+                content_type: PROMETHEUS_CONTENT_TYPE,
+                body: self.prometheus_metrics(),
+            },
+            "/detect" => self.with_image(body, |image| self.detect(image)),
+            "/embed" => self.with_image(body, |image| self.embed(image)),
+            _ => self.with_image(body, |image| self.identify(image)),
+        }
+    }
+
+    fn respond(&self, mut request: Request) {
+        let response = self.handle_request(&mut request);
+        let content_type = Header::from_bytes("Content-Type", response.content_type).unwrap();
+        let response = Response::from_string(response.body)
+            .with_status_code(response.status)
+            .with_header(content_type);
+
+        // The client may have disconnected, which does not concern the other requests
+        let _ = request.respond(response);
+    }
+
+    fn handle_request(&self, request: &mut Request) -> HttpResponse {
+        let mut body = Vec::new();
+        if let Err(err) = request
+            .as_reader()
+            .take(MAX_BODY_SIZE + 1)
+            .read_to_end(&mut body)
+        {
+            return HttpResponse::error(400, format!("Failed to read the request body: {err}"));
+        }
+        if body.len() as u64 > MAX_BODY_SIZE {
+            return HttpResponse::error(413, "Request body too large");
+        }
+
+        self.handle(request.method().as_str(), request.url(), &body)
+    }
+
+    fn with_image(
+        &self,
+        body: &[u8],
+        f: impl FnOnce(DynamicImage) -> HttpResponse,
+    ) -> HttpResponse {
+        match image::load_from_memory(body) {
+            Ok(image) => f(image),
+            Err(err) => HttpResponse::error(400, format!("Failed to decode the image: {err}")),
+        }
+    }
+
+    fn model_info(&self) -> Value {
+        let detector = &self.detector_config;
+        let recognizer = self.recognizer.as_ref().map(|recognizer| {
+            let recognizer = lock(recognizer);
+            json!({
+                "input_size": recognizer.input_size(),
+                "identity_threshold": recognizer.threshold(),
+                "identities": recognizer.identities(),
+            })
+        });
+
+        json!({
+            "detector": {
+                "input_height": detector.input_height,
+                "input_width": detector.input_width,
+                "iou_threshold": detector.iou_threshold,
+                "score_threshold": detector.score_threshold,
+                "class_names": self.class_names,
+            },
+            "recognizer": recognizer,
+        })
+    }
+
+    /// Model metrics followed by the batch statistics, in the Prometheus text format.
+    fn prometheus_metrics(&self) -> String {
+        let mut text = self.metrics.to_prometheus(now_ms());
+        let metrics = self.batch_metrics();
+        let batcher = self.batcher.config();
+
+        let counters = [
+            (
+                "batches_total",
+                "Detection forward passes.",
+                metrics.batches,
+            ),
+            (
+                "batched_requests_total",
+                "Batched detection requests.",
+                metrics.requests,
+            ),
+            (
+                "rejected_requests_total",
+                "Detection requests rejected by a full queue.",
+                metrics.rejected,
+            ),
+        ];
+        for (name, help, value) in counters {
+            writeln!(text, "# HELP yolo_{name} {help}").unwrap();
+            writeln!(text, "# TYPE yolo_{name} counter").unwrap();
+            writeln!(text, "yolo_{name} {value}").unwrap();
+        }
+        let gauges = [
+            (
+                "mean_batch_size",
+                "Average number of images per forward pass.",
+                metrics.mean_batch_size(),
+            ),
+            (
+                "mean_batch_wait_milliseconds",
+                "Average time requests waited for their batch.",
+                metrics.mean_wait_ms(),
+            ),
+            (
+                "max_batch_wait_milliseconds",
+                "Longest time a request waited for its batch.",
+                metrics.max_wait_ms,
+            ),
+            (
+                "queue_depth",
+                "Detection requests waiting for a batch.",
+                metrics.queue_depth as f64,
+            ),
+            (
+                "max_batch_size",
+                "Maximum number of images per forward pass.",
+                batcher.max_batch_size as f64,
+            ),
+            (
+                "max_queue_size",
+                "Maximum number of waiting detection requests.",
+                batcher.max_queue_size as f64,
+            ),
+        ];
+        for (name, help, value) in gauges {
+            writeln!(text, "# HELP yolo_{name} {help}").unwrap();
+            writeln!(text, "# TYPE yolo_{name} gauge").unwrap();
+            writeln!(text, "yolo_{name} {value}").unwrap();
+        }
+
+        text
+    }
+
+    fn detect(&self, image: DynamicImage) -> HttpResponse {
+        let (width, height) = (image.width(), image.height());
+        let boxes = match self.batcher.detect(image) {
+            Ok(boxes) => boxes,
+            Err(err @ BatchError::QueueFull) => return HttpResponse::error(503, err.to_string()),
+            Err(err @ (BatchError::Failed | BatchError::Stopped)) => {
+                return HttpResponse::error(500, err.to_string())
+            }
+        };
+        self.metrics.record_frame(now_ms());
+        let detections: Vec<_> = boxes
+            .iter()
+            .enumerate()
+            .flat_map(|(class, boxes)| {
+                boxes
+                    .iter()
+                    .map(move |bbox| DetectionResult::new(class, bbox, None))
+            })
+            .collect();
+
+        HttpResponse::ok(json!({
+            "width": width,
+            "height": height,
+            "detections": detections,
+        }))
+    }
+
+    fn embed(&self, image: DynamicImage) -> HttpResponse {
+        self.with_recognizer(|recognizer| {
+            let embedding = recognizer.embed(&[image]).remove(0);
+
+            HttpResponse::ok(json!({ "embedding": embedding }))
+        })
+    }
+
+    fn identify(&self, image: DynamicImage) -> HttpResponse {
+        self.with_recognizer(|recognizer| {
+            let embedding = recognizer.embed(&[image]).remove(0);
+            let (identity, similarity) = recognizer.identify(&embedding).unzip();
+
+            HttpResponse::ok(json!({ "identity": identity, "similarity": similarity }))
+        })
+    }
+
+    fn with_recognizer(&self, f: impl FnOnce(&Recognizer<B>) -> HttpResponse) -> HttpResponse {
+        let Some(recognizer) = &self.recognizer else {
+            return HttpResponse::error(503, "No face recognition model loaded");
+        };
+        let recognizer = lock(recognizer);
+        // A panic only fails its request, the worker keeps serving the next ones
+        panic::catch_unwind(AssertUnwindSafe(|| f(&recognizer)))
+            .unwrap_or_else(|_| HttpResponse::error(500, "Face recognition failed"))
+    }
+}
+
+/// Locks the recognizer. It holds no state updated by the requests, so it stays usable even if a
+/// thread panicked while holding the lock.
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(PoisonError::into_inner)
+}
@@ -0,0 +1,29 @@
+use alloc::vec::Vec;
+
+/// Scales an embedding to unit L2 norm.
+pub(crate) fn normalize(embedding: &[f32]) -> Vec<f32> {
+    let norm = embedding
+        .iter()
+        .map(|v| v * v)
+        .sum::<f32>()
+        .sqrt()
+        .max(1e-12);
+    embedding.iter().map(|v| v / norm).collect()
+}
+
+/// Cosine distance (`1 - cosine similarity`) between two unit norm embeddings, in `[0, 2]`.
+pub(crate) fn cosine_distance(a: &[f32], b: &[f32]) -> f32 {
+    1. - a.iter().zip(b).map(|(a, b)| a * b).sum::<f32>()
+}
+
+/// Updates a running (unit norm) embedding with a new observation using an exponential moving
+/// average, as in [DeepSORT](https://arxiv.org/abs/1703.07402) and its variants.
+pub(crate) fn smooth(running: &mut Vec<f32>, observation: &[f32], momentum: f32) {
+    let observation = normalize(observation);
+    let mixed: Vec<f32> = running
+        .iter()
+        .zip(&observation)
+        .map(|(r, o)| momentum * r + (1. - momentum) * o)
+        .collect();
+    *running = normalize(&mixed);
+}
@@ -0,0 +1,128 @@
+use alloc::{vec, vec::Vec};
+
+/// Solves the linear assignment problem with the
+/// [Hungarian algorithm](https://en.wikipedia.org/wiki/Hungarian_algorithm).
+///
+/// Finds the set of `(row, column)` pairs minimizing the total cost, where each row and each
+/// column is assigned at most once. For rectangular matrices, `min(rows, columns)` pairs are
+/// returned.
+///
+/// Non-finite costs, e.g. of degenerate boxes, are higher than any finite cost, so that their
+/// pairs are only assigned when the others cannot be.
+///
+/// # Arguments
+///
+/// * `cost` - Cost matrix, one inner vector per row. All rows must have the same length.
+///
+/// # Returns
+///
+/// The assigned `(row, column)` pairs, sorted by row.
+pub fn linear_assignment(cost: &[Vec<f32>]) -> Vec<(usize, usize)> {
+    let rows = cost.len();
+    let cols = cost.first().map_or(0, |row| row.len());
+    if rows == 0 || cols == 0 {
+        return Vec::new();
+    }
+
+    // The algorithm below assigns every row, so it requires rows <= columns.
+    if rows > cols {
+        let transposed: Vec<Vec<f32>> = (0..cols)
+            .map(|c| (0..rows).map(|r| cost[r][c]).collect())
+            .collect();
+        let mut pairs: Vec<_> = linear_assignment(&transposed)
+            .into_iter()
+            .map(|(c, r)| (r, c))
+            .collect();
+        pairs.sort_unstable();
+        return pairs;
+    }
+
+    // Non-finite costs would leave no column to augment to. They are replaced by a cost higher
+    // than the difference between any two assignments of finite costs.
+    let max_cost = cost
+        .iter()
+        .flatten()
+        .filter(|cost| cost.is_finite())
+        .fold(0f64, |max, cost| max.max((*cost as f64).abs()));
+    let non_finite = 2. * max_cost * rows as f64 + 1.;
+    let cost: Vec<Vec<f64>> = cost
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|&cost| {
+                    if cost.is_finite() {
+                        cost as f64
+                    } else {
+                        non_finite
+                    }
+                })
+                .collect()
+        })
+        .collect();
+
+    // Potentials method with 1-based indices, where row/column 0 is a virtual element.
+    let (n, m) = (rows, cols);
+    let mut u = vec![0f64; n + 1];
+    let mut v = vec![0f64; m + 1];
+    // Row assigned to each column
+    let mut assigned = vec![0usize; m + 1];
+    let mut way = vec![0usize; m + 1];
+
+    for row in 1..=n {
+        assigned[0] = row;
+        let mut col0 = 0;
+        let mut min_v = vec![f64::INFINITY; m + 1];
+        let mut used = vec![false; m + 1];
+
+        // Find an augmenting path from the current row to a free column
+        loop {
+            used[col0] = true;
+            let row0 = assigned[col0];
+            let mut delta = f64::INFINITY;
+            let mut col1 = 0;
+            for col in 1..=m {
+                if used[col] {
+                    continue;
+                }
+                let reduced = cost[row0 - 1][col - 1] - u[row0] - v[col];
+                if reduced < min_v[col] {
+                    min_v[col] = reduced;
+                    way[col] = col0;
+                }
+                if min_v[col] < delta {
+                    delta = min_v[col];
+                    col1 = col;
+                }
+            }
+            for col in 0..=m {
+                if used[col] {
+                    u[assigned[col]] += delta;
+                    v[col] -= delta;
+                } else {
+                    min_v[col] -= delta;
+                }
+            }
+            col0 = col1;
+            if assigned[col0] == 0 {
+                break;
+            }
+        }
+
+        // Flip the assignments along the augmenting path
+        loop {
+            let col1 = way[col0];
+            assigned[col0] = assigned[col1];
+            col0 = col1;
+            if col0 == 0 {
+                break;
+            }
+        }
+    }
+
+    let mut pairs: Vec<_> = (1..=m)
+        .filter(|col| assigned[*col] != 0)
+        .map(|col| (assigned[col] - 1, col - 1))
+        .collect();
+    pairs.sort_unstable();
+    pairs
+}
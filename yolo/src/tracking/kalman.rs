@@ -0,0 +1,136 @@
+use crate::yolox_model::BoundingBox;
+
+/// Process noise of the box position, relative to the box size.
+const STD_WEIGHT_POSITION: f32 = 1. / 20.;
+/// Process noise of the box velocity, relative to the box size.
+const STD_WEIGHT_VELOCITY: f32 = 1. / 160.;
+
+/// Constant velocity Kalman filter of a single box coordinate.
+///
+/// The state is the coordinate value and its velocity (per frame), with a 2x2 covariance.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Axis {
+    value: f32,
+    velocity: f32,
+    // Covariance [[p00, p01], [p01, p11]]
+    p00: f32,
+    p01: f32,
+    p11: f32,
+}
+
+impl Axis {
+    fn new(value: f32, std_position: f32, std_velocity: f32) -> Self {
+        Self {
+            value,
+            velocity: 0.,
+            p00: std_position.powi(2),
+            p01: 0.,
+            p11: std_velocity.powi(2),
+        }
+    }
+
+    /// Propagates the state one frame forward: `x = F x`, `P = F P F^T + Q` with
+    /// `F = [[1, 1], [0, 1]]`.
+    fn predict(&mut self, std_position: f32, std_velocity: f32) {
+        self.value += self.velocity;
+        self.p00 += 2. * self.p01 + self.p11 + std_position.powi(2);
+        self.p01 += self.p11;
+        self.p11 += std_velocity.powi(2);
+    }
+
+    /// Corrects the state with a measurement of the coordinate value.
+    fn update(&mut self, measurement: f32, std_measurement: f32) {
+        let innovation_cov = self.p00 + std_measurement.powi(2);
+        let gain = [self.p00 / innovation_cov, self.p01 / innovation_cov];
+        let residual = measurement - self.value;
+
+        self.value += gain[0] * residual;
+        self.velocity += gain[1] * residual;
+        self.p11 -= gain[1] * self.p01;
+        self.p01 *= 1. - gain[0];
+        self.p00 *= 1. - gain[0];
+    }
+}
+
+/// Kalman filter tracking a bounding box in `(center x, center y, width, height)` space with a
+/// constant velocity model, similar to [SORT](https://arxiv.org/abs/1602.00763) and
+/// [ByteTrack](https://arxiv.org/abs/2110.06864).
+///
+/// Since the motion and observation models are diagonal over the four box coordinates, the
+/// filter is split into four independent filters of a coordinate and its velocity.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KalmanBoxFilter {
+    axes: [Axis; 4],
+}
+
+impl KalmanBoxFilter {
+    /// Initializes the filter from a first observation of the box, with zero velocity.
+    pub fn new(bbox: &BoundingBox) -> Self {
+        let measurement = to_xywh(bbox);
+        let scales = noise_scales(measurement[2], measurement[3]);
+
+        let axes = core::array::from_fn(|i| {
+            Axis::new(
+                measurement[i],
+                2. * STD_WEIGHT_POSITION * scales[i],
+                10. * STD_WEIGHT_VELOCITY * scales[i],
+            )
+        });
+
+        Self { axes }
+    }
+
+    /// Advances the state by one frame.
+    pub fn predict(&mut self) {
+        let scales = self.scales();
+        for (axis, scale) in self.axes.iter_mut().zip(scales) {
+            axis.predict(STD_WEIGHT_POSITION * scale, STD_WEIGHT_VELOCITY * scale);
+        }
+    }
+
+    /// Corrects the state with an observed box.
+    pub fn update(&mut self, bbox: &BoundingBox) {
+        let measurement = to_xywh(bbox);
+        let scales = self.scales();
+        for ((axis, value), scale) in self.axes.iter_mut().zip(measurement).zip(scales) {
+            axis.update(value, STD_WEIGHT_POSITION * scale);
+        }
+    }
+
+    /// Current box estimate, with the given `confidence`.
+    pub fn bbox(&self, confidence: f32) -> BoundingBox {
+        let [cx, cy, w, h] = self.axes.map(|axis| axis.value);
+        let (w, h) = (w.max(0.), h.max(0.));
+
+        BoundingBox {
+            xmin: cx - w / 2.,
+            ymin: cy - h / 2.,
+            xmax: cx + w / 2.,
+            ymax: cy + h / 2.,
+            confidence,
+        }
+    }
+
+    /// Current velocity estimate of the box center, in pixels per frame.
+    pub fn velocity(&self) -> [f32; 2] {
+        [self.axes[0].velocity, self.axes[1].velocity]
+    }
+
+    fn scales(&self) -> [f32; 4] {
+        noise_scales(self.axes[2].value, self.axes[3].value)
+    }
+}
+
+/// Noise scales of each coordinate, relative to the box size. Clamped to one pixel so that
+/// degenerate boxes keep a non-zero uncertainty.
+fn noise_scales(w: f32, h: f32) -> [f32; 4] {
+    let (w, h) = (w.abs().max(1.), h.abs().max(1.));
+    [w, h, w, h]
+}
+
+/// Converts a box from corners to `(center x, center y, width, height)`.
+fn to_xywh(bbox: &BoundingBox) -> [f32; 4] {
+    let w = bbox.xmax - bbox.xmin;
+    let h = bbox.ymax - bbox.ymin;
+    [bbox.xmin + w / 2., bbox.ymin + h / 2., w, h]
+}
@@ -0,0 +1,8 @@
+mod appearance;
+mod assignment;
+mod kalman;
+mod tracker;
+
+pub use assignment::linear_assignment;
+pub use kalman::KalmanBoxFilter;
+pub use tracker::{Track, TrackIds, TrackState, Tracker, TrackerConfig};
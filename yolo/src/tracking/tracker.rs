@@ -0,0 +1,461 @@
+use alloc::{sync::Arc, vec, vec::Vec};
+use burn::config::Config;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use super::{
+    appearance::{cosine_distance, normalize, smooth},
+    assignment::linear_assignment,
+    kalman::KalmanBoxFilter,
+};
+use crate::yolox_model::{boxes::iou, BoundingBox};
+
+/// Lifecycle state of a [track](Track).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackState {
+    /// Newly created track, not yet matched in enough consecutive frames to be reported.
+    Tentative,
+    /// Track matched in at least `min_hits` consecutive frames.
+    Confirmed,
+    /// Confirmed track that was not matched in the latest frame(s). It is kept (and its motion
+    /// extrapolated) for up to `max_age` frames, so it can be recovered with the same ID.
+    Lost,
+}
+
+/// A tracked object.
+#[derive(Debug, Clone)]
+pub struct Track {
+    id: u64,
+    state: TrackState,
+    filter: KalmanBoxFilter,
+    confidence: f32,
+    embedding: Option<Vec<f32>>,
+    hits: usize,
+    age: usize,
+    time_since_update: usize,
+}
+
+impl Track {
+    fn new(id: u64, detection: &BoundingBox, embedding: Option<&[f32]>, min_hits: usize) -> Self {
+        let state = if min_hits <= 1 {
+            TrackState::Confirmed
+        } else {
+            TrackState::Tentative
+        };
+
+        Self {
+            id,
+            state,
+            filter: KalmanBoxFilter::new(detection),
+            confidence: detection.confidence,
+            embedding: embedding.map(normalize),
+            hits: 1,
+            age: 1,
+            time_since_update: 0,
+        }
+    }
+
+    /// Unique (per tracker) identifier of the track.
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+
+    /// Lifecycle state of the track.
+    pub fn state(&self) -> TrackState {
+        self.state
+    }
+
+    /// Current (filtered) box of the track, with the confidence of its last matched detection.
+    pub fn bbox(&self) -> BoundingBox {
+        self.filter.bbox(self.confidence)
+    }
+
+    /// Estimated velocity of the box center, in pixels per frame.
+    pub fn velocity(&self) -> [f32; 2] {
+        self.filter.velocity()
+    }
+
+    /// Running (unit norm) appearance embedding of the track, if it was matched with detections
+    /// that had one.
+    pub fn embedding(&self) -> Option<&[f32]> {
+        self.embedding.as_deref()
+    }
+
+    /// Number of frames the track was matched with a detection.
+    pub fn hits(&self) -> usize {
+        self.hits
+    }
+
+    /// Number of frames since the track was created.
+    pub fn age(&self) -> usize {
+        self.age
+    }
+
+    /// Number of frames since the track was last matched with a detection.
+    pub fn time_since_update(&self) -> usize {
+        self.time_since_update
+    }
+
+    fn predict(&mut self) {
+        self.filter.predict();
+        self.age += 1;
+        self.time_since_update += 1;
+    }
+
+    fn update(
+        &mut self,
+        detection: &BoundingBox,
+        embedding: Option<&[f32]>,
+        config: &TrackerConfig,
+    ) {
+        self.filter.update(detection);
+        self.confidence = detection.confidence;
+        self.update_embedding(embedding, config.embedding_momentum);
+        self.time_since_update = 0;
+        self.hits += 1;
+
+        match self.state {
+            TrackState::Tentative if self.hits >= config.min_hits => {
+                self.state = TrackState::Confirmed
+            }
+            TrackState::Lost => self.state = TrackState::Confirmed,
+            _ => {}
+        }
+    }
+
+    /// Re-identifies the track by appearance at a new location. The motion state is restarted
+    /// from the detection since the extrapolated one is not consistent with it.
+    fn revive(&mut self, detection: &BoundingBox, embedding: &[f32], config: &TrackerConfig) {
+        self.filter = KalmanBoxFilter::new(detection);
+        self.confidence = detection.confidence;
+        self.update_embedding(Some(embedding), config.embedding_momentum);
+        self.time_since_update = 0;
+        self.hits += 1;
+        self.state = TrackState::Confirmed;
+    }
+
+    fn update_embedding(&mut self, embedding: Option<&[f32]>, momentum: f32) {
+        match (&mut self.embedding, embedding) {
+            (Some(running), Some(embedding)) => smooth(running, embedding, momentum),
+            (None, Some(embedding)) => self.embedding = Some(normalize(embedding)),
+            _ => {}
+        }
+    }
+}
+
+/// [Tracker](Tracker) configuration.
+#[derive(Config, Debug)]
+pub struct TrackerConfig {
+    /// Minimum IoU between a predicted track box and a detection for them to be matched.
+    #[config(default = "0.3")]
+    pub iou_threshold: f32,
+    /// Detections with a confidence greater or equal than this threshold are matched first and
+    /// can start new tracks.
+    #[config(default = "0.5")]
+    pub high_confidence: f32,
+    /// Detections with a confidence between this threshold and `high_confidence` are only used
+    /// to keep existing tracks alive (ByteTrack second association). Lower ones are ignored.
+    #[config(default = "0.1")]
+    pub low_confidence: f32,
+    /// Number of matched frames before a tentative track is confirmed.
+    #[config(default = "3")]
+    pub min_hits: usize,
+    /// Number of frames a lost track is kept before being removed.
+    #[config(default = "30")]
+    pub max_age: usize,
+    /// Weight of the appearance (cosine) distance in the association cost, the IoU distance
+    /// having weight `1 - appearance_weight`. Only used when both the track and the detection
+    /// have an embedding.
+    #[config(default = "0.5")]
+    pub appearance_weight: f32,
+    /// Maximum cosine distance between a track and a detection embedding for them to be matched.
+    #[config(default = "0.4")]
+    pub max_appearance_distance: f32,
+    /// Maximum cosine distance for a lost track to be re-identified with a high confidence
+    /// detection that does not overlap its predicted box.
+    #[config(default = "0.3")]
+    pub reid_distance: f32,
+    /// Momentum of the exponential moving average of the track embeddings.
+    #[config(default = "0.9")]
+    pub embedding_momentum: f32,
+}
+
+impl TrackerConfig {
+    /// Initialize a new [tracker](Tracker).
+    pub fn init(&self) -> Tracker {
+        self.init_with_ids(&TrackIds::default())
+    }
+
+    /// Initialize a new [tracker](Tracker) drawing the IDs of its tracks from `ids`, so that
+    /// trackers sharing them never report the same ID (e.g. one tracker per class).
+    pub fn init_with_ids(&self, ids: &TrackIds) -> Tracker {
+        Tracker {
+            config: self.clone(),
+            tracks: Vec::new(),
+            ids: ids.clone(),
+        }
+    }
+}
+
+/// Sequence of track IDs, starting at 1, shared by the [trackers](Tracker) it is cloned into.
+#[derive(Debug, Clone, Default)]
+pub struct TrackIds(Arc<AtomicU64>);
+
+impl TrackIds {
+    fn next(&self) -> u64 {
+        self.0.fetch_add(1, Ordering::Relaxed) + 1
+    }
+}
+
+/// Association cost of the pairs that must not be matched.
+const INFEASIBLE: f32 = 1e6;
+
+/// Multi-object tracker assigning stable IDs to per-frame detections.
+///
+/// Track boxes are propagated between frames with a [Kalman filter](KalmanBoxFilter) and matched
+/// to the new detections by maximizing their IoU with the
+/// [Hungarian algorithm](linear_assignment). As in [ByteTrack](https://arxiv.org/abs/2110.06864),
+/// low confidence detections are matched in a second pass against the remaining tracks.
+///
+/// When detections come with appearance embeddings (e.g. MobileFaceNet embeddings of the face
+/// crops), see [Tracker::update_with_embeddings], each track keeps a running embedding. The
+/// association cost then combines the IoU and cosine distances, and lost tracks can be
+/// re-identified by appearance alone when the object reappears away from its predicted box.
+///
+/// The tracker is class-agnostic: use one tracker per class to track several classes, sharing
+/// their [track IDs](TrackerConfig::init_with_ids).
+#[derive(Debug, Clone)]
+pub struct Tracker {
+    config: TrackerConfig,
+    tracks: Vec<Track>,
+    ids: TrackIds,
+}
+
+impl Tracker {
+    /// Updates the tracks with the detections of a new frame.
+    ///
+    /// # Returns
+    ///
+    /// All the active tracks (tentative, confirmed and lost). Use [Tracker::confirmed] to only get
+    /// the tracks matched in this frame that should be reported.
+    pub fn update(&mut self, detections: &[BoundingBox]) -> &[Track] {
+        self.step(detections, None)
+    }
+
+    /// Updates the tracks with the detections of a new frame and their appearance embeddings.
+    ///
+    /// # Arguments
+    ///
+    /// * `detections` - Detected boxes.
+    /// * `embeddings` - Appearance embedding of each detection (not necessarily normalized). All
+    ///   embeddings must have the same size.
+    ///
+    /// # Returns
+    ///
+    /// All the active tracks (tentative, confirmed and lost).
+    pub fn update_with_embeddings(
+        &mut self,
+        detections: &[BoundingBox],
+        embeddings: &[Vec<f32>],
+    ) -> &[Track] {
+        assert_eq!(
+            detections.len(),
+            embeddings.len(),
+            "Expected one embedding per detection"
+        );
+        self.step(detections, Some(embeddings))
+    }
+
+    /// Advances the tracks by one frame for which no detection was run, e.g. a frame skipped by
+    /// a [scheduler](crate::scheduler::Scheduler).
+    ///
+    /// Unlike an update without detections, the tracks are not considered missed: their state
+    /// and [time since update](Track::time_since_update) are unchanged, only their boxes are
+    /// extrapolated.
+    pub fn predict(&mut self) -> &[Track] {
+        for track in self.tracks.iter_mut() {
+            track.filter.predict();
+            track.age += 1;
+        }
+
+        &self.tracks
+    }
+
+    /// All the active tracks (tentative, confirmed and lost).
+    pub fn tracks(&self) -> &[Track] {
+        &self.tracks
+    }
+
+    /// Confirmed tracks matched with a detection in the latest frame.
+    pub fn confirmed(&self) -> impl Iterator<Item = &Track> {
+        self.tracks
+            .iter()
+            .filter(|track| track.state == TrackState::Confirmed && track.time_since_update == 0)
+    }
+
+    /// Removes all the tracks. Track IDs keep increasing.
+    pub fn reset(&mut self) {
+        self.tracks.clear();
+    }
+
+    fn step(&mut self, detections: &[BoundingBox], embeddings: Option<&[Vec<f32>]>) -> &[Track] {
+        let embedding = |det: usize| embeddings.map(|e| e[det].as_slice());
+
+        for track in self.tracks.iter_mut() {
+            track.predict();
+        }
+
+        let (high, low): (Vec<usize>, Vec<usize>) = (0..detections.len())
+            .filter(|i| detections[*i].confidence >= self.config.low_confidence)
+            .partition(|i| detections[*i].confidence >= self.config.high_confidence);
+
+        // First association: all tracks against high confidence detections
+        let all_tracks: Vec<usize> = (0..self.tracks.len()).collect();
+        let (matches, unmatched_tracks, unmatched_high) =
+            self.associate(&all_tracks, &high, |t, d| {
+                self.motion_cost(t, &detections[d], embedding(d))
+            });
+        for (track, det) in matches {
+            self.tracks[track].update(&detections[det], embedding(det), &self.config);
+        }
+
+        // Second association: remaining confirmed tracks against low confidence detections
+        let (remaining, unconfirmed): (Vec<usize>, Vec<usize>) = unmatched_tracks
+            .into_iter()
+            .partition(|t| self.tracks[*t].state == TrackState::Confirmed);
+        let (matches, unmatched_confirmed, _) = self.associate(&remaining, &low, |t, d| {
+            self.motion_cost(t, &detections[d], embedding(d))
+        });
+        for (track, det) in matches {
+            self.tracks[track].update(&detections[det], embedding(det), &self.config);
+        }
+
+        // Third association: re-identify the remaining tracks by appearance only
+        let unmatched_high = match embeddings {
+            Some(embeddings) => {
+                let candidates: Vec<usize> = unmatched_confirmed
+                    .into_iter()
+                    .chain(unconfirmed)
+                    .filter(|t| self.tracks[*t].state != TrackState::Tentative)
+                    .collect();
+                let (matches, _, unmatched_high) =
+                    self.associate(&candidates, &unmatched_high, |t, d| {
+                        self.appearance_cost(t, &embeddings[d])
+                    });
+                for (track, det) in matches {
+                    self.tracks[track].revive(&detections[det], &embeddings[det], &self.config);
+                }
+                unmatched_high
+            }
+            None => unmatched_high,
+        };
+
+        // Update the lifecycle of unmatched tracks
+        let max_age = self.config.max_age;
+        self.tracks.retain_mut(|track| {
+            if track.time_since_update == 0 {
+                return true;
+            }
+            match track.state {
+                TrackState::Tentative => false,
+                TrackState::Confirmed => {
+                    track.state = TrackState::Lost;
+                    track.time_since_update <= max_age
+                }
+                TrackState::Lost => track.time_since_update <= max_age,
+            }
+        });
+
+        // Unmatched high confidence detections start new tracks
+        for det in unmatched_high {
+            let track = Track::new(
+                self.ids.next(),
+                &detections[det],
+                embedding(det),
+                self.config.min_hits,
+            );
+            self.tracks.push(track);
+        }
+
+        &self.tracks
+    }
+
+    /// Association cost between the predicted box of a track and a detection, combining the IoU
+    /// and appearance distances. `None` if they must not be matched.
+    fn motion_cost(
+        &self,
+        track: &Track,
+        detection: &BoundingBox,
+        embedding: Option<&[f32]>,
+    ) -> Option<f32> {
+        let iou = iou(&track.bbox(), detection);
+        if iou < self.config.iou_threshold {
+            return None;
+        }
+
+        match (track.embedding(), embedding) {
+            (Some(running), Some(embedding)) => {
+                let distance = cosine_distance(running, &normalize(embedding));
+                if distance > self.config.max_appearance_distance {
+                    return None;
+                }
+                let weight = self.config.appearance_weight;
+                Some((1. - weight) * (1. - iou) + weight * distance)
+            }
+            _ => Some(1. - iou),
+        }
+    }
+
+    /// Appearance only association cost, used to re-identify tracks. `None` if they must not be
+    /// matched.
+    fn appearance_cost(&self, track: &Track, embedding: &[f32]) -> Option<f32> {
+        let distance = cosine_distance(track.embedding()?, &normalize(embedding));
+        (distance <= self.config.reid_distance).then_some(distance)
+    }
+
+    /// Matches the given tracks and detections (indices) with the lowest total cost.
+    ///
+    /// # Returns
+    ///
+    /// The matched `(track, detection)` pairs, the unmatched tracks and the unmatched detections.
+    fn associate(
+        &self,
+        tracks: &[usize],
+        detections: &[usize],
+        cost: impl Fn(&Track, usize) -> Option<f32>,
+    ) -> (Vec<(usize, usize)>, Vec<usize>, Vec<usize>) {
+        let cost: Vec<Vec<f32>> = tracks
+            .iter()
+            .map(|t| {
+                detections
+                    .iter()
+                    .map(|d| cost(&self.tracks[*t], *d).unwrap_or(INFEASIBLE))
+                    .collect()
+            })
+            .collect();
+
+        let matches: Vec<(usize, usize)> = linear_assignment(&cost)
+            .into_iter()
+            .filter(|(t, d)| cost[*t][*d] < INFEASIBLE)
+            .map(|(t, d)| (tracks[t], detections[d]))
+            .collect();
+
+        let mut matched_tracks = vec![false; self.tracks.len()];
+        let mut matched_detections = vec![false; detections.iter().max().map_or(0, |d| d + 1)];
+        for (t, d) in matches.iter() {
+            matched_tracks[*t] = true;
+            matched_detections[*d] = true;
+        }
+        let unmatched_tracks = tracks
+            .iter()
+            .filter(|t| !matched_tracks[**t])
+            .copied()
+            .collect();
+        let unmatched_detections = detections
+            .iter()
+            .filter(|d| !matched_detections[**d])
+            .copied()
+            .collect();
+
+        (matches, unmatched_tracks, unmatched_detections)
+    }
+}
@@ -0,0 +1,184 @@
+use std::io::{self, BufRead};
+
+use image::ImageFormat;
+
+use super::{stream_frame_name, Frame};
+
+/// Start of image marker.
+const SOI: u8 = 0xD8;
+/// End of image marker.
+const EOI: u8 = 0xD9;
+/// Start of scan marker, followed by entropy coded data.
+const SOS: u8 = 0xDA;
+
+/// Reads the frames of an MJPEG stream, i.e. JPEG images written back to back, as written by
+/// `ffmpeg -f mjpeg`.
+///
+/// Images are split by walking the JPEG segments rather than by searching for the end of image
+/// marker, which may also appear inside embedded thumbnails.
+pub struct MjpegReader<R> {
+    reader: R,
+    index: usize,
+    buffer: Vec<u8>,
+}
+
+impl<R: BufRead> MjpegReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            index: 0,
+            buffer: Vec::new(),
+        }
+    }
+
+    fn read_frame(&mut self) -> io::Result<Option<Frame>> {
+        self.buffer.clear();
+        if !self.read_image()? {
+            return Ok(None);
+        }
+
+        let image = image::load_from_memory_with_format(&self.buffer, ImageFormat::Jpeg)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let frame = Frame {
+            index: self.index,
+            name: stream_frame_name(self.index),
+            image,
+        };
+        self.index += 1;
+
+        Ok(Some(frame))
+    }
+
+    /// Reads the next JPEG image into the buffer. Returns `false` at the end of the stream.
+    fn read_image(&mut self) -> io::Result<bool> {
+        // Skip padding between images
+        loop {
+            let available = self.reader.fill_buf()?;
+            if available.is_empty() {
+                return Ok(false);
+            }
+            match available.iter().position(|b| *b == 0xFF) {
+                Some(start) => {
+                    self.reader.consume(start);
+                    break;
+                }
+                None => {
+                    let len = available.len();
+                    self.reader.consume(len);
+                }
+            }
+        }
+        if self.read_marker()? != SOI {
+            return Err(invalid_data("Missing JPEG start of image"));
+        }
+
+        let mut marker = self.read_marker()?;
+        loop {
+            match marker {
+                EOI => return Ok(true),
+                // Standalone markers without payload
+                0x01 | 0xD0..=0xD7 => marker = self.read_marker()?,
+                _ => {
+                    let mut length_bytes = [0; 2];
+                    self.reader.read_exact(&mut length_bytes)?;
+                    let length = u16::from_be_bytes(length_bytes) as usize;
+                    if length < 2 {
+                        return Err(invalid_data("Invalid JPEG segment length"));
+                    }
+                    self.buffer.extend_from_slice(&length_bytes);
+                    let start = self.buffer.len();
+                    self.buffer.resize(start + length - 2, 0);
+                    self.reader.read_exact(&mut self.buffer[start..])?;
+
+                    marker = if marker == SOS {
+                        self.copy_entropy_coded_data()?
+                    } else {
+                        self.read_marker()?
+                    };
+                }
+            }
+        }
+    }
+
+    /// Copies the entropy coded data following a scan header into the buffer.
+    ///
+    /// # Returns
+    ///
+    /// The marker ending the data.
+    fn copy_entropy_coded_data(&mut self) -> io::Result<u8> {
+        loop {
+            // Copy the data up to the next 0xFF byte
+            let available = self.reader.fill_buf()?;
+            if available.is_empty() {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "Truncated JPEG image",
+                ));
+            }
+            let Some(end) = available.iter().position(|b| *b == 0xFF) else {
+                let len = available.len();
+                self.buffer.extend_from_slice(available);
+                self.reader.consume(len);
+                continue;
+            };
+            self.buffer.extend_from_slice(&available[..=end]);
+            self.reader.consume(end + 1);
+
+            // 0xFF00 is an escaped 0xFF and restart markers belong to the data
+            match self.read_byte()? {
+                0x00 => self.buffer.push(0x00),
+                marker @ 0xD0..=0xD7 => self.buffer.push(marker),
+                0xFF => {
+                    // Fill byte
+                    self.buffer.pop();
+                    let marker = self.read_fill_bytes()?;
+                    self.buffer.extend_from_slice(&[0xFF, marker]);
+                    return Ok(marker);
+                }
+                marker => {
+                    self.buffer.push(marker);
+                    return Ok(marker);
+                }
+            }
+        }
+    }
+
+    /// Reads a marker (`0xFF` followed by the marker code) into the buffer.
+    fn read_marker(&mut self) -> io::Result<u8> {
+        if self.read_byte()? != 0xFF {
+            return Err(invalid_data("Expected JPEG marker"));
+        }
+        let marker = self.read_fill_bytes()?;
+        self.buffer.extend_from_slice(&[0xFF, marker]);
+
+        Ok(marker)
+    }
+
+    /// Skips the optional `0xFF` fill bytes preceding a marker code and returns the code.
+    fn read_fill_bytes(&mut self) -> io::Result<u8> {
+        loop {
+            let byte = self.read_byte()?;
+            if byte != 0xFF {
+                return Ok(byte);
+            }
+        }
+    }
+
+    fn read_byte(&mut self) -> io::Result<u8> {
+        let mut byte = [0];
+        self.reader.read_exact(&mut byte)?;
+        Ok(byte[0])
+    }
+}
+
+impl<R: BufRead> Iterator for MjpegReader<R> {
+    type Item = io::Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_frame().transpose()
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,98 @@
+//! Frame sources for video processing: directories of images and raw Y4M or MJPEG streams.
+
+mod mjpeg;
+mod y4m;
+
+use std::{
+    fs,
+    io::{self, BufRead, BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+use image::DynamicImage;
+
+pub use mjpeg::MjpegReader;
+pub use y4m::Y4mReader;
+
+/// A decoded video frame.
+#[derive(Debug, Clone)]
+pub struct Frame {
+    /// Index of the frame in its source, starting at 0.
+    pub index: usize,
+    /// File name of the frame for directories, `frame_<index>` for streams.
+    pub name: String,
+    pub image: DynamicImage,
+}
+
+/// Iterator over the frames of a video source.
+pub enum FrameReader {
+    Directory { files: Vec<PathBuf>, index: usize },
+    Y4m(Y4mReader<Box<dyn BufRead>>),
+    Mjpeg(MjpegReader<Box<dyn BufRead>>),
+}
+
+impl FrameReader {
+    /// Reads the images of a directory as frames, in file name order.
+    ///
+    /// Files whose extension is not a supported image format are ignored.
+    pub fn directory(path: &Path) -> io::Result<Self> {
+        let mut files = fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<io::Result<Vec<_>>>()?;
+        files.retain(|file| {
+            file.is_file() && image::ImageFormat::from_path(file).is_ok_and(|f| f.can_read())
+        });
+        files.sort();
+
+        Ok(Self::Directory { files, index: 0 })
+    }
+
+    /// Reads frames from a raw stream, detecting whether it is a Y4M or an MJPEG (concatenated
+    /// JPEG images) stream from its first bytes.
+    pub fn stream<R: Read + 'static>(reader: R) -> io::Result<Self> {
+        let mut reader: Box<dyn BufRead> = Box::new(BufReader::new(reader));
+        let magic = reader.fill_buf()?;
+
+        if magic.starts_with(b"YUV4MPEG2") {
+            Ok(Self::Y4m(Y4mReader::new(reader)?))
+        } else if magic.starts_with(&[0xFF, 0xD8]) || magic.is_empty() {
+            Ok(Self::Mjpeg(MjpegReader::new(reader)))
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Unknown stream format, expected Y4M or MJPEG",
+            ))
+        }
+    }
+}
+
+impl Iterator for FrameReader {
+    type Item = io::Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Directory { files, index } => {
+                let path = files.get(*index)?;
+                let frame = image::open(path)
+                    .map(|image| Frame {
+                        index: *index,
+                        name: path
+                            .file_name()
+                            .map(|name| name.to_string_lossy().into_owned())
+                            .unwrap_or_default(),
+                        image,
+                    })
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err));
+                *index += 1;
+                Some(frame)
+            }
+            Self::Y4m(reader) => reader.next(),
+            Self::Mjpeg(reader) => reader.next(),
+        }
+    }
+}
+
+/// Name of the `index`-th frame of a stream.
+fn stream_frame_name(index: usize) -> String {
+    format!("frame_{index:06}")
+}
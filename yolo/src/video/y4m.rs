@@ -0,0 +1,177 @@
+use std::io::{self, BufRead};
+
+use image::{DynamicImage, RgbImage};
+
+use super::{stream_frame_name, Frame};
+
+/// Chroma subsampling of a Y4M stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Chroma {
+    /// 4:2:0, chroma planes with half the width and height.
+    C420,
+    /// 4:2:2, chroma planes with half the width.
+    C422,
+    /// 4:4:4, full resolution chroma planes.
+    C444,
+    /// Luma plane only.
+    Mono,
+}
+
+impl Chroma {
+    /// Chroma plane size `(width, height)` for the given luma plane size.
+    fn plane_size(&self, width: usize, height: usize) -> (usize, usize) {
+        match self {
+            Self::C420 => (width.div_ceil(2), height.div_ceil(2)),
+            Self::C422 => (width.div_ceil(2), height),
+            Self::C444 => (width, height),
+            Self::Mono => (0, 0),
+        }
+    }
+}
+
+/// Reads the frames of a [YUV4MPEG2](https://wiki.multimedia.cx/index.php/YUV4MPEG2) stream, as
+/// written by `ffmpeg -f yuv4mpegpipe`.
+///
+/// Only 8-bit streams are supported. Frames are converted to RGB assuming BT.601 limited range.
+pub struct Y4mReader<R> {
+    reader: R,
+    width: usize,
+    height: usize,
+    chroma: Chroma,
+    index: usize,
+    buffer: Vec<u8>,
+}
+
+impl<R: BufRead> Y4mReader<R> {
+    /// Parses the stream header.
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let header = read_line(&mut reader)?.ok_or_else(|| invalid_data("Empty Y4M stream"))?;
+        let mut params = header.split(' ');
+        if params.next() != Some("YUV4MPEG2") {
+            return Err(invalid_data("Missing YUV4MPEG2 signature"));
+        }
+
+        let (mut width, mut height, mut chroma) = (None, None, Chroma::C420);
+        for param in params {
+            let (tag, value) = param.split_at(param.len().min(1));
+            match tag {
+                "W" => width = value.parse().ok(),
+                "H" => height = value.parse().ok(),
+                "C" => {
+                    chroma = match value {
+                        "420" | "420jpeg" | "420paldv" | "420mpeg2" => Chroma::C420,
+                        "422" => Chroma::C422,
+                        "444" => Chroma::C444,
+                        "mono" => Chroma::Mono,
+                        _ => {
+                            return Err(invalid_data(&format!(
+                                "Unsupported Y4M colorspace {value}"
+                            )))
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        let (Some(width), Some(height)) = (width, height) else {
+            return Err(invalid_data("Missing Y4M frame size"));
+        };
+
+        Ok(Self {
+            reader,
+            width,
+            height,
+            chroma,
+            index: 0,
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Frame size `(width, height)`.
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn read_frame(&mut self) -> io::Result<Option<Frame>> {
+        let Some(header) = read_line(&mut self.reader)? else {
+            return Ok(None);
+        };
+        if !header.starts_with("FRAME") {
+            return Err(invalid_data("Missing Y4M frame header"));
+        }
+
+        let (w, h) = (self.width, self.height);
+        let (cw, ch) = self.chroma.plane_size(w, h);
+        self.buffer.resize(w * h + 2 * cw * ch, 0);
+        self.reader.read_exact(&mut self.buffer)?;
+
+        let (luma, chroma) = self.buffer.split_at(w * h);
+        let (u, v) = chroma.split_at(cw * ch);
+        let mut pixels = Vec::with_capacity(w * h * 3);
+        for y in 0..h {
+            for x in 0..w {
+                let luma = luma[y * w + x];
+                let rgb = match self.chroma {
+                    Chroma::Mono => yuv_to_rgb(luma, 128, 128),
+                    _ => {
+                        let i = (y * ch / h) * cw + x * cw / w;
+                        yuv_to_rgb(luma, u[i], v[i])
+                    }
+                };
+                pixels.extend_from_slice(&rgb);
+            }
+        }
+
+        let image = RgbImage::from_raw(w as u32, h as u32, pixels).unwrap();
+        let frame = Frame {
+            index: self.index,
+            name: stream_frame_name(self.index),
+            image: DynamicImage::ImageRgb8(image),
+        };
+        self.index += 1;
+
+        Ok(Some(frame))
+    }
+}
+
+impl<R: BufRead> Iterator for Y4mReader<R> {
+    type Item = io::Result<Frame>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_frame().transpose()
+    }
+}
+
+/// Converts a BT.601 limited range YCbCr pixel to RGB.
+fn yuv_to_rgb(y: u8, u: u8, v: u8) -> [u8; 3] {
+    let y = 1.164 * (y as f32 - 16.);
+    let (u, v) = (u as f32 - 128., v as f32 - 128.);
+
+    [
+        (y + 1.596 * v).round().clamp(0., 255.) as u8,
+        (y - 0.392 * u - 0.813 * v).round().clamp(0., 255.) as u8,
+        (y + 2.017 * u).round().clamp(0., 255.) as u8,
+    ]
+}
+
+/// Reads a header line, without the trailing newline. Returns `None` at the end of the stream.
+fn read_line<R: BufRead>(reader: &mut R) -> io::Result<Option<String>> {
+    let mut line = Vec::new();
+    if reader.read_until(b'\n', &mut line)? == 0 {
+        return Ok(None);
+    }
+    if line.pop() != Some(b'\n') {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "Truncated Y4M header",
+        ));
+    }
+
+    String::from_utf8(line)
+        .map(Some)
+        .map_err(|_| invalid_data("Invalid Y4M header"))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
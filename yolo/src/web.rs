@@ -1,15 +1,28 @@
 #![allow(clippy::new_without_default)]
 
-use alloc::string::String;
-use js_sys::Array;
+use alloc::{format, string::String, sync::Arc, vec::Vec};
+use image::{DynamicImage, RgbaImage};
+use inference_metrics::{now_ms, Metrics};
+use inference_runtime::mnist::{
+    drawing_to_ink, drawing_to_input, top_k, MnistOptions, NUM_CLASSES,
+};
+use inference_runtime::{BackendKind, Weights};
+use js_sys::{Array, Object, Reflect, Uint8Array, JSON};
+use wasm_bindgen::JsValue;
 
 #[cfg(target_family = "wasm")]
 use wasm_bindgen::prelude::*;
 
+use crate::detector::{Detector as YoloxDetector, DetectorConfig};
 use crate::model::Model;
-use crate::state::{build_and_load_model, Backend};
+use crate::scheduler::{Propagation, Scheduler, SchedulerConfig};
+#[cfg(feature = "embedded")]
+use crate::state::build_and_load_model;
+use crate::state::{build_and_load_model_from, init_device, Backend};
+use crate::yolox_model::{yolox::Yolox, COCO_CLASSES};
 
-use burn::tensor::Tensor;
+use burn::config::{config_to_json, Config};
+use burn::tensor::{activation::softmax, Tensor};
 
 #[cfg_attr(target_family = "wasm", wasm_bindgen(start))]
 pub fn start() {
@@ -20,16 +33,70 @@ pub fn start() {
 /// See:[exporting-rust-struct](https://rustwasm.github.io/wasm-bindgen/contributing/design/exporting-rust-struct.html)
 #[cfg_attr(target_family = "wasm", wasm_bindgen)]
 pub struct Mnist {
+    /// Weights received from JavaScript, loaded on the first inference instead of the embedded
+    /// ones.
+    weights: Option<Vec<u8>>,
     model: Option<Model<Backend>>,
+    options: MnistOptions,
 }
 
 #[cfg_attr(target_family = "wasm", wasm_bindgen)]
 impl Mnist {
-    /// Constructor called by JavaScripts with the new keyword.
+    /// Constructor called by JavaScripts with the new keyword, using the weights embedded in the
+    /// wasm module.
+    #[cfg(feature = "embedded")]
     #[cfg_attr(target_family = "wasm", wasm_bindgen(constructor))]
     pub fn new() -> Self {
         console_error_panic_hook::set_once();
-        Self { model: None }
+        Self {
+            weights: None,
+            model: None,
+            options: MnistOptions::new(),
+        }
+    }
+
+    /// Returns a model using weights received from JavaScript, e.g. with `fetchWeights`.
+    ///
+    /// # Arguments
+    ///
+    /// * `weights` - MNIST record, loaded on the first inference.
+    ///
+    /// # Returns
+    ///
+    /// An error if the weights are incomplete or do not match their checksum.
+    pub fn with_weights(weights: Weights) -> Result<Mnist, String> {
+        console_error_panic_hook::set_once();
+        Ok(Self {
+            weights: Some(weights.into_bytes()?),
+            model: None,
+            options: MnistOptions::new(),
+        })
+    }
+
+    /// Sets the inference options, see [MnistOptions]. `undefined` restores the default options.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - Plain object with the snake case names of the options, e.g.
+    ///   `{top_k: 3, logits: true}`. The missing options keep their default value.
+    pub fn set_options(&mut self, options: JsValue) -> Result<(), String> {
+        let options = if options.is_undefined() || options.is_null() {
+            MnistOptions::new()
+        } else {
+            // The missing options keep their default value
+            let defaults = JSON::parse(&config_to_json(&MnistOptions::new()))
+                .map_err(|_| "Failed to serialize the default options")?;
+            let options = Object::assign(&defaults.into(), &options.into());
+            let json: String = JSON::stringify(&options)
+                .map_err(|_| "The options cannot be serialized to JSON")?
+                .into();
+            MnistOptions::load_binary(json.as_bytes())
+                .map_err(|err| format!("Invalid options: {err}"))?
+        };
+        options.validate()?;
+        self.options = options;
+
+        Ok(())
     }
 
     /// Returns the inference results.
@@ -38,43 +105,410 @@ impl Mnist {
     ///
     /// # Arguments
     ///
-    /// * `input` - A f32 slice of input 28x28 image
+    /// * `rgba` - RGBA pixels of the drawing, of any size, e.g. the `data` of a canvas
+    ///   `ImageData`. The digit is cropped, scaled and normalized, see [drawing_to_input].
+    /// * `width` - Drawing width.
+    /// * `height` - Drawing height.
+    ///
+    /// # Returns
+    ///
+    /// The probabilities of the digits, or their logits with the `logits` option. With the
+    /// `top_k` option, the best digits as `{index, label, score}` objects, by decreasing score.
     ///
     /// See bindgen support types for passing and returning arrays:
     /// * [number-slices](https://rustwasm.github.io/wasm-bindgen/reference/types/number-slices.html)
     /// * [boxed-number-slices](https://rustwasm.github.io/wasm-bindgen/reference/types/boxed-number-slices.html)
     ///
-    pub async fn inference(&mut self, input: &[f32]) -> Result<Array, String> {
-        if self.model.is_none() {
-            self.model = Some(build_and_load_model().await);
+    pub async fn inference(
+        &mut self,
+        rgba: &[u8],
+        width: u32,
+        height: u32,
+    ) -> Result<Array, String> {
+        self.load_model().await?;
+
+        let device = Default::default();
+
+        // Crop, scale and normalize the drawing into a 3d tensor [batch, height, width]
+        let input = drawing_to_input::<Backend>(
+            rgba,
+            width as usize,
+            height as usize,
+            &self.options,
+            &device,
+        )?;
+        let scores = self.forward(input).await;
+
+        Ok(self.output(&scores[0]))
+    }
+
+    /// Returns the inference results of several drawings, classified with a single forward pass.
+    ///
+    /// # Arguments
+    ///
+    /// * `images` - Drawings as `{data, width, height}` objects, e.g. canvas `ImageData`s.
+    ///
+    /// # Returns
+    ///
+    /// The results of each drawing, see [inference](Mnist::inference).
+    pub async fn inference_batch(&mut self, images: Array) -> Result<Array, String> {
+        self.load_model().await?;
+
+        let device = Default::default();
+
+        let inputs = images
+            .iter()
+            .enumerate()
+            .map(|(index, image)| {
+                let get = |key: &str| {
+                    Reflect::get(&image, &key.into())
+                        .ok()
+                        .filter(|value| !value.is_undefined())
+                        .ok_or_else(|| format!("The image {index} has no {key}"))
+                };
+                let size = |key: &str| {
+                    get(key)?
+                        .as_f64()
+                        .map(|value| value as usize)
+                        .ok_or_else(|| format!("The {key} of the image {index} is not a number"))
+                };
+
+                let rgba = Uint8Array::new(&get("data")?).to_vec();
+                drawing_to_input::<Backend>(
+                    &rgba,
+                    size("width")?,
+                    size("height")?,
+                    &self.options,
+                    &device,
+                )
+                .map_err(|err| format!("Invalid image {index}: {err}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if inputs.is_empty() {
+            return Ok(Array::new());
         }
+        let scores = self.forward(Tensor::cat(inputs, 0)).await;
 
-        let model = self.model.as_ref().unwrap();
+        Ok(scores.iter().map(|scores| self.output(scores)).collect())
+    }
 
+    /// Returns the 28x28 RGBA pixels of the digit given to the model for a drawing, white on
+    /// black as the MNIST digits, e.g. to show them in a canvas.
+    ///
+    /// # Arguments
+    ///
+    /// * `rgba` - RGBA pixels of the drawing, as given to [inference](Mnist::inference).
+    /// * `width` - Drawing width.
+    /// * `height` - Drawing height.
+    pub async fn preview(&self, rgba: &[u8], width: u32, height: u32) -> Result<Vec<u8>, String> {
         let device = Default::default();
-        // Reshape from the 1D array to 3d tensor [batch, height, width]
-        let input = Tensor::<Backend, 1>::from_floats(input, &device).reshape([1, 28, 28]);
+        let margin = self.options.crop.then_some(self.options.margin);
+        let ink =
+            drawing_to_ink::<Backend>(rgba, width as usize, height as usize, margin, &device)?;
+        let ink = ink.into_data_async().await;
+
+        Ok(ink
+            .iter::<f32>()
+            .flat_map(|value| {
+                // Casts saturate, so only rounding is needed
+                let value = (value + 0.5) as u8;
+                [value, value, value, 255]
+            })
+            .collect())
+    }
+}
+
+impl Mnist {
+    /// Builds the model on the first inference.
+    async fn load_model(&mut self) -> Result<(), String> {
+        if self.model.is_none() {
+            let model = match self.weights.as_deref() {
+                Some(weights) => build_and_load_model_from(weights)
+                    .await
+                    .map_err(|err| format!("Failed to load the weights: {err}"))?,
+                #[cfg(feature = "embedded")]
+                None => build_and_load_model().await,
+                #[cfg(not(feature = "embedded"))]
+                None => unreachable!("Without embedded weights, models are built with weights"),
+            };
+            self.weights = None;
+            self.model = Some(model);
+        }
 
-        // Normalize input: make between [0,1] and make the mean=0 and std=1
-        // values mean=0.1307,std=0.3081 were copied from Pytorch Mist Example
-        // https://github.com/pytorch/examples/blob/54f4572509891883a947411fd7239237dd2a39c3/mnist/main.py#L122
+        Ok(())
+    }
 
-        let input = ((input / 255) - 0.1307) / 0.3081;
+    /// Runs the model on a `[batch, height, width]` input, and returns the scores of each digit
+    /// of the batch, following the `logits` option.
+    async fn forward(&self, input: Tensor<Backend, 3>) -> Vec<Vec<f32>> {
+        let model = self.model.as_ref().unwrap();
 
         // Run the tensor input through the model
         let output: Tensor<Backend, 2> = model.forward(input);
 
         // Convert the model output into probability distribution using softmax formula
-        let output = burn::tensor::activation::softmax(output, 1);
+        let output = if self.options.logits {
+            output
+        } else {
+            softmax(output, 1)
+        };
 
-        // Flatten output tensor with [1, 10] shape into boxed slice of [f32]
+        // Split the output tensor with [batch, 10] shape into the scores of each digit
         let output = output.into_data_async().await;
+        let scores: Vec<f32> = output.iter::<f32>().collect();
+
+        scores.chunks(NUM_CLASSES).map(<[f32]>::to_vec).collect()
+    }
+
+    /// Converts the scores of a digit into its result, see [inference](Mnist::inference).
+    fn output(&self, scores: &[f32]) -> Array {
+        let Some(k) = self.options.top_k else {
+            return scores.iter().map(|score| JsValue::from(*score)).collect();
+        };
+
+        top_k(scores, k)
+            .into_iter()
+            .map(|(class, score)| {
+                let result = Object::new();
+                for (key, value) in [
+                    ("index", JsValue::from(class as u32)),
+                    ("label", self.options.label(class).into()),
+                    ("score", score.into()),
+                ] {
+                    Reflect::set(&result, &key.into(), &value).unwrap();
+                }
+                result
+            })
+            .collect()
+    }
+}
+
+/// Detector structure that corresponds to JavaScript class, running YOLOX-Tiny on video frames.
+///
+/// A [scheduler](Scheduler) decides on which frames the detection runs, and propagates the boxes
+/// to the other ones, so that real-time streams can be processed on slow backends.
+///
+/// The backend is selected when building the wasm module, see [backend](Detector::backend).
+#[cfg_attr(target_family = "wasm", wasm_bindgen)]
+pub struct Detector {
+    weights: Option<Vec<u8>>,
+    config: DetectorConfig,
+    detector: Option<YoloxDetector<Backend>>,
+    scheduler: Scheduler,
+    metrics: Arc<Metrics>,
+}
 
+#[cfg_attr(target_family = "wasm", wasm_bindgen)]
+impl Detector {
+    /// Constructor called by JavaScripts with the new keyword.
+    ///
+    /// # Arguments
+    ///
+    /// * `weights` - YOLOX-Tiny weights, as written by `yolo convert`. They are loaded on the
+    ///   first detection.
+    #[cfg_attr(target_family = "wasm", wasm_bindgen(constructor))]
+    pub fn new(weights: &[u8]) -> Self {
+        Self::init(weights.to_vec())
+    }
+
+    /// Returns a detector using weights streamed from JavaScript, e.g. with `fetchWeights`.
+    ///
+    /// # Returns
+    ///
+    /// An error if the weights are incomplete or do not match their checksum.
+    pub fn with_weights(weights: Weights) -> Result<Detector, String> {
+        Ok(Self::init(weights.into_bytes()?))
+    }
+
+    /// Name of the backend the wasm module was built with, e.g. `ndarray` or `wgpu`.
+    pub fn backend() -> String {
+        BackendKind::selected().name().into()
+    }
+
+    /// Names of the COCO classes, indexed by the class of the boxes.
+    pub fn classes() -> Array {
+        COCO_CLASSES
+            .iter()
+            .map(|name| JsValue::from_str(name))
+            .collect()
+    }
+
+    /// Sets the model input size and the detection thresholds.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_size` - Side of the square the frames are resized to, a multiple of 32. Smaller
+    ///   sizes are faster but miss small objects.
+    /// * `score_threshold` - Minimum score of the reported boxes, in `[0, 1]`.
+    /// * `iou_threshold` - IoU threshold of the non-maximum suppression, in `[0, 1]`.
+    pub fn set_options(
+        &mut self,
+        input_size: usize,
+        score_threshold: f32,
+        iou_threshold: f32,
+    ) -> Result<(), String> {
+        if input_size == 0 || !input_size.is_multiple_of(32) {
+            return Err(format!(
+                "The input size must be a positive multiple of 32, got {input_size}"
+            ));
+        }
+        for (name, threshold) in [("score", score_threshold), ("IoU", iou_threshold)] {
+            if !(0. ..=1.).contains(&threshold) {
+                return Err(format!(
+                    "The {name} threshold must be in [0, 1], got {threshold}"
+                ));
+            }
+        }
+
+        self.config = DetectorConfig::new()
+            .with_input_height(input_size)
+            .with_input_width(input_size)
+            .with_score_threshold(score_threshold)
+            .with_iou_threshold(iou_threshold);
+        if let Some(detector) = &mut self.detector {
+            detector.set_config(self.config.clone());
+        }
+
+        Ok(())
+    }
+
+    /// Sets the frame skipping schedule. Resets the propagated boxes.
+    ///
+    /// # Arguments
+    ///
+    /// * `detection_interval` - Detection runs at least once every `detection_interval` frames.
+    /// * `motion_threshold` - Mean pixel difference (in `[0, 1]`) with the last detected frame
+    ///   above which detection runs earlier.
+    /// * `latency_budget_ms` - Target average processing time per frame. Zero or negative
+    ///   values disable the budget.
+    /// * `tracking` - Whether boxes are propagated with a tracker (and have track IDs), or with
+    ///   exponential smoothing.
+    pub fn set_schedule(
+        &mut self,
+        detection_interval: usize,
+        motion_threshold: f32,
+        latency_budget_ms: f64,
+        tracking: bool,
+    ) {
+        let propagation = if tracking {
+            Propagation::Tracker
+        } else {
+            Propagation::Smoothing
+        };
+        self.scheduler = SchedulerConfig::new()
+            .with_detection_interval(detection_interval.max(1))
+            .with_motion_threshold(motion_threshold)
+            .with_latency_budget_ms((latency_budget_ms > 0.).then_some(latency_budget_ms))
+            .with_propagation(propagation)
+            .init();
+    }
+
+    /// Returns the boxes of a video frame, detected or propagated from the previous detections.
+    ///
+    /// # Arguments
+    ///
+    /// * `rgba` - RGBA pixels of the frame, e.g. the `data` of a canvas `ImageData`.
+    /// * `width` - Frame width.
+    /// * `height` - Frame height.
+    ///
+    /// # Returns
+    ///
+    /// One `[class, xmin, ymin, xmax, ymax, confidence, track_id]` array per box, in frame
+    /// pixels. The track ID is `-1` without tracking.
+    pub async fn detect(&mut self, rgba: &[u8], width: u32, height: u32) -> Result<Array, String> {
         let array = Array::new();
-        for value in output.iter::<f32>() {
-            array.push(&value.into());
+        for values in self.boxes(rgba, width, height).await? {
+            array.push(
+                &values
+                    .iter()
+                    .map(|v| JsValue::from_f64(*v))
+                    .collect::<Array>(),
+            );
         }
 
         Ok(array)
     }
-}
\ No newline at end of file
+
+    /// Average detection latency in milliseconds, once a detection ran.
+    pub fn detection_latency_ms(&self) -> Option<f64> {
+        self.scheduler.detection_latency_ms()
+    }
+
+    /// Returns the metrics of the processed frames, as `{fps, frames, stages, counters}`:
+    /// * `stages` - `preprocess`, `forward` and `nms` latencies, each as
+    ///   `{count, mean_ms, p50_ms, p95_ms, p99_ms, max_ms}`.
+    /// * `counters` - Number of `detections`, and `detections_by_class`.
+    ///
+    /// Every frame counts for the frame rate, including the ones whose detection was skipped.
+    pub fn metrics(&self) -> Object {
+        self.metrics.to_js(now_ms())
+    }
+
+    /// Forgets the recorded metrics.
+    pub fn reset_metrics(&self) {
+        self.metrics.reset();
+    }
+}
+
+impl Detector {
+    /// Returns the `[class, xmin, ymin, xmax, ymax, confidence, track_id]` boxes of a video frame,
+    /// see [detect](Detector::detect).
+    pub(crate) async fn boxes(
+        &mut self,
+        rgba: &[u8],
+        width: u32,
+        height: u32,
+    ) -> Result<Vec<[f64; 7]>, String> {
+        if let Some(weights) = self.weights.take() {
+            let device = init_device().await;
+            let model = Yolox::yolox_tiny_from_bytes(weights, &device)
+                .map_err(|err| format!("Failed to load the weights: {err}"))?
+                .fuse();
+            self.detector = Some(self.config.init(model).with_metrics(self.metrics.clone()));
+        }
+        let detector = self.detector.as_ref().ok_or("The weights failed to load")?;
+
+        let frame = RgbaImage::from_raw(width, height, rgba.to_vec())
+            .map(DynamicImage::ImageRgba8)
+            .ok_or("The pixels do not match the frame size")?;
+
+        let frame = if self.scheduler.should_detect(&frame) {
+            let start = now_ms();
+            let boxes = detector.detect_async(&frame).await;
+            self.scheduler.on_detection(boxes, now_ms() - start)
+        } else {
+            self.scheduler.on_skip()
+        };
+        self.metrics.record_frame(now_ms());
+
+        let boxes = frame
+            .detections
+            .into_iter()
+            .map(|detection| {
+                let b = detection.bbox;
+                [
+                    detection.class as f64,
+                    b.xmin as f64,
+                    b.ymin as f64,
+                    b.xmax as f64,
+                    b.ymax as f64,
+                    b.confidence as f64,
+                    detection.track_id.map_or(-1., |id| id as f64),
+                ]
+            })
+            .collect();
+
+        Ok(boxes)
+    }
+
+    fn init(weights: Vec<u8>) -> Self {
+        console_error_panic_hook::set_once();
+        Self {
+            weights: Some(weights),
+            config: DetectorConfig::new(),
+            detector: None,
+            scheduler: SchedulerConfig::new().init(),
+            metrics: Arc::new(Metrics::new("yolo")),
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use alloc::string::String;
+use core::cell::RefCell;
+
+use inference_metrics::now_ms;
+use inference_runtime::{DropPolicy, Frame, FrameQueue, Weights};
+use js_sys::{Array, Object};
+use wasm_bindgen::JsValue;
+
+#[cfg(target_family = "wasm")]
+use wasm_bindgen::prelude::*;
+
+use crate::web::Detector;
+
+/// DetectorWorker structure that corresponds to JavaScript class, answering the frames posted to a
+/// Web Worker (see `worker.js`) so that detection does not block the main thread.
+///
+/// The frames are received with [receive](DetectorWorker::receive) as soon as they arrive, and
+/// detected one at a time with [process](DetectorWorker::process). The frames received meanwhile
+/// are dropped by the [DropPolicy] when the detector cannot keep up.
+///
+/// The output of the results is a `Float32Array` of 7 values per box, as returned by
+/// [Detector::detect].
+#[cfg_attr(target_family = "wasm", wasm_bindgen)]
+pub struct DetectorWorker {
+    /// Taken while a frame is detected.
+    detector: RefCell<Option<Detector>>,
+    frames: RefCell<FrameQueue<Frame>>,
+}
+
+#[cfg_attr(target_family = "wasm", wasm_bindgen)]
+impl DetectorWorker {
+    /// Constructor called by JavaScripts with the new keyword.
+    ///
+    /// # Arguments
+    ///
+    /// * `weights` - YOLOX-Tiny weights, as written by `yolo convert`.
+    /// * `policy` - What happens to the frames received during a detection: `latest` (default)
+    ///   keeps the most recent one, `skip` drops them, and `queue` detects all of them.
+    #[cfg_attr(target_family = "wasm", wasm_bindgen(constructor))]
+    pub fn new(weights: Weights, policy: Option<String>) -> Result<DetectorWorker, String> {
+        let policy = policy
+            .map(|policy| policy.parse())
+            .transpose()?
+            .unwrap_or(DropPolicy::Latest);
+
+        Ok(Self {
+            detector: RefCell::new(Some(Detector::with_weights(weights)?)),
+            frames: RefCell::new(FrameQueue::new(policy)),
+        })
+    }
+
+    /// Sets the frame skipping schedule, see [Detector::set_schedule].
+    pub fn set_schedule(
+        &self,
+        detection_interval: usize,
+        motion_threshold: f32,
+        latency_budget_ms: f64,
+        tracking: bool,
+    ) -> Result<(), String> {
+        let mut detector = self.detector.borrow_mut();
+        let detector = detector.as_mut().ok_or("A frame is being detected")?;
+        detector.set_schedule(
+            detection_interval,
+            motion_threshold,
+            latency_budget_ms,
+            tracking,
+        );
+
+        Ok(())
+    }
+
+    /// Sets the model input size and the detection thresholds, see [Detector::set_options].
+    pub fn set_options(
+        &self,
+        input_size: usize,
+        score_threshold: f32,
+        iou_threshold: f32,
+    ) -> Result<(), String> {
+        let mut detector = self.detector.borrow_mut();
+        let detector = detector.as_mut().ok_or("A frame is being detected")?;
+
+        detector.set_options(input_size, score_threshold, iou_threshold)
+    }
+
+    /// Handles the data of a message received by the worker, see [FrameQueue::receive].
+    ///
+    /// # Returns
+    ///
+    /// The responses to post right away, for the frames dropped or cancelled by the message.
+    pub fn receive(&self, data: JsValue) -> Result<Array, String> {
+        self.frames.borrow_mut().receive(&data)
+    }
+
+    /// Detects the next pending frame.
+    ///
+    /// # Returns
+    ///
+    /// The response to post, or `undefined` if no frame is pending or one is being detected.
+    pub async fn process(&self) -> JsValue {
+        let Some(mut detector) = self.detector.take() else {
+            return JsValue::UNDEFINED;
+        };
+        let Some((id, frame)) = self.frames.borrow_mut().start() else {
+            self.detector.replace(Some(detector));
+            return JsValue::UNDEFINED;
+        };
+
+        let start = now_ms();
+        let output = detector
+            .boxes(&frame.rgba(), frame.width, frame.height)
+            .await
+            .map(|boxes| boxes.iter().flatten().map(|v| *v as f32).collect());
+        self.detector.replace(Some(detector));
+
+        self.frames
+            .borrow_mut()
+            .respond(id, frame, output, now_ms() - start)
+    }
+
+    /// Returns the metrics of the detector, see [Detector::metrics].
+    pub fn metrics(&self) -> Result<Object, String> {
+        let detector = self.detector.borrow();
+        let detector = detector.as_ref().ok_or("A frame is being detected")?;
+
+        Ok(detector.metrics())
+    }
+}
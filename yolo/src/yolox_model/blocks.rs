@@ -4,10 +4,11 @@ use burn::{
     module::Module,
     nn::{
         conv::{Conv2d, Conv2dConfig},
-        BatchNorm, BatchNormConfig, PaddingConfig2d,
+        BatchNormConfig, PaddingConfig2d,
     },
     tensor::{activation::silu, backend::Backend, Device, Tensor},
 };
+use inference_runtime::{fold_batch_norm, ConvNorm};
 
 /// Compute the number of channels based on the provided factor.
 pub fn expand(num_channels: usize, factor: f64) -> usize {
@@ -18,6 +19,7 @@ pub fn expand(num_channels: usize, factor: f64) -> usize {
 /// Allows to switch between regular and depthwise separable convolution blocks based on the
 /// architecture.
 #[derive(Module, Debug)]
+#[allow(clippy::large_enum_variant)]
 pub enum Conv<B: Backend> {
     /// Basic convolution block used for all variants.
     BaseConv(BaseConv<B>),
@@ -32,6 +34,14 @@ impl<B: Backend> Conv<B> {
             Self::DwsConv(conv) => conv.forward(x),
         }
     }
+
+    /// Folds the batch norms into the convolutions, see [BaseConv::fuse].
+    pub fn fuse(self) -> Self {
+        match self {
+            Self::BaseConv(conv) => Self::BaseConv(conv.fuse()),
+            Self::DwsConv(conv) => Self::DwsConv(conv.fuse()),
+        }
+    }
 }
 
 #[derive(Config)]
@@ -75,7 +85,7 @@ impl ConvConfig {
 #[derive(Module, Debug)]
 pub struct BaseConv<B: Backend> {
     conv: Conv2d<B>,
-    bn: BatchNorm<B, 2>,
+    bn: ConvNorm<B>,
 }
 
 impl<B: Backend> BaseConv<B> {
@@ -85,6 +95,14 @@ impl<B: Backend> BaseConv<B> {
 
         silu(x)
     }
+
+    /// Folds the batch norm into the convolution, which skips it at inference with the same
+    /// output. The fused block must not be trained.
+    pub fn fuse(self) -> Self {
+        let (conv, bn) = fold_batch_norm(self.conv, self.bn);
+
+        Self { conv, bn }
+    }
 }
 
 /// [Base convolution block](BaseConv) configuration.
@@ -121,7 +139,7 @@ impl BaseConvConfig {
     pub fn init<B: Backend>(&self, device: &Device<B>) -> BaseConv<B> {
         BaseConv {
             conv: self.conv.init(device),
-            bn: self.bn.init(device),
+            bn: self.bn.init(device).into(),
         }
     }
 }
@@ -139,6 +157,14 @@ impl<B: Backend> DwsConv<B> {
         let x = self.dconv.forward(x);
         self.pconv.forward(x)
     }
+
+    /// Folds the batch norms into the convolutions, see [BaseConv::fuse].
+    pub fn fuse(self) -> Self {
+        Self {
+            dconv: self.dconv.fuse(),
+            pconv: self.pconv.fuse(),
+        }
+    }
 }
 
 /// [Depthwise separable convolution block](DwsConv) configuration.
@@ -209,6 +235,13 @@ impl<B: Backend> Focus<B> {
 
         self.conv.forward(x)
     }
+
+    /// Folds the batch norm into the convolution, see [BaseConv::fuse].
+    pub fn fuse(self) -> Self {
+        Self {
+            conv: self.conv.fuse(),
+        }
+    }
 }
 
 /// [Focus block](Focus) configuration.
@@ -244,6 +277,14 @@ impl<B: Backend> ConvBlock<B> {
         let x = self.conv0.forward(x);
         self.conv1.forward(x)
     }
+
+    /// Folds the batch norms into the convolutions, see [BaseConv::fuse].
+    pub fn fuse(self) -> Self {
+        Self {
+            conv0: self.conv0.fuse(),
+            conv1: self.conv1.fuse(),
+        }
+    }
 }
 
 /// [Dual convolution block](ConvBlock) configuration.
@@ -30,6 +30,15 @@ impl<B: Backend> Bottleneck<B> {
 
         x
     }
+
+    /// Folds the batch norms into the convolutions, see [BaseConv::fuse].
+    pub fn fuse(self) -> Self {
+        Self {
+            conv1: self.conv1.fuse(),
+            conv2: self.conv2.fuse(),
+            ..self
+        }
+    }
 }
 
 /// [Bottleneck block](Bottleneck) configuration.
@@ -89,6 +98,15 @@ impl<B: Backend> SppBottleneck<B> {
 
         self.conv2.forward(x)
     }
+
+    /// Folds the batch norms into the convolutions, see [BaseConv::fuse].
+    pub fn fuse(self) -> Self {
+        Self {
+            conv1: self.conv1.fuse(),
+            conv2: self.conv2.fuse(),
+            ..self
+        }
+    }
 }
 
 /// [SppBottleneck block](SppBottleneck) configuration.
@@ -152,6 +170,16 @@ impl<B: Backend> CspBottleneck<B> {
 
         self.conv3.forward(x)
     }
+
+    /// Folds the batch norms into the convolutions, see [BaseConv::fuse].
+    pub fn fuse(self) -> Self {
+        Self {
+            conv1: self.conv1.fuse(),
+            conv2: self.conv2.fuse(),
+            conv3: self.conv3.fuse(),
+            m: self.m.into_iter().map(Bottleneck::fuse).collect(),
+        }
+    }
 }
 
 /// [CspBottleneck block](CspBottleneck) configuration.
@@ -1,7 +1,8 @@
 use alloc::vec::Vec;
-use burn::tensor::{backend::Backend, ElementConversion, Tensor};
+use burn::tensor::{backend::Backend, ElementConversion, Tensor, TensorData};
 use itertools::Itertools;
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct BoundingBox {
     pub xmin: f32,
     pub ymin: f32,
@@ -33,34 +34,74 @@ pub fn nms<B: Backend>(
     iou_threshold: f32,
     score_threshold: f32,
 ) -> Vec<Vec<Vec<BoundingBox>>> {
-    let [batch_size, num_boxes, num_classes] = scores.dims();
+    let dims = scores.dims();
+
+    // Keep max scoring boxes only ([batch_size, num_boxes, 1], [batch_size, num_boxes, 1])
+    let (cls_score, cls_idx) = scores.max_dim_with_indices(2);
+
+    select_boxes::<B>(
+        cls_score.into_data(),
+        cls_idx.into_data(),
+        boxes.into_data(),
+        dims,
+        iou_threshold,
+        score_threshold,
+    )
+}
+
+/// [Non-maximum suppression](nms) reading the tensors asynchronously, as required by the `wgpu`
+/// backend in the browser.
+pub async fn nms_async<B: Backend>(
+    boxes: Tensor<B, 3>,
+    scores: Tensor<B, 3>,
+    iou_threshold: f32,
+    score_threshold: f32,
+) -> Vec<Vec<Vec<BoundingBox>>> {
+    let dims = scores.dims();
+    let (cls_score, cls_idx) = scores.max_dim_with_indices(2);
+
+    select_boxes::<B>(
+        cls_score.into_data_async().await,
+        cls_idx.into_data_async().await,
+        boxes.into_data_async().await,
+        dims,
+        iou_threshold,
+        score_threshold,
+    )
+}
+
+/// Filters the boxes of [nms], given the data of the max class scores, their class indices and the
+/// box coordinates.
+fn select_boxes<B: Backend>(
+    cls_score: TensorData,
+    cls_idx: TensorData,
+    boxes: TensorData,
+    [batch_size, num_boxes, num_classes]: [usize; 3],
+    iou_threshold: f32,
+    score_threshold: f32,
+) -> Vec<Vec<Vec<BoundingBox>>> {
+    let cls_score: Vec<_> = cls_score
+        .iter::<B::FloatElem>()
+        .map(|v| v.elem::<f32>())
+        .collect();
+    let cls_idx: Vec<_> = cls_idx
+        .iter::<B::IntElem>()
+        .map(|v| v.elem::<i64>() as usize)
+        .collect();
+    // [batch_size, num_boxes, 4]
+    let boxes: Vec<_> = boxes
+        .iter::<B::FloatElem>()
+        .map(|v| v.elem::<f32>())
+        .collect();
 
     // Bounding boxes grouped by batch and by (maximum) class index
-    let mut bboxes = boxes
-        .iter_dim(0)
-        .zip(scores.iter_dim(0))
-        .enumerate()
+    let mut bboxes = (0..batch_size)
         // Per-batch
-        .map(|(_, (candidate_boxes, candidate_scores))| {
-            // Keep max scoring boxes only ([num_boxes, 1], [num_boxes, 1])
-            let (cls_score, cls_idx) = candidate_scores.squeeze::<2>(0).max_dim_with_indices(1);
-            let cls_score: Vec<_> = cls_score
-                .into_data()
-                .iter::<B::FloatElem>()
-                .map(|v| v.elem::<f32>())
-                .collect();
-            let cls_idx: Vec<_> = cls_idx
-                .into_data()
-                .iter::<B::IntElem>()
-                .map(|v| v.elem::<i64>() as usize)
-                .collect();
-
-            // [num_boxes, 4]
-            let candidate_boxes: Vec<_> = candidate_boxes
-                .into_data()
-                .iter::<B::FloatElem>()
-                .map(|v| v.elem::<f32>())
-                .collect();
+        .map(|batch| {
+            let offset = batch * num_boxes;
+            let cls_score = &cls_score[offset..offset + num_boxes];
+            let cls_idx = &cls_idx[offset..offset + num_boxes];
+            let candidate_boxes = &boxes[offset * 4..(offset + num_boxes) * 4];
 
             // Per-class filtering based on score
             (0..num_classes)
@@ -93,7 +134,7 @@ pub fn nms<B: Backend>(
         })
         .collect::<Vec<_>>();
 
-    for batch_bboxes in bboxes.iter_mut().take(batch_size) {
+    for batch_bboxes in bboxes.iter_mut() {
         non_maximum_suppression(batch_bboxes, iou_threshold);
     }
 
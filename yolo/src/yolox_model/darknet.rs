@@ -34,6 +34,18 @@ impl<B: Backend> CspDarknet<B> {
 
         DarknetFeatures(f1, f2, f3)
     }
+
+    /// Folds the batch norms into the convolutions, see
+    /// [BaseConv::fuse](super::blocks::BaseConv::fuse).
+    pub fn fuse(self) -> Self {
+        Self {
+            stem: self.stem.fuse(),
+            dark2: self.dark2.fuse(),
+            dark3: self.dark3.fuse(),
+            dark4: self.dark4.fuse(),
+            dark5: self.dark5.fuse(),
+        }
+    }
 }
 
 /// [CSPDarknet-53](CspDarknet) configuration.
@@ -131,6 +143,16 @@ impl<B: Backend> CspBlock<B> {
 
         self.c3.forward(x)
     }
+
+    /// Folds the batch norms into the convolutions, see
+    /// [BaseConv::fuse](super::blocks::BaseConv::fuse).
+    pub fn fuse(self) -> Self {
+        Self {
+            conv: self.conv.fuse(),
+            c3: self.c3.fuse(),
+            spp: self.spp.map(SppBottleneck::fuse),
+        }
+    }
 }
 
 /// [CSP block](CspBlock) configuration.
@@ -87,6 +87,16 @@ impl<B: Backend> Head<B> {
         self.decode(Tensor::cat(outputs, 2).swap_dims(2, 1), shapes.as_ref())
     }
 
+    /// Folds the batch norms into the convolutions, see [BaseConv::fuse].
+    pub fn fuse(self) -> Self {
+        Self {
+            stems: self.stems.into_iter().map(BaseConv::fuse).collect(),
+            cls_convs: self.cls_convs.into_iter().map(ConvBlock::fuse).collect(),
+            reg_convs: self.reg_convs.into_iter().map(ConvBlock::fuse).collect(),
+            ..self
+        }
+    }
+
     /// Decode bounding box absolute values from regression output offsets.
     fn decode(&self, outputs: Tensor<B, 3>, shapes: &[(usize, usize)]) -> Tensor<B, 3> {
         let device = outputs.device();
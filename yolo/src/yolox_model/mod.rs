@@ -1,9 +1,11 @@
 mod blocks;
 mod bottleneck;
 pub mod boxes;
+mod classes;
 mod darknet;
 mod head;
 mod pafpn;
 pub mod yolox;
 
-pub use boxes::BoundingBox;
\ No newline at end of file
+pub use boxes::BoundingBox;
+pub use classes::COCO_CLASSES;
\ No newline at end of file
@@ -67,6 +67,21 @@ impl<B: Backend> Pafpn<B> {
 
         FpnFeatures(pan_out2, pan_out1, pan_out0)
     }
+
+    /// Folds the batch norms into the convolutions, see [BaseConv::fuse].
+    pub fn fuse(self) -> Self {
+        Self {
+            backbone: self.backbone.fuse(),
+            lateral_conv0: self.lateral_conv0.fuse(),
+            c3_n3: self.c3_n3.fuse(),
+            c3_n4: self.c3_n4.fuse(),
+            c3_p3: self.c3_p3.fuse(),
+            c3_p4: self.c3_p4.fuse(),
+            reduce_conv1: self.reduce_conv1.fuse(),
+            bu_conv1: self.bu_conv1.fuse(),
+            bu_conv2: self.bu_conv2.fuse(),
+        }
+    }
 }
 
 /// [PAFPN block](Pafpn) configuration.
@@ -3,6 +3,7 @@ use burn::{
     module::{ConstantRecord, Module},
     tensor::{backend::Backend, Device, Tensor},
 };
+use inference_runtime::ModelLoader;
 
 use crate::yolox_model::bottleneck::SPP_POOLING;
 
@@ -29,18 +30,35 @@ impl<B: Backend> Yolox<B> {
         self.head.forward(features)
     }
 
+    /// Folds the batch norm of every convolution block into its convolution, for a faster
+    /// inference with the same detections. The fused model must not be trained, and its records
+    /// cannot be loaded into an unfused model.
+    pub fn fuse(self) -> Self {
+        Self {
+            backbone: self.backbone.fuse(),
+            head: self.head.fuse(),
+        }
+    }
+
     pub fn yolox_tiny(device: &Device<B>) -> Result<Self, RecorderError> {
         let record = Self::load_weights_record(device)?;
-        let model = YoloxConfig::new(0.33, 0.375, 80, false).init(device).load_record(record);
+        let model = YoloxConfig::yolox_tiny().init(device).load_record(record);
 
         Ok(model)
     }
 
-    /// Load specified pre-trained PyTorch weights as a record.
-    fn load_weights_record(
+    /// YOLOX-Tiny with the weights of a record saved with
+    /// [BinBytesRecorder](burn::record::BinBytesRecorder) (e.g. by `yolo convert`), which unlike
+    /// the PyTorch weights can be loaded in the browser.
+    pub fn yolox_tiny_from_bytes(
+        bytes: Vec<u8>,
         device: &Device<B>,
-    ) -> Result<YoloxRecord<B>, RecorderError> {
+    ) -> Result<Self, RecorderError> {
+        Self::from_bytes(&YoloxConfig::yolox_tiny(), bytes, device)
+    }
 
+    /// Load specified pre-trained PyTorch weights as a record.
+    fn load_weights_record(device: &Device<B>) -> Result<YoloxRecord<B>, RecorderError> {
         // Load weights from torch state_dict
         let load_args = LoadArgs::new("yolox_tiny.pth".into())
             // State dict contains "model", "amp", "optimizer", "start_epoch"
@@ -92,6 +110,11 @@ impl YoloxConfig {
         Self { backbone, head }
     }
 
+    /// Configuration of YOLOX-Tiny, trained on the 80 COCO classes.
+    pub fn yolox_tiny() -> Self {
+        Self::new(0.33, 0.375, 80, false)
+    }
+
     /// Initialize a new [YOLOX detector](Yolox) module.
     pub fn init<B: Backend>(&self, device: &Device<B>) -> Yolox<B> {
         Yolox {
@@ -100,3 +123,11 @@ impl YoloxConfig {
         }
     }
 }
+
+impl<B: Backend> ModelLoader<B> for Yolox<B> {
+    type Config = YoloxConfig;
+
+    fn init_model(config: &Self::Config, device: &Device<B>) -> Self {
+        config.init(device)
+    }
+}
@@ -0,0 +1,140 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+use yolo::{
+    anonymize::{AnonymizerConfig, Redaction},
+    yolox_model::BoundingBox,
+};
+
+fn bbox(xmin: f32, ymin: f32, xmax: f32, ymax: f32) -> BoundingBox {
+    BoundingBox {
+        xmin,
+        ymin,
+        xmax,
+        ymax,
+        confidence: 0.9,
+    }
+}
+
+/// Black and white checkerboard of 1 pixel squares.
+fn checkerboard(width: u32, height: u32) -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_fn(width, height, |x, y| {
+        if (x + y) % 2 == 0 {
+            Rgba([255, 255, 255, 255])
+        } else {
+            Rgba([0, 0, 0, 255])
+        }
+    }))
+}
+
+fn is_gray(pixel: &Rgba<u8>) -> bool {
+    (64..=192).contains(&pixel.0[0])
+}
+
+#[test]
+fn fill_covers_the_padded_box() {
+    let anonymizer = AnonymizerConfig::new()
+        .with_redaction(Redaction::Fill)
+        .with_fill_color([255, 0, 0])
+        .with_padding(0.25)
+        .with_elliptical(false)
+        .init();
+    let image = checkerboard(64, 64);
+
+    let anonymized = anonymizer.anonymize(&image, &[bbox(20., 20., 40., 40.)]);
+
+    // 5 pixels of padding on each side
+    let red = Rgba([255, 0, 0, 255]);
+    assert_eq!(*anonymized.get_pixel(15, 15), red);
+    assert_eq!(*anonymized.get_pixel(44, 30), red);
+    assert_ne!(*anonymized.get_pixel(14, 30), red);
+    assert_ne!(*anonymized.get_pixel(45, 30), red);
+    assert_eq!(
+        anonymized.get_pixel(0, 0),
+        image.as_rgba8().unwrap().get_pixel(0, 0)
+    );
+}
+
+#[test]
+fn elliptical_mask_keeps_the_corners() {
+    let anonymizer = AnonymizerConfig::new()
+        .with_redaction(Redaction::Fill)
+        .with_padding(0.)
+        .init();
+    let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(40, 40, Rgba([255; 4])));
+
+    let anonymized = anonymizer.anonymize(&image, &[bbox(0., 0., 40., 20.)]);
+
+    let black = Rgba([0, 0, 0, 255]);
+    assert_eq!(*anonymized.get_pixel(20, 10), black);
+    assert_eq!(*anonymized.get_pixel(1, 10), black);
+    assert_eq!(*anonymized.get_pixel(20, 0), black);
+    assert_eq!(*anonymized.get_pixel(0, 0), Rgba([255; 4]));
+    assert_eq!(*anonymized.get_pixel(39, 19), Rgba([255; 4]));
+    assert_eq!(*anonymized.get_pixel(20, 25), Rgba([255; 4]));
+}
+
+#[test]
+fn blur_averages_the_region_only() {
+    let anonymizer = AnonymizerConfig::new()
+        .with_elliptical(false)
+        .with_padding(0.)
+        .init();
+    let image = checkerboard(64, 64);
+
+    let anonymized = anonymizer.anonymize(&image, &[bbox(16., 16., 48., 48.)]);
+
+    assert!((18..46).all(|x| is_gray(anonymized.get_pixel(x, 32))));
+    assert_eq!(*anonymized.get_pixel(10, 10), Rgba([255, 255, 255, 255]));
+    assert_eq!(*anonymized.get_pixel(50, 51), Rgba([0, 0, 0, 255]));
+}
+
+#[test]
+fn pixelate_produces_uniform_blocks() {
+    let anonymizer = AnonymizerConfig::new()
+        .with_redaction(Redaction::Pixelate)
+        .with_pixel_blocks(4)
+        .with_elliptical(false)
+        .with_padding(0.)
+        .init();
+    // Horizontal gradient
+    let image = DynamicImage::ImageRgba8(RgbaImage::from_fn(32, 32, |x, _| {
+        Rgba([x as u8 * 8, 0, 0, 255])
+    }));
+
+    let anonymized = anonymizer.anonymize(&image, &[bbox(0., 0., 32., 16.)]);
+
+    // 4x4 pixels blocks, along the smaller side
+    for (x, y) in [(0, 0), (3, 3), (9, 6), (30, 15)] {
+        let block = (x / 4 * 4, y / 4 * 4);
+        assert_eq!(
+            anonymized.get_pixel(x, y),
+            anonymized.get_pixel(block.0, block.1)
+        );
+    }
+    assert_ne!(anonymized.get_pixel(3, 0), anonymized.get_pixel(4, 0));
+    assert_eq!(
+        anonymized.get_pixel(5, 20),
+        image.as_rgba8().unwrap().get_pixel(5, 20)
+    );
+}
+
+#[test]
+fn inverted_and_out_of_bounds_boxes() {
+    let anonymizer = AnonymizerConfig::new()
+        .with_redaction(Redaction::Fill)
+        .with_elliptical(false)
+        .with_padding(0.)
+        .init();
+    let image = DynamicImage::ImageRgba8(RgbaImage::from_pixel(16, 16, Rgba([255; 4])));
+    let boxes = [
+        bbox(8., 8., -10., -10.),
+        bbox(100., 100., 200., 200.),
+        bbox(5., 5., 5., 5.),
+    ];
+
+    let anonymized = anonymizer.anonymize(&image, &boxes);
+
+    assert_eq!(*anonymized.get_pixel(0, 0), Rgba([0, 0, 0, 255]));
+    assert_eq!(*anonymized.get_pixel(7, 7), Rgba([0, 0, 0, 255]));
+    assert_eq!(*anonymized.get_pixel(8, 8), Rgba([255; 4]));
+    assert_eq!(*anonymized.get_pixel(15, 15), Rgba([255; 4]));
+}
@@ -0,0 +1,172 @@
+use std::{sync::Barrier, thread};
+
+use burn::backend::NdArray;
+use image::{DynamicImage, Rgb, RgbImage};
+use yolo::{
+    detector::{Detector, DetectorConfig},
+    server::{BatchError, BatcherConfig},
+    yolox_model::yolox::YoloxConfig,
+};
+
+type Backend = NdArray<f32>;
+
+fn detector() -> Detector<Backend> {
+    let model = YoloxConfig::new(0.33, 0.25, 2, true).init::<Backend>(&Default::default());
+    DetectorConfig::new()
+        .with_input_height(64)
+        .with_input_width(64)
+        .with_score_threshold(0.)
+        .init(model)
+}
+
+fn image() -> DynamicImage {
+    DynamicImage::ImageRgb8(RgbImage::from_fn(64, 64, |x, y| {
+        Rgb([0, (x * 4) as u8, (y * 4) as u8])
+    }))
+}
+
+#[test]
+fn concurrent_requests_are_batched() {
+    let batcher = BatcherConfig::new()
+        .with_max_batch_size(2)
+        .with_max_wait_ms(5000.)
+        .init(detector());
+    // Same content, so the boxes only differ by the scaling
+    let images = [
+        DynamicImage::ImageRgb8(RgbImage::from_pixel(64, 64, Rgb([0, 128, 255]))),
+        DynamicImage::ImageRgb8(RgbImage::from_pixel(128, 64, Rgb([0, 128, 255]))),
+    ];
+
+    let barrier = Barrier::new(images.len());
+    let boxes: Vec<_> = thread::scope(|scope| {
+        let handles: Vec<_> = images
+            .iter()
+            .map(|image| {
+                scope.spawn(|| {
+                    barrier.wait();
+                    batcher.detect(image.clone()).unwrap()
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    // Each request gets the boxes of its own image
+    let (small, wide) = (&boxes[0][0], &boxes[1][0]);
+    assert!(!small.is_empty());
+    assert_eq!(small.len(), wide.len());
+    for (a, b) in small.iter().zip(wide) {
+        assert!((a.xmin * 2. - b.xmin).abs() < 1e-3);
+        assert!((a.ymax - b.ymax).abs() < 1e-3);
+    }
+
+    // A full batch does not wait for the deadline
+    let metrics = batcher.metrics();
+    assert_eq!(metrics.batches, 1);
+    assert_eq!(metrics.requests, 2);
+    assert_eq!(metrics.batch_sizes, [0, 0, 1]);
+    assert_eq!(metrics.mean_batch_size(), 2.);
+    assert!(metrics.max_wait_ms < 2500., "{}", metrics.max_wait_ms);
+    assert_eq!(metrics.queue_depth, 0);
+}
+
+#[test]
+fn single_request_waits_for_the_deadline() {
+    let batcher = BatcherConfig::new()
+        .with_max_batch_size(8)
+        .with_max_wait_ms(50.)
+        .init(detector());
+
+    let boxes = batcher.detect(image()).unwrap();
+    assert_eq!(boxes.len(), 2);
+    batcher.detect(image()).unwrap();
+
+    let metrics = batcher.metrics();
+    assert_eq!(metrics.batches, 2);
+    assert_eq!(metrics.batch_sizes, [0, 2]);
+    assert!(metrics.max_wait_ms >= 45., "{}", metrics.max_wait_ms);
+    assert!(metrics.mean_wait_ms() >= 45., "{}", metrics.mean_wait_ms());
+}
+
+#[test]
+fn full_queue_rejects_requests() {
+    let batcher = BatcherConfig::new()
+        .with_max_batch_size(1)
+        .with_max_wait_ms(0.)
+        .with_max_queue_size(1)
+        .init(detector());
+    let num_requests = 12;
+
+    // One request is running, one is waiting, the others are rejected
+    let barrier = Barrier::new(num_requests);
+    let results: Vec<_> = thread::scope(|scope| {
+        let handles: Vec<_> = (0..num_requests)
+            .map(|_| {
+                scope.spawn(|| {
+                    barrier.wait();
+                    batcher.detect(image())
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    let rejected = results
+        .iter()
+        .filter(|result| matches!(result, Err(BatchError::QueueFull)))
+        .count();
+    let succeeded = results.iter().filter(|result| result.is_ok()).count();
+    assert!(rejected > 0);
+    assert_eq!(rejected + succeeded, num_requests);
+
+    let metrics = batcher.metrics();
+    assert_eq!(metrics.rejected, rejected as u64);
+    assert_eq!(metrics.requests, succeeded as u64);
+    assert_eq!(metrics.queue_depth, 0);
+    assert!(metrics.batch_sizes.len() == 2, "{:?}", metrics.batch_sizes);
+}
+
+#[test]
+fn detector_panics_only_fail_their_batch() {
+    // The model panics on inputs which are not a multiple of its strides
+    let model = YoloxConfig::new(0.33, 0.25, 2, true).init::<Backend>(&Default::default());
+    let detector = DetectorConfig::new()
+        .with_input_height(50)
+        .with_input_width(50)
+        .init(model);
+    let batcher = BatcherConfig::new()
+        .with_max_batch_size(1)
+        .with_max_wait_ms(0.)
+        .init(detector);
+
+    // The worker keeps serving requests after a panic
+    assert_eq!(batcher.detect(image()), Err(BatchError::Failed));
+    assert_eq!(batcher.detect(image()), Err(BatchError::Failed));
+
+    let metrics = batcher.metrics();
+    assert_eq!(metrics.batches, 2);
+    assert_eq!(metrics.queue_depth, 0);
+}
+
+#[test]
+fn invalid_configs_are_rejected() {
+    assert!(BatcherConfig::new().validate().is_ok());
+    assert!(BatcherConfig::new().with_max_wait_ms(0.).validate().is_ok());
+
+    let invalid = [
+        BatcherConfig::new().with_max_batch_size(0),
+        BatcherConfig::new().with_max_queue_size(0),
+        BatcherConfig::new().with_max_wait_ms(-1.),
+        BatcherConfig::new().with_max_wait_ms(f64::NAN),
+        BatcherConfig::new().with_max_wait_ms(f64::INFINITY),
+    ];
+    for config in invalid {
+        assert!(config.validate().is_err(), "{config:?}");
+    }
+}
+
+#[test]
+#[should_panic(expected = "The maximum queue size must be positive")]
+fn batcher_requires_a_queue() {
+    BatcherConfig::new().with_max_queue_size(0).init(detector());
+}
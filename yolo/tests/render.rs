@@ -0,0 +1,150 @@
+use image::{DynamicImage, Rgba, RgbaImage};
+use yolo::{
+    render::{Annotation, RendererConfig, DEFAULT_PALETTE},
+    yolox_model::BoundingBox,
+};
+
+fn bbox(xmin: f32, ymin: f32, xmax: f32, ymax: f32) -> BoundingBox {
+    BoundingBox {
+        xmin,
+        ymin,
+        xmax,
+        ymax,
+        confidence: 0.875,
+    }
+}
+
+fn blank(width: u32, height: u32) -> DynamicImage {
+    DynamicImage::ImageRgba8(RgbaImage::from_pixel(width, height, Rgba([0, 0, 0, 255])))
+}
+
+fn color(index: usize) -> Rgba<u8> {
+    let [r, g, b] = DEFAULT_PALETTE[index];
+    Rgba([r, g, b, 255])
+}
+
+#[test]
+fn outline_has_configured_thickness() {
+    let renderer = RendererConfig::new()
+        .with_thickness(3)
+        .with_font_scale(0)
+        .init();
+    let annotations = [Annotation::new(1, bbox(10., 10., 40., 40.))];
+
+    let image = renderer.render(&blank(50, 50), &annotations);
+
+    for x in [10, 11, 12, 38, 39, 40] {
+        assert_eq!(*image.get_pixel(x, 25), color(1), "x = {x}");
+    }
+    for x in [9, 13, 25, 37, 41] {
+        assert_eq!(*image.get_pixel(x, 25), Rgba([0, 0, 0, 255]), "x = {x}");
+    }
+}
+
+#[test]
+fn inverted_and_out_of_bounds_boxes_are_clipped() {
+    let renderer = RendererConfig::new().with_thickness(1).init();
+    let annotations = [
+        Annotation::new(0, bbox(30., 30., -20., 5.)),
+        Annotation::new(0, bbox(-100., -100., 500., 500.)),
+        Annotation::new(0, bbox(1000., 1000., 2000., 2000.)),
+    ]
+    .map(|annotation| annotation.with_landmarks(vec![[-5., 100.], [31., 31.]]));
+
+    let image = renderer.render(&blank(32, 32), &annotations);
+
+    // The inverted box is drawn as if its corners were ordered
+    assert_eq!(*image.get_pixel(30, 20), color(0));
+    assert_eq!(*image.get_pixel(31, 31), color(0));
+}
+
+#[test]
+fn label_text() {
+    let annotation = Annotation::new(0, bbox(0., 0., 1., 1.));
+    let renderer = RendererConfig::new().init();
+
+    assert_eq!(renderer.label(&annotation), "person 0.88");
+    assert_eq!(
+        renderer.label(&annotation.clone().with_track_id(3).with_identity("alice")),
+        "person 0.88 #3 alice"
+    );
+
+    let renderer = RendererConfig::new()
+        .with_show_confidence(false)
+        .with_class_names(vec!["face".into()])
+        .init();
+    assert_eq!(renderer.label(&annotation), "face");
+    assert_eq!(
+        renderer.label(&Annotation::new(4, bbox(0., 0., 1., 1.))),
+        "4"
+    );
+}
+
+#[test]
+fn label_is_drawn_above_the_box() {
+    let renderer = RendererConfig::new()
+        .with_thickness(1)
+        .with_font_scale(1)
+        .init();
+    let annotations = [Annotation::new(0, bbox(10., 40., 60., 80.))];
+
+    let image = renderer.render(&blank(100, 100), &annotations);
+
+    // 7 pixels high text with 1 pixel of padding, on the palette color
+    let label_pixels = (31..40)
+        .flat_map(|y| (10..60).map(move |x| (x, y)))
+        .filter(|(x, y)| *image.get_pixel(*x, *y) != Rgba([0, 0, 0, 255]))
+        .count();
+    assert!(label_pixels > 100, "{label_pixels}");
+    assert_eq!(*image.get_pixel(10, 31), color(0));
+    assert_eq!(*image.get_pixel(10, 30), Rgba([0, 0, 0, 255]));
+    // Nothing is drawn inside the box
+    assert_eq!(*image.get_pixel(30, 50), Rgba([0, 0, 0, 255]));
+}
+
+#[test]
+fn landmarks_are_drawn_as_dots() {
+    let renderer = RendererConfig::new()
+        .with_font_scale(0)
+        .with_landmark_radius(2)
+        .init();
+    let annotations = [Annotation::new(2, bbox(0., 0., 60., 60.)).with_landmarks(vec![[30., 30.]])];
+
+    let image = renderer.render(&blank(64, 64), &annotations);
+
+    for (x, y) in [(30, 30), (28, 30), (32, 30), (30, 28), (31, 31)] {
+        assert_eq!(*image.get_pixel(x, y), color(2), "({x}, {y})");
+    }
+    assert_eq!(*image.get_pixel(32, 32), Rgba([0, 0, 0, 255]));
+    assert_eq!(*image.get_pixel(33, 30), Rgba([0, 0, 0, 255]));
+}
+
+#[test]
+fn boxes_are_colored_by_class_or_track() {
+    let annotations = [
+        Annotation::new(0, bbox(2., 2., 10., 10.)).with_track_id(5),
+        Annotation::new(9, bbox(20., 2., 30., 10.)),
+    ];
+
+    let by_class = RendererConfig::new().with_font_scale(0).init();
+    let image = by_class.render(&blank(32, 16), &annotations);
+    assert_eq!(*image.get_pixel(2, 5), color(0));
+    // Class indices wrap around the palette
+    assert_eq!(*image.get_pixel(20, 5), color(1));
+
+    let by_track = RendererConfig::new()
+        .with_font_scale(0)
+        .with_color_by_track(true)
+        .init();
+    let image = by_track.render(&blank(32, 16), &annotations);
+    assert_eq!(*image.get_pixel(2, 5), color(5));
+    // Untracked boxes fall back to the class color
+    assert_eq!(*image.get_pixel(20, 5), color(1));
+
+    let custom = RendererConfig::new()
+        .with_font_scale(0)
+        .with_palette(vec![[1, 2, 3]])
+        .init();
+    let image = custom.render(&blank(32, 16), &annotations);
+    assert_eq!(*image.get_pixel(20, 5), Rgba([1, 2, 3, 255]));
+}
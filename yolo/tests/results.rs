@@ -0,0 +1,291 @@
+use std::{fs, io::Cursor};
+
+use yolo::{
+    results::{
+        read_coco, read_csv, read_json_lines, read_voc, read_yolo, write_voc, write_yolo,
+        CocoWriter, CsvWriter, DetectionResult, FrameResult, ResultFormat, ResultWriter,
+    },
+    yolox_model::COCO_CLASSES,
+};
+
+fn detection(class: usize, confidence: f32, bbox: [f32; 4]) -> DetectionResult {
+    DetectionResult {
+        class,
+        confidence,
+        bbox,
+        track_id: None,
+        identity: None,
+        similarity: None,
+    }
+}
+
+fn frames() -> Vec<FrameResult> {
+    let mut tracked = detection(0, 0.91, [10.5, 20., 110.25, 220.]);
+    tracked.track_id = Some(3);
+    tracked.identity = Some("alice, \"al\"".into());
+    tracked.similarity = Some(0.75);
+
+    vec![
+        FrameResult {
+            frame: 0,
+            name: "a.jpg".into(),
+            width: 640,
+            height: 480,
+            detections: vec![tracked, detection(2, 0.5, [300., 100., 640., 480.])],
+        },
+        FrameResult {
+            frame: 1,
+            name: "b & c.jpg".into(),
+            width: 320,
+            height: 240,
+            detections: vec![],
+        },
+        FrameResult {
+            frame: 2,
+            name: "d.jpg".into(),
+            width: 320,
+            height: 240,
+            detections: vec![detection(79, 0.66, [0., 0., 32., 24.])],
+        },
+    ]
+}
+
+fn class_names() -> Vec<String> {
+    COCO_CLASSES.iter().map(|name| name.to_string()).collect()
+}
+
+fn assert_detections_eq(actual: &[DetectionResult], expected: &[DetectionResult], tolerance: f32) {
+    assert_eq!(actual.len(), expected.len());
+    for (a, e) in actual.iter().zip(expected) {
+        assert_eq!(a.class, e.class);
+        assert!(
+            (a.confidence - e.confidence).abs() <= 1e-5,
+            "{a:?} != {e:?}"
+        );
+        for (a, e) in a.bbox.iter().zip(e.bbox) {
+            assert!((a - e).abs() <= tolerance, "{a} != {e}");
+        }
+    }
+}
+
+/// Frame results without the information the formats do not keep.
+fn detections_only(frames: &[FrameResult]) -> Vec<Vec<DetectionResult>> {
+    frames
+        .iter()
+        .filter(|frame| !frame.detections.is_empty())
+        .map(|frame| frame.detections.clone())
+        .collect()
+}
+
+#[test]
+fn json_lines_round_trip() {
+    let dir = std::env::temp_dir().join(format!("yolo-results-jsonl-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = ResultFormat::JsonLines.default_path(&dir);
+
+    let mut writer = ResultWriter::create(ResultFormat::JsonLines, &path, &[]).unwrap();
+    for frame in frames() {
+        writer.write(&frame).unwrap();
+    }
+    writer.finish().unwrap();
+    let text = fs::read_to_string(&path).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    // Optional fields are omitted
+    assert!(text.lines().nth(1).unwrap().ends_with("\"detections\":[]}"));
+    assert!(!text.lines().nth(2).unwrap().contains("track_id"));
+    assert_eq!(read_json_lines(Cursor::new(text)).unwrap(), frames());
+}
+
+#[test]
+fn coco_round_trip() {
+    let mut writer = CocoWriter::new(Vec::new()).unwrap();
+    for frame in frames() {
+        writer.write(&frame).unwrap();
+    }
+    let json = writer.finish().unwrap();
+
+    let parsed: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    let first = &parsed[0];
+    assert_eq!(first["image_id"], 0);
+    assert_eq!(first["category_id"], 1);
+    assert_eq!(first["bbox"][2], 99.75);
+    // The last COCO class has ID 90
+    assert_eq!(parsed[2]["category_id"], 90);
+
+    let read = read_coco(Cursor::new(json)).unwrap();
+    assert_eq!(read.iter().map(|f| f.frame).collect::<Vec<_>>(), [0, 2]);
+    for (read, expected) in read.iter().zip(detections_only(&frames())) {
+        assert_detections_eq(&read.detections, &expected, 1e-4);
+    }
+}
+
+#[test]
+fn coco_empty_and_invalid() {
+    let json = CocoWriter::new(Vec::new()).unwrap().finish().unwrap();
+    assert!(read_coco(Cursor::new(json)).unwrap().is_empty());
+
+    let mut writer = CocoWriter::new(Vec::new()).unwrap();
+    let mut frame = frames().remove(0);
+    frame.detections[0].class = 80;
+    assert!(writer.write(&frame).is_err());
+
+    let json = r#"[{"image_id": 0, "category_id": 12, "bbox": [0, 0, 1, 1], "score": 1}]"#;
+    assert!(read_coco(Cursor::new(json)).is_err());
+}
+
+#[test]
+fn yolo_round_trip() {
+    for frame in frames() {
+        let mut text = Vec::new();
+        write_yolo(&mut text, &frame).unwrap();
+
+        let read = read_yolo(Cursor::new(text), frame.width, frame.height).unwrap();
+        assert_detections_eq(&read, &frame.detections, 1e-2);
+    }
+
+    let frame = &frames()[0];
+    let mut text = Vec::new();
+    write_yolo(&mut text, frame).unwrap();
+    let line = String::from_utf8(text)
+        .unwrap()
+        .lines()
+        .next()
+        .unwrap()
+        .to_string();
+    assert_eq!(line, "0 0.094336 0.250000 0.155859 0.416667 0.910000");
+
+    // Boxes of empty frames cannot be normalized
+    let empty = FrameResult {
+        width: 0,
+        ..frames()[2].clone()
+    };
+    let err = write_yolo(&mut Vec::new(), &empty).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+}
+
+#[test]
+fn yolo_labels_without_confidence() {
+    let labels = "1 0.5 0.5 0.5 0.25\n\n0 0.25 0.75 0.5 0.5\n";
+
+    let read = read_yolo(Cursor::new(labels), 100, 200).unwrap();
+
+    assert_detections_eq(
+        &read,
+        &[
+            detection(1, 1., [25., 75., 75., 125.]),
+            detection(0, 1., [0., 100., 50., 200.]),
+        ],
+        1e-4,
+    );
+    assert!(read_yolo(Cursor::new("0 0.5 0.5"), 100, 100).is_err());
+    assert!(read_yolo(Cursor::new("person 0.5 0.5 1 1"), 100, 100).is_err());
+}
+
+#[test]
+fn voc_round_trip() {
+    let class_names = class_names();
+    for frame in frames() {
+        let mut xml = Vec::new();
+        write_voc(&mut xml, &frame, &class_names).unwrap();
+        let text = String::from_utf8(xml.clone()).unwrap();
+        assert_eq!(text.contains("<name>person</name>"), frame.frame == 0);
+
+        let read = read_voc(Cursor::new(xml), &class_names).unwrap();
+        assert_eq!(read.name, frame.name);
+        assert_eq!((read.width, read.height), (frame.width, frame.height));
+        assert_detections_eq(&read.detections, &frame.detections, 0.5);
+    }
+
+    // Boxes are written as 1-based integer pixel indices
+    let mut xml = Vec::new();
+    write_voc(&mut xml, &frames()[0], &class_names).unwrap();
+    let text = String::from_utf8(xml).unwrap();
+    for element in [
+        "<xmin>12</xmin>",
+        "<ymin>21</ymin>",
+        "<xmax>110</xmax>",
+        "<ymax>220</ymax>",
+    ] {
+        assert!(text.contains(element), "{element} not in {text}");
+    }
+}
+
+#[test]
+fn voc_unnamed_classes_and_ground_truth() {
+    // Classes without a name are written by index
+    let frame = &frames()[2];
+    let mut xml = Vec::new();
+    write_voc(&mut xml, frame, &[]).unwrap();
+    assert!(String::from_utf8(xml.clone())
+        .unwrap()
+        .contains("<name>79</name>"));
+    let read = read_voc(Cursor::new(xml), &[]).unwrap();
+    assert_eq!(read.detections[0].class, 79);
+
+    let ground_truth = "<annotation><filename>x.png</filename>\
+        <object><name>dog</name><bndbox><xmin>1</xmin><ymin>2</ymin><xmax>3</xmax><ymax>4</ymax>\
+        </bndbox></object></annotation>";
+    let read = read_voc(Cursor::new(ground_truth), &class_names()).unwrap();
+    assert_eq!((read.width, read.height), (0, 0));
+    assert_detections_eq(&read.detections, &[detection(16, 1., [0., 1., 3., 4.])], 0.);
+
+    // Fractional coordinates, as written by some tools, are accepted
+    let fractional = ground_truth.replace("<xmin>1</xmin>", "<xmin>1.5</xmin>");
+    let read = read_voc(Cursor::new(fractional), &class_names()).unwrap();
+    assert_detections_eq(
+        &read.detections,
+        &[detection(16, 1., [0.5, 1., 3., 4.])],
+        0.,
+    );
+
+    let unknown = ground_truth.replace("dog", "unicorn");
+    assert!(read_voc(Cursor::new(unknown), &class_names()).is_err());
+}
+
+#[test]
+fn csv_round_trip() {
+    let mut writer = CsvWriter::new(Vec::new());
+    for frame in frames() {
+        writer.write(&frame).unwrap();
+    }
+    let csv = writer.finish().unwrap();
+
+    let text = String::from_utf8(csv.clone()).unwrap();
+    assert_eq!(
+        text.lines().next().unwrap(),
+        "frame,name,width,height,class,confidence,xmin,ymin,xmax,ymax,track_id,identity,similarity"
+    );
+    assert_eq!(text.lines().count(), 4);
+
+    // Frames without detections have no rows
+    let expected: Vec<_> = frames()
+        .into_iter()
+        .filter(|frame| !frame.detections.is_empty())
+        .collect();
+    assert_eq!(read_csv(Cursor::new(csv)).unwrap(), expected);
+}
+
+#[test]
+fn per_frame_formats_write_one_file_per_frame() {
+    let dir = std::env::temp_dir().join(format!("yolo-results-voc-{}", std::process::id()));
+    let path = ResultFormat::Voc.default_path(&dir);
+    assert!(ResultFormat::Voc.is_per_frame());
+
+    let mut writer = ResultWriter::create(ResultFormat::Voc, &path, &class_names()).unwrap();
+    for frame in frames() {
+        writer.write(&frame).unwrap();
+    }
+    writer.finish().unwrap();
+
+    let mut files: Vec<_> = fs::read_dir(&path)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    files.sort();
+    let read = read_voc(fs::File::open(path.join("a.xml")).unwrap(), &class_names()).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(files, ["a.xml", "b & c.xml", "d.xml"]);
+    assert_detections_eq(&read.detections, &frames()[0].detections, 0.5);
+}
@@ -0,0 +1,155 @@
+use image::{DynamicImage, Luma};
+use yolo::{
+    scheduler::{Propagation, Scheduler, SchedulerConfig},
+    yolox_model::BoundingBox,
+};
+
+fn frame(value: u8) -> DynamicImage {
+    DynamicImage::ImageLuma8(image::GrayImage::from_pixel(64, 48, Luma([value])))
+}
+
+fn bbox(x: f32, confidence: f32) -> BoundingBox {
+    BoundingBox {
+        xmin: x,
+        ymin: 10.,
+        xmax: x + 40.,
+        ymax: 50.,
+        confidence,
+    }
+}
+
+/// Runs the scheduler over the given frames with a detector returning `boxes(frame index)` for
+/// class 0, and returns whether each frame was detected.
+fn schedule(
+    scheduler: &mut Scheduler,
+    frames: &[DynamicImage],
+    latency_ms: f64,
+    boxes: impl Fn(usize) -> Vec<BoundingBox>,
+) -> Vec<bool> {
+    frames
+        .iter()
+        .enumerate()
+        .map(|(i, frame)| {
+            if scheduler.should_detect(frame) {
+                scheduler.on_detection(vec![boxes(i)], latency_ms).detected
+            } else {
+                scheduler.on_skip().detected
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn detection_runs_every_interval() {
+    let mut scheduler = SchedulerConfig::new().with_detection_interval(3).init();
+    let frames = vec![frame(100); 7];
+
+    let detected = schedule(&mut scheduler, &frames, 10., |_| vec![]);
+
+    assert_eq!(detected, [true, false, false, true, false, false, true]);
+    assert_eq!(scheduler.detection_latency_ms(), Some(10.));
+}
+
+#[test]
+fn motion_triggers_detection() {
+    let mut scheduler = SchedulerConfig::new()
+        .with_detection_interval(10)
+        .with_motion_threshold(0.1)
+        .init();
+    // Small changes are ignored, a scene change is detected
+    let frames = [frame(100), frame(105), frame(110), frame(200), frame(200)];
+
+    let detected = schedule(&mut scheduler, &frames, 10., |_| vec![]);
+
+    assert_eq!(detected, [true, false, false, true, false]);
+}
+
+#[test]
+fn latency_budget_limits_detection_rate() {
+    let mut scheduler = SchedulerConfig::new()
+        .with_detection_interval(1)
+        .with_motion_threshold(0.)
+        .with_latency_budget_ms(Some(25.))
+        .init();
+    let frames: Vec<_> = (0..9).map(|i| frame(i * 20)).collect();
+
+    // 100ms detections within 25ms per frame: one detection every 4 frames
+    let detected = schedule(&mut scheduler, &frames, 100., |_| vec![]);
+
+    assert_eq!(
+        detected,
+        [true, false, false, false, true, false, false, false, true]
+    );
+}
+
+#[test]
+fn tracker_extrapolates_skipped_frames() {
+    let mut scheduler = SchedulerConfig::new().with_detection_interval(2).init();
+    let speed = 4.;
+
+    let mut last = None;
+    for i in 0..20 {
+        let frame = if scheduler.should_detect(&frame(0)) {
+            scheduler.on_detection(vec![vec![bbox(speed * i as f32, 0.9)]], 1.)
+        } else {
+            scheduler.on_skip()
+        };
+
+        assert_eq!(frame.detected, i % 2 == 0);
+        assert_eq!(frame.detections.len(), 1, "frame {i}");
+        let detection = &frame.detections[0];
+        assert_eq!(detection.class, 0);
+        assert_eq!(detection.track_id, Some(1));
+        last = Some(detection.bbox);
+    }
+
+    // The box keeps moving on skipped frames once the velocity is learned
+    let last = last.unwrap();
+    assert!((last.xmin - speed * 19.).abs() < 2., "xmin = {}", last.xmin);
+}
+
+#[test]
+fn tracks_of_different_classes_have_distinct_ids() {
+    let mut scheduler = SchedulerConfig::new().init();
+
+    assert!(scheduler.should_detect(&frame(0)));
+    let frame = scheduler.on_detection(vec![vec![bbox(0., 0.9)], vec![bbox(0., 0.9)]], 1.);
+
+    let ids: Vec<_> = frame
+        .detections
+        .iter()
+        .map(|d| (d.class, d.track_id))
+        .collect();
+    assert_eq!(ids, [(0, Some(1)), (1, Some(2))]);
+}
+
+#[test]
+fn smoothing_averages_detections_and_holds_boxes() {
+    let mut scheduler = SchedulerConfig::new()
+        .with_detection_interval(2)
+        .with_propagation(Propagation::Smoothing)
+        .with_smoothing_factor(0.5)
+        .init();
+    let frames = vec![frame(0); 5];
+    let positions = [0., 0., 10., 0., 14.];
+
+    let mut boxes = Vec::new();
+    for (i, frame) in frames.iter().enumerate() {
+        let scheduled = if scheduler.should_detect(frame) {
+            scheduler.on_detection(vec![vec![], vec![bbox(positions[i], 0.8)]], 1.)
+        } else {
+            scheduler.on_skip()
+        };
+        assert!(scheduled.detections.iter().all(|d| d.track_id.is_none()));
+        assert!(scheduled.detections.iter().all(|d| d.class == 1));
+        boxes.push(scheduled.detections[0].bbox.xmin);
+    }
+
+    assert_eq!(boxes, [0., 0., 5., 5., 9.5]);
+
+    // Non-overlapping detections replace the boxes
+    scheduler.reset();
+    assert!(scheduler.should_detect(&frames[0]));
+    let scheduled = scheduler.on_detection(vec![vec![], vec![bbox(500., 0.8)]], 1.);
+    assert_eq!(scheduled.detections[0].bbox, bbox(500., 0.8));
+}
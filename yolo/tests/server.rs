@@ -0,0 +1,279 @@
+use std::{
+    io::{Cursor, Read, Write},
+    net::{SocketAddr, TcpStream},
+    sync::OnceLock,
+    thread,
+};
+
+use burn::backend::NdArray;
+use facenet_burn::mobilefacenet::MobileFaceNetConfig;
+use image::{DynamicImage, ImageOutputFormat, Rgb, RgbImage};
+use serde_json::Value;
+use tiny_http::Server;
+use yolo::{
+    detector::DetectorConfig,
+    recognition::Recognizer,
+    server::{InferenceServer, InferenceServerConfig},
+    yolox_model::yolox::YoloxConfig,
+};
+
+type Backend = NdArray<f32>;
+
+fn face(color: [u8; 3]) -> DynamicImage {
+    DynamicImage::ImageRgb8(RgbImage::from_fn(48, 48, |x, y| {
+        if (x / 8 + y / 8) % 2 == 0 {
+            Rgb(color)
+        } else {
+            Rgb([255 - color[0], 255 - color[1], 255 - color[2]])
+        }
+    }))
+}
+
+fn png(image: &DynamicImage) -> Vec<u8> {
+    let mut bytes = Cursor::new(Vec::new());
+    image.write_to(&mut bytes, ImageOutputFormat::Png).unwrap();
+    bytes.into_inner()
+}
+
+/// Small randomly initialized models.
+fn inference_server(with_recognizer: bool) -> InferenceServer<Backend> {
+    let device = Default::default();
+    let model = YoloxConfig::new(0.33, 0.25, 2, true).init::<Backend>(&device);
+    let detector = DetectorConfig::new()
+        .with_input_height(64)
+        .with_input_width(64)
+        .with_score_threshold(0.)
+        .init(model);
+
+    let recognizer = with_recognizer.then(|| {
+        let config = MobileFaceNetConfig::new()
+            .with_embedding_size(16)
+            .with_width_multiplier(0.25)
+            .with_input_size(64);
+        let mut recognizer = Recognizer::new(config.init(&device), 64, 0.99, &device);
+        recognizer.add_identities(
+            ["alice".to_string(), "bob".to_string()],
+            &[face([255, 0, 0]), face([0, 0, 255])],
+        );
+        recognizer
+    });
+
+    InferenceServerConfig::new()
+        .init(detector, recognizer)
+        .with_class_names(vec!["face".into(), "hand".into()])
+}
+
+/// Starts a server in the background, and returns its address.
+fn start(with_recognizer: bool) -> SocketAddr {
+    let http = Server::http("127.0.0.1:0").unwrap();
+    let address = http.server_addr().to_ip().unwrap();
+    let server = inference_server(with_recognizer);
+    thread::spawn(move || server.serve(&http));
+
+    address
+}
+
+/// Server shared by the tests, with a recognizer.
+fn address() -> SocketAddr {
+    static ADDRESS: OnceLock<SocketAddr> = OnceLock::new();
+    *ADDRESS.get_or_init(|| start(true))
+}
+
+/// Sends a request with a minimal HTTP/1.1 client, and returns the status, the content type and
+/// the body.
+fn raw_request(
+    address: SocketAddr,
+    method: &str,
+    path: &str,
+    body: &[u8],
+) -> (u16, String, String) {
+    let mut stream = TcpStream::connect(address).unwrap();
+    write!(
+        stream,
+        "{method} {path} HTTP/1.1\r\nHost: {address}\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n",
+        body.len()
+    )
+    .unwrap();
+    stream.write_all(body).unwrap();
+
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let status = head.split(' ').nth(1).unwrap().parse().unwrap();
+    let content_type = head
+        .lines()
+        .find_map(|line| {
+            let (name, value) = line.split_once(':')?;
+            name.eq_ignore_ascii_case("Content-Type")
+                .then(|| value.trim().to_string())
+        })
+        .unwrap_or_default();
+
+    (status, content_type, body.to_string())
+}
+
+/// Sends a request, and returns the status and the JSON body.
+fn request(address: SocketAddr, method: &str, path: &str, body: &[u8]) -> (u16, Value) {
+    let (status, content_type, body) = raw_request(address, method, path, body);
+    assert_eq!(content_type, "application/json");
+
+    (status, serde_json::from_str(&body).unwrap())
+}
+
+#[test]
+fn health() {
+    let (status, body) = request(address(), "GET", "/health", b"");
+
+    assert_eq!(status, 200);
+    assert_eq!(body["status"], "ok");
+}
+
+#[test]
+fn model_info() {
+    let (status, body) = request(address(), "GET", "/model-info", b"");
+
+    assert_eq!(status, 200);
+    assert_eq!(body["detector"]["input_width"], 64);
+    assert_eq!(body["detector"]["score_threshold"], 0.);
+    assert_eq!(body["detector"]["class_names"][1], "hand");
+    assert_eq!(body["recognizer"]["input_size"], 64);
+    assert_eq!(
+        body["recognizer"]["identities"],
+        serde_json::json!(["alice", "bob"])
+    );
+}
+
+#[test]
+fn detect_returns_boxes_in_image_coordinates() {
+    let image = DynamicImage::ImageRgb8(RgbImage::new(128, 32));
+
+    let (status, body) = request(address(), "POST", "/detect", &png(&image));
+
+    assert_eq!(status, 200, "{body}");
+    assert_eq!(body["width"], 128);
+    assert_eq!(body["height"], 32);
+    let detections = body["detections"].as_array().unwrap();
+    assert!(!detections.is_empty());
+    for detection in detections {
+        assert!(detection["class"].as_u64().unwrap() < 2);
+        let bbox: Vec<_> = detection["bbox"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|value| value.as_f64().unwrap())
+            .collect();
+        assert_eq!(bbox.len(), 4);
+        assert!(detection["confidence"].as_f64().is_some());
+    }
+}
+
+#[test]
+fn embed_returns_unit_embedding() {
+    let (status, body) = request(address(), "POST", "/embed", &png(&face([255, 0, 0])));
+
+    assert_eq!(status, 200, "{body}");
+    let embedding: Vec<_> = body["embedding"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|value| value.as_f64().unwrap())
+        .collect();
+    assert_eq!(embedding.len(), 16);
+    let norm = embedding.iter().map(|x| x * x).sum::<f64>().sqrt();
+    assert!((norm - 1.).abs() < 1e-4, "{norm}");
+}
+
+#[test]
+fn identify_matches_gallery() {
+    let (status, body) = request(address(), "POST", "/identify", &png(&face([0, 0, 255])));
+
+    assert_eq!(status, 200, "{body}");
+    assert_eq!(body["identity"], "bob");
+    assert!(body["similarity"].as_f64().unwrap() > 0.99);
+
+    // Below the identity threshold
+    let unknown =
+        DynamicImage::ImageRgb8(RgbImage::from_fn(48, 48, |x, _| Rgb([x as u8 * 5, 128, 0])));
+    let (status, body) = request(address(), "POST", "/identify", &png(&unknown));
+    assert_eq!(status, 200, "{body}");
+    assert!(body["identity"].is_null());
+    assert!(body["similarity"].is_null());
+}
+
+#[test]
+fn metrics_report_stages_and_detection_batches() {
+    let address = start(false);
+    let image = png(&DynamicImage::ImageRgb8(RgbImage::new(64, 64)));
+    for _ in 0..3 {
+        assert_eq!(request(address, "POST", "/detect", &image).0, 200);
+    }
+
+    let (status, content_type, body) = raw_request(address, "GET", "/metrics", b"");
+
+    assert_eq!(status, 200);
+    assert!(content_type.starts_with("text/plain"), "{content_type}");
+    let value = |name: &str| -> f64 {
+        body.lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(' '))
+            .unwrap_or_else(|| panic!("Missing {name} in\n{body}"))
+            .parse()
+            .unwrap()
+    };
+    for stage in ["preprocess", "forward", "nms"] {
+        let count = format!("yolo_stage_latency_milliseconds_count{{stage=\"{stage}\"}}");
+        assert_eq!(value(&count), 3.);
+    }
+    assert_eq!(value("yolo_frames_total"), 3.);
+    assert!(value("yolo_fps") > 0.);
+    assert_eq!(value("yolo_batched_requests_total"), 3.);
+    assert_eq!(value("yolo_rejected_requests_total"), 0.);
+    assert_eq!(value("yolo_queue_depth"), 0.);
+    assert_eq!(value("yolo_max_batch_size"), 8.);
+    assert!(value("yolo_batches_total") >= 1.);
+    assert!(value("yolo_mean_batch_size") >= 1.);
+}
+
+#[test]
+fn errors() {
+    let address = address();
+
+    let (status, body) = request(address, "POST", "/detect", b"not an image");
+    assert_eq!(status, 400);
+    assert!(body["error"].as_str().unwrap().contains("decode"));
+
+    assert_eq!(request(address, "GET", "/detect", b"").0, 405);
+    assert_eq!(request(address, "POST", "/health", b"").0, 405);
+    assert_eq!(request(address, "GET", "/unknown", b"").0, 404);
+}
+
+#[test]
+fn recognition_endpoints_require_a_recognizer() {
+    let address = start(false);
+    let image = png(&face([255, 0, 0]));
+
+    assert_eq!(request(address, "POST", "/embed", &image).0, 503);
+    assert_eq!(request(address, "POST", "/identify", &image).0, 503);
+    let (status, body) = request(address, "GET", "/model-info", b"");
+    assert_eq!(status, 200);
+    assert!(body["recognizer"].is_null());
+}
+
+#[test]
+fn recognizer_panics_only_fail_their_request() {
+    let device = Default::default();
+    let model = YoloxConfig::new(0.33, 0.25, 2, true).init::<Backend>(&device);
+    let detector = DetectorConfig::new().init(model);
+    // Crops resized below the input size of the model make it panic
+    let config = MobileFaceNetConfig::new()
+        .with_embedding_size(16)
+        .with_width_multiplier(0.25)
+        .with_input_size(64);
+    let recognizer = Recognizer::new(config.init(&device), 16, 0.99, &device);
+    let server = InferenceServerConfig::new().init(detector, Some(recognizer));
+    let image = png(&face([255, 0, 0]));
+
+    assert_eq!(server.handle("POST", "/embed", &image).status, 500);
+    assert_eq!(server.handle("POST", "/identify", &image).status, 500);
+    assert_eq!(server.handle("GET", "/model-info", b"").status, 200);
+}
@@ -0,0 +1,327 @@
+use yolo::{
+    tracking::{linear_assignment, KalmanBoxFilter, TrackIds, TrackState, Tracker, TrackerConfig},
+    yolox_model::BoundingBox,
+};
+
+fn bbox(x: f32, y: f32, size: f32, confidence: f32) -> BoundingBox {
+    BoundingBox {
+        xmin: x,
+        ymin: y,
+        xmax: x + size,
+        ymax: y + size,
+        confidence,
+    }
+}
+
+/// IDs of the confirmed tracks matched in the latest frame, sorted.
+fn confirmed_ids(tracker: &Tracker) -> Vec<u64> {
+    let mut ids: Vec<_> = tracker.confirmed().map(|track| track.id()).collect();
+    ids.sort_unstable();
+    ids
+}
+
+#[test]
+fn linear_assignment_square() {
+    let cost = vec![vec![4., 1., 3.], vec![2., 0., 5.], vec![3., 2., 2.]];
+
+    assert_eq!(linear_assignment(&cost), vec![(0, 1), (1, 0), (2, 2)]);
+}
+
+#[test]
+fn linear_assignment_more_columns_than_rows() {
+    let cost = vec![vec![0.9, 0.1, 0.5, 0.7], vec![0.2, 0.3, 0.8, 0.0]];
+
+    assert_eq!(linear_assignment(&cost), vec![(0, 1), (1, 3)]);
+}
+
+#[test]
+fn linear_assignment_more_rows_than_columns() {
+    let cost = vec![vec![0.9, 0.1], vec![0.2, 0.3], vec![0.0, 0.8]];
+
+    assert_eq!(linear_assignment(&cost), vec![(0, 1), (2, 0)]);
+}
+
+#[test]
+fn linear_assignment_all_nan() {
+    let cost = vec![vec![f32::NAN; 3]; 2];
+
+    assert_eq!(linear_assignment(&cost), vec![(0, 0), (1, 1)]);
+}
+
+#[test]
+fn linear_assignment_avoids_non_finite_costs() {
+    let cost = vec![
+        vec![f32::NAN, 5.0, f32::INFINITY],
+        vec![0.1, f32::NAN, 0.2],
+        vec![f32::NEG_INFINITY, f32::NAN, 0.3],
+    ];
+
+    assert_eq!(linear_assignment(&cost), vec![(0, 1), (1, 0), (2, 2)]);
+}
+
+#[test]
+fn linear_assignment_empty() {
+    assert!(linear_assignment(&[]).is_empty());
+    assert!(linear_assignment(&[vec![], vec![]]).is_empty());
+}
+
+#[test]
+fn kalman_filter_learns_constant_velocity() {
+    let mut filter = KalmanBoxFilter::new(&bbox(0., 0., 40., 1.));
+    for frame in 1..30 {
+        filter.predict();
+        filter.update(&bbox(5. * frame as f32, 2. * frame as f32, 40., 1.));
+    }
+
+    let [vx, vy] = filter.velocity();
+    assert!((vx - 5.).abs() < 0.1, "vx = {vx}");
+    assert!((vy - 2.).abs() < 0.1, "vy = {vy}");
+
+    // Extrapolates the motion without measurements
+    filter.predict();
+    let predicted = filter.bbox(1.);
+    assert!(
+        (predicted.xmin - 150.).abs() < 1.,
+        "xmin = {}",
+        predicted.xmin
+    );
+    assert!(
+        (predicted.ymin - 60.).abs() < 1.,
+        "ymin = {}",
+        predicted.ymin
+    );
+}
+
+#[test]
+fn track_is_confirmed_after_min_hits() {
+    let mut tracker = TrackerConfig::new().with_min_hits(3).init();
+
+    for frame in 0..5 {
+        tracker.update(&[bbox(10. + 2. * frame as f32, 10., 50., 0.9)]);
+
+        let expected: Vec<u64> = if frame < 2 { vec![] } else { vec![1] };
+        assert_eq!(confirmed_ids(&tracker), expected, "frame {frame}");
+    }
+    assert_eq!(tracker.tracks().len(), 1);
+    assert_eq!(tracker.tracks()[0].hits(), 5);
+}
+
+#[test]
+fn moving_objects_keep_their_ids() {
+    let mut tracker = TrackerConfig::new().with_min_hits(1).init();
+
+    for frame in 0..20 {
+        let offset = 4. * frame as f32;
+        // Listed in a different order every frame
+        let mut detections = vec![
+            bbox(0. + offset, 0., 50., 0.9),
+            bbox(200. - offset, 100., 50., 0.8),
+            bbox(400., 300. + offset, 60., 0.7),
+        ];
+        if frame % 2 == 1 {
+            detections.reverse();
+        }
+        tracker.update(&detections);
+
+        assert_eq!(confirmed_ids(&tracker), vec![1, 2, 3], "frame {frame}");
+        let first = tracker.tracks().iter().find(|t| t.id() == 1).unwrap();
+        assert!((first.bbox().xmin - offset).abs() < 2., "frame {frame}");
+    }
+}
+
+#[test]
+fn occluded_track_is_recovered_with_same_id() {
+    let mut tracker = TrackerConfig::new().with_min_hits(1).init();
+    let position = |frame: usize| bbox(10. * frame as f32, 50., 60., 0.9);
+
+    for frame in 0..10 {
+        tracker.update(&[position(frame)]);
+    }
+    // Occluded for 3 frames
+    for _ in 10..13 {
+        tracker.update(&[]);
+        assert!(confirmed_ids(&tracker).is_empty());
+        assert_eq!(tracker.tracks()[0].state(), TrackState::Lost);
+    }
+    // Reappears further along its trajectory
+    tracker.update(&[position(13)]);
+
+    assert_eq!(confirmed_ids(&tracker), vec![1]);
+    assert_eq!(tracker.tracks().len(), 1);
+}
+
+#[test]
+fn lost_track_is_removed_after_max_age() {
+    let mut tracker = TrackerConfig::new().with_min_hits(1).with_max_age(5).init();
+
+    tracker.update(&[bbox(100., 100., 50., 0.9)]);
+    for _ in 0..5 {
+        tracker.update(&[]);
+        assert_eq!(tracker.tracks().len(), 1);
+    }
+    tracker.update(&[]);
+    assert!(tracker.tracks().is_empty());
+
+    tracker.update(&[bbox(100., 100., 50., 0.9)]);
+    assert_eq!(confirmed_ids(&tracker), vec![2]);
+}
+
+#[test]
+fn unconfirmed_track_is_dropped_on_first_miss() {
+    let mut tracker = TrackerConfig::new().with_min_hits(3).init();
+
+    tracker.update(&[bbox(10., 10., 50., 0.9)]);
+    tracker.update(&[bbox(11., 10., 50., 0.9)]);
+    tracker.update(&[]);
+
+    assert!(tracker.tracks().is_empty());
+}
+
+#[test]
+fn low_confidence_detections_only_extend_confirmed_tracks() {
+    let mut tracker = TrackerConfig::new().with_min_hits(1).init();
+
+    tracker.update(&[bbox(10., 10., 50., 0.9)]);
+    // The tracked face gets blurry, and an unrelated low confidence box appears
+    tracker.update(&[bbox(12., 10., 50., 0.3), bbox(300., 300., 50., 0.3)]);
+
+    assert_eq!(confirmed_ids(&tracker), vec![1]);
+    assert_eq!(tracker.tracks().len(), 1);
+    assert_eq!(tracker.tracks()[0].bbox().confidence, 0.3);
+
+    // Detections below the low confidence threshold are ignored
+    tracker.update(&[bbox(14., 10., 50., 0.05)]);
+    assert!(confirmed_ids(&tracker).is_empty());
+}
+
+#[test]
+fn non_overlapping_detection_starts_new_track() {
+    let mut tracker = TrackerConfig::new().with_min_hits(1).init();
+
+    tracker.update(&[bbox(0., 0., 50., 0.9)]);
+    tracker.update(&[bbox(500., 500., 50., 0.9)]);
+
+    assert_eq!(confirmed_ids(&tracker), vec![2]);
+    assert_eq!(tracker.tracks()[0].state(), TrackState::Lost);
+}
+
+#[test]
+fn trackers_sharing_ids_never_reuse_them() {
+    let config = TrackerConfig::new().with_min_hits(1);
+    let ids = TrackIds::default();
+    let mut people = config.init_with_ids(&ids);
+    let mut cars = config.init_with_ids(&ids);
+
+    people.update(&[bbox(0., 0., 50., 0.9)]);
+    cars.update(&[bbox(0., 0., 50., 0.9), bbox(200., 0., 50., 0.9)]);
+    people.update(&[bbox(2., 0., 50., 0.9), bbox(400., 0., 50., 0.9)]);
+
+    assert_eq!(confirmed_ids(&people), vec![1, 4]);
+    assert_eq!(confirmed_ids(&cars), vec![2, 3]);
+}
+
+/// Unit embedding along the given axis.
+fn embedding(axis: usize) -> Vec<f32> {
+    let mut embedding = vec![0.; 8];
+    embedding[axis] = 1.;
+    embedding
+}
+
+#[test]
+fn lost_track_is_revived_by_appearance() {
+    let mut tracker = TrackerConfig::new().with_min_hits(1).init();
+
+    for frame in 0..5 {
+        let detections = [
+            bbox(10. + frame as f32, 10., 50., 0.9),
+            bbox(300., 10., 50., 0.9),
+        ];
+        tracker.update_with_embeddings(&detections, &[embedding(0), embedding(1)]);
+    }
+    for _ in 0..3 {
+        tracker.update_with_embeddings(&[bbox(300., 10., 50., 0.9)], &[embedding(1)]);
+    }
+    // The first face re-enters the frame far from where it left
+    let detections = [bbox(300., 10., 50., 0.9), bbox(100., 400., 60., 0.9)];
+    tracker.update_with_embeddings(&detections, &[embedding(1), embedding(0)]);
+
+    assert_eq!(confirmed_ids(&tracker), vec![1, 2]);
+    let revived = tracker.tracks().iter().find(|t| t.id() == 1).unwrap();
+    assert_eq!(revived.bbox(), bbox(100., 400., 60., 0.9));
+
+    // An unknown face at the same place starts a new track
+    tracker.update_with_embeddings(&[bbox(600., 400., 60., 0.9)], &[embedding(2)]);
+    assert_eq!(confirmed_ids(&tracker), vec![3]);
+}
+
+#[test]
+fn appearance_gates_overlapping_detections() {
+    let mut tracker = TrackerConfig::new().with_min_hits(1).init();
+
+    tracker.update_with_embeddings(&[bbox(10., 10., 50., 0.9)], &[embedding(0)]);
+    // Same place, different face
+    tracker.update_with_embeddings(&[bbox(12., 10., 50., 0.9)], &[embedding(1)]);
+
+    assert_eq!(confirmed_ids(&tracker), vec![2]);
+    assert_eq!(tracker.tracks()[0].state(), TrackState::Lost);
+}
+
+#[test]
+fn appearance_resolves_ambiguous_overlaps() {
+    let mut tracker = TrackerConfig::new()
+        .with_min_hits(1)
+        .with_appearance_weight(0.9)
+        .with_max_appearance_distance(2.)
+        .init();
+    let mixed = |a: usize, b: usize| {
+        let mut embedding = embedding(a);
+        embedding[b] = 0.5;
+        embedding
+    };
+
+    tracker.update_with_embeddings(
+        &[bbox(0., 0., 50., 0.9), bbox(20., 0., 50., 0.9)],
+        &[embedding(0), embedding(1)],
+    );
+    // Both boxes overlap both tracks, but the IoU alone would swap their IDs
+    let detections = [bbox(18., 0., 50., 0.9), bbox(2., 0., 50., 0.9)];
+    tracker.update_with_embeddings(&detections, &[mixed(0, 1), mixed(1, 0)]);
+
+    let first = tracker.tracks().iter().find(|t| t.id() == 1).unwrap();
+    // Filtered towards the detection on the right
+    assert!(first.bbox().xmin > 10.);
+    let embedding = first.embedding().unwrap();
+    assert!(embedding[0] > 0.99 && embedding[1] > 0.);
+    let norm: f32 = embedding.iter().map(|v| v * v).sum();
+    assert!((norm - 1.).abs() < 1e-5);
+}
+
+#[test]
+#[should_panic(expected = "Expected one embedding per detection")]
+fn embeddings_must_match_detections() {
+    let mut tracker = TrackerConfig::new().init();
+
+    tracker.update_with_embeddings(&[bbox(0., 0., 50., 0.9)], &[]);
+}
+
+#[test]
+fn predict_extrapolates_without_missing_tracks() {
+    let mut tracker = TrackerConfig::new().with_min_hits(1).with_max_age(1).init();
+    for frame in 0..10 {
+        tracker.update(&[bbox(5. * frame as f32, 0., 50., 0.9)]);
+    }
+
+    for _ in 0..5 {
+        tracker.predict();
+    }
+
+    let track = &tracker.tracks()[0];
+    assert_eq!(track.state(), TrackState::Confirmed);
+    assert_eq!(track.time_since_update(), 0);
+    assert!(
+        (track.bbox().xmin - 70.).abs() < 3.,
+        "{}",
+        track.bbox().xmin
+    );
+    assert_eq!(confirmed_ids(&tracker), vec![1]);
+}
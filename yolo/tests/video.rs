@@ -0,0 +1,161 @@
+use std::{fs, io::Cursor};
+
+use burn::backend::NdArray;
+use image::{codecs::jpeg::JpegEncoder, DynamicImage, GenericImageView, Rgb, RgbImage};
+use yolo::{
+    detector::DetectorConfig,
+    video::{FrameReader, MjpegReader, Y4mReader},
+    yolox_model::yolox::YoloxConfig,
+};
+
+type Backend = NdArray<f32>;
+
+/// Y4M stream of `frames` 4x2 4:2:0 frames, with the given luma and chroma values.
+fn y4m_stream(frames: &[(u8, u8, u8)]) -> Vec<u8> {
+    let mut stream = b"YUV4MPEG2 W4 H2 F30:1 Ip A1:1 C420jpeg XYSCSS=420JPEG\n".to_vec();
+    for (y, u, v) in frames {
+        stream.extend_from_slice(b"FRAME\n");
+        stream.extend_from_slice(&[*y; 8]);
+        stream.extend_from_slice(&[*u; 2]);
+        stream.extend_from_slice(&[*v; 2]);
+    }
+    stream
+}
+
+fn jpeg(width: u32, height: u32, color: [u8; 3]) -> Vec<u8> {
+    let image = RgbImage::from_pixel(width, height, Rgb(color));
+    let mut bytes = Vec::new();
+    JpegEncoder::new_with_quality(&mut bytes, 95)
+        .encode_image(&image)
+        .unwrap();
+    bytes
+}
+
+#[test]
+fn y4m_frames_are_converted_to_rgb() {
+    let stream = y4m_stream(&[(235, 128, 128), (16, 128, 128), (81, 90, 240)]);
+    let reader = Y4mReader::new(Cursor::new(stream)).unwrap();
+    assert_eq!(reader.size(), (4, 2));
+
+    let frames: Vec<_> = reader.map(Result::unwrap).collect();
+    assert_eq!(frames.len(), 3);
+    assert_eq!(frames[2].index, 2);
+    assert_eq!(frames[2].name, "frame_000002");
+    assert_eq!(frames[0].image.dimensions(), (4, 2));
+    assert_eq!(frames[0].image.get_pixel(3, 1).0, [255, 255, 255, 255]);
+    assert_eq!(frames[1].image.get_pixel(0, 0).0, [0, 0, 0, 255]);
+
+    // BT.601 red
+    let [r, g, b, _] = frames[2].image.get_pixel(1, 1).0;
+    assert!(r > 250 && g < 5 && b < 5, "{r} {g} {b}");
+}
+
+#[test]
+fn y4m_truncated_frame_is_an_error() {
+    let mut stream = y4m_stream(&[(235, 128, 128)]);
+    stream.truncate(stream.len() - 1);
+
+    let mut reader = Y4mReader::new(Cursor::new(stream)).unwrap();
+    assert!(reader.next().unwrap().is_err());
+}
+
+#[test]
+fn y4m_unsupported_colorspace() {
+    let stream = b"YUV4MPEG2 W4 H2 C420p10\n".to_vec();
+
+    assert!(Y4mReader::new(Cursor::new(stream)).is_err());
+}
+
+#[test]
+fn mjpeg_stream_is_split_into_images() {
+    let mut stream = jpeg(16, 8, [255, 0, 0]);
+    // Padding between images is skipped
+    stream.extend_from_slice(&[0, 0]);
+    stream.extend_from_slice(&jpeg(8, 16, [0, 0, 255]));
+
+    let frames: Vec<_> = MjpegReader::new(Cursor::new(stream))
+        .map(Result::unwrap)
+        .collect();
+
+    assert_eq!(frames.len(), 2);
+    assert_eq!(frames[0].image.dimensions(), (16, 8));
+    assert_eq!(frames[1].image.dimensions(), (8, 16));
+    assert_eq!(frames[1].name, "frame_000001");
+    let [r, _, b, _] = frames[1].image.get_pixel(4, 4).0;
+    assert!(r < 10 && b > 245);
+}
+
+#[test]
+fn mjpeg_truncated_image_is_an_error() {
+    let mut stream = jpeg(16, 8, [255, 0, 0]);
+    stream.truncate(stream.len() - 10);
+
+    let mut reader = MjpegReader::new(Cursor::new(stream));
+    assert!(reader.next().unwrap().is_err());
+}
+
+#[test]
+fn stream_format_is_detected() {
+    let y4m = FrameReader::stream(Cursor::new(y4m_stream(&[(16, 128, 128)]))).unwrap();
+    assert!(matches!(y4m, FrameReader::Y4m(_)));
+    assert_eq!(y4m.count(), 1);
+
+    let mjpeg = FrameReader::stream(Cursor::new(jpeg(8, 8, [0, 0, 0]))).unwrap();
+    assert!(matches!(mjpeg, FrameReader::Mjpeg(_)));
+    assert_eq!(mjpeg.count(), 1);
+
+    assert!(FrameReader::stream(Cursor::new(b"RIFF....".to_vec())).is_err());
+}
+
+#[test]
+fn directory_frames_are_sorted_by_name() {
+    let dir = std::env::temp_dir().join(format!("yolo-video-test-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for (name, width) in [("b.png", 2), ("a.png", 1), ("c.jpg", 3)] {
+        DynamicImage::ImageRgb8(RgbImage::new(width, 4))
+            .save(dir.join(name))
+            .unwrap();
+    }
+    fs::write(dir.join("results.jsonl"), "").unwrap();
+
+    let frames: Vec<_> = FrameReader::directory(&dir)
+        .unwrap()
+        .map(Result::unwrap)
+        .collect();
+    fs::remove_dir_all(&dir).unwrap();
+
+    let names: Vec<_> = frames.iter().map(|frame| frame.name.as_str()).collect();
+    assert_eq!(names, ["a.png", "b.png", "c.jpg"]);
+    let widths: Vec<_> = frames.iter().map(|frame| frame.image.width()).collect();
+    assert_eq!(widths, [1, 2, 3]);
+}
+
+#[test]
+fn detector_scales_boxes_to_each_image() {
+    let device = Default::default();
+    let model = YoloxConfig::new(0.33, 0.25, 2, true).init::<Backend>(&device);
+    let detector = DetectorConfig::new()
+        .with_input_height(64)
+        .with_input_width(64)
+        .with_score_threshold(0.)
+        .init(model);
+    let images = [
+        DynamicImage::ImageRgb8(RgbImage::new(64, 64)),
+        DynamicImage::ImageRgb8(RgbImage::new(128, 32)),
+    ];
+
+    let boxes = detector.detect_batch(&images);
+    assert_eq!(boxes.len(), 2);
+    assert!(boxes.iter().all(|boxes| boxes.len() == 2));
+
+    // Same content, so the boxes only differ by the scaling
+    let single = detector.detect(&images[0]);
+    assert_eq!(single, boxes[0]);
+    let (small, wide) = (&boxes[0][0], &boxes[1][0]);
+    assert!(!small.is_empty());
+    assert_eq!(small.len(), wide.len());
+    for (a, b) in small.iter().zip(wide) {
+        assert!((a.xmin * 2. - b.xmin).abs() < 1e-3);
+        assert!((a.ymax / 2. - b.ymax).abs() < 1e-3);
+    }
+}
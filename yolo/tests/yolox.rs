@@ -0,0 +1,78 @@
+use burn::{
+    backend::ndarray::NdArray,
+    module::{Module, ModuleMapper, ParamId},
+    tensor::{Distribution, Tensor},
+};
+use yolo::yolox_model::{
+    boxes::{nms, nms_async},
+    yolox::{Yolox, YoloxConfig},
+};
+
+type Backend = NdArray<f32>;
+
+/// Replaces the 1D parameters and running statistics, which the batch norms initialize to an
+/// identity, with random positive values, as after training.
+struct RandomStatistics;
+
+impl ModuleMapper<Backend> for RandomStatistics {
+    fn map_float<const D: usize>(
+        &mut self,
+        _id: &ParamId,
+        tensor: Tensor<Backend, D>,
+    ) -> Tensor<Backend, D> {
+        match D {
+            1 => Tensor::random(
+                tensor.shape(),
+                Distribution::Uniform(0.5, 1.5),
+                &tensor.device(),
+            ),
+            _ => tensor,
+        }
+    }
+}
+
+#[test]
+fn fused_yolox_matches_unfused_model() {
+    let device = Default::default();
+    let input = Tensor::<Backend, 4>::random([1, 3, 64, 64], Distribution::Default, &device);
+
+    // Nano sized models keep the test fast, the depthwise one has depthwise separable convolutions
+    for depthwise in [false, true] {
+        let model: Yolox<Backend> = YoloxConfig::new(0.33, 0.25, 80, depthwise)
+            .init(&device)
+            .map(&mut RandomStatistics);
+
+        let expected = model.forward(input.clone());
+        let output = model.fuse().forward(input.clone());
+
+        output
+            .into_data()
+            .assert_approx_eq(&expected.into_data(), 2);
+    }
+}
+
+#[test]
+fn async_nms_matches_nms() {
+    let device = Default::default();
+    // Centers and sizes of 2x50 overlapping boxes, with the scores of 3 classes
+    let boxes = Tensor::<Backend, 3>::random([2, 50, 4], Distribution::Uniform(10., 60.), &device);
+    let scores = Tensor::<Backend, 3>::random([2, 50, 3], Distribution::Default, &device);
+
+    let expected = nms(boxes.clone(), scores.clone(), 0.5, 0.3);
+    let output = block_on(nms_async(boxes, scores, 0.5, 0.3));
+
+    assert_eq!(output, expected);
+    assert!(expected.iter().flatten().any(|boxes| boxes.len() > 1));
+}
+
+/// Runs a future which is ready right away, as are those of the ndarray backend.
+fn block_on<F: std::future::Future>(future: F) -> F::Output {
+    use std::task::{Context, Poll, Waker};
+
+    let mut future = std::pin::pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+    match future.as_mut().poll(&mut context) {
+        Poll::Ready(output) => output,
+        Poll::Pending => panic!("The future is not ready"),
+    }
+}
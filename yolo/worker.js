@@ -0,0 +1,109 @@
+/**
+ *
+ * This demo is part of Burn project: https://github.com/tracel-ai/burn
+ *
+ * Released under a dual license:
+ * https://github.com/tracel-ai/burn/blob/main/LICENSE-MIT
+ * https://github.com/tracel-ai/burn/blob/main/LICENSE-APACHE
+ *
+ */
+
+/**
+ * Web Worker running the YOLOX detector off the main thread, e.g.
+ * `new Worker("worker.js", { type: "module" })`.
+ *
+ * Messages of the main thread:
+ * - `{type: "init", weights, checksum, policy, schedule, options}` - Loads the `weights`
+ *   ArrayBuffer, whose SHA-256 `checksum` is optional, and answers `{type: "ready"}`. The drop
+ *   `policy` is `latest`, `skip` or `queue`, the optional `schedule` is
+ *   `{detection_interval, motion_threshold, latency_budget_ms, tracking}`, and the optional
+ *   `options` are `{input_size, score_threshold, iou_threshold}`.
+ * - `{type: "options", input_size, score_threshold, iou_threshold}` - Changes the options between
+ *   two frames.
+ * - `{id, type: "frame", pixels, width, height}` - Detects a frame, whose RGBA `pixels` ArrayBuffer
+ *   should be transferred.
+ * - `{id, type: "cancel"}` - Cancels a frame.
+ * - `{type: "metrics"}` - Answers `{type: "metrics", metrics}`.
+ *
+ * Each frame is answered once, see `DetectorWorker`, with its pixels transferred back.
+ */
+import { default as wasm, DetectorWorker, Weights } from "./pkg/yolo.js";
+
+let worker;
+let draining = false;
+let pendingOptions;
+
+function post(response) {
+    const transfer = [response.pixels, response.output?.buffer].filter((buffer) => buffer);
+    self.postMessage(response, transfer);
+}
+
+async function drain() {
+    for (let response = await worker.process(); response !== undefined; response = await worker.process()) {
+        post(response);
+        applyPendingOptions();
+        // Let the frames received meanwhile reach the queue, so that the drop policy applies
+        await new Promise((resolve) => setTimeout(resolve, 0));
+    }
+    draining = false;
+    applyPendingOptions();
+}
+
+function applyPendingOptions() {
+    if (pendingOptions) {
+        const options = pendingOptions;
+        pendingOptions = undefined;
+        try {
+            setOptions(options);
+        } catch (error) {
+            self.postMessage({ type: "error", message: String(error) });
+        }
+    }
+}
+
+async function init({ weights, checksum, policy, schedule, options }) {
+    await wasm();
+    worker = new DetectorWorker(Weights.from_bytes(new Uint8Array(weights), checksum), policy);
+    if (schedule) {
+        const { detection_interval, motion_threshold, latency_budget_ms, tracking } = schedule;
+        worker.set_schedule(detection_interval, motion_threshold, latency_budget_ms, tracking);
+    }
+    if (options) {
+        setOptions(options);
+    }
+}
+
+function setOptions({ input_size, score_threshold, iou_threshold }) {
+    worker.set_options(input_size, score_threshold, iou_threshold);
+}
+
+self.onmessage = async (event) => {
+    try {
+        switch (event.data.type) {
+            case "init":
+                await init(event.data);
+                self.postMessage({ type: "ready" });
+                return;
+            case "options":
+                // The detector is busy while a frame is detected, so the options apply after it
+                pendingOptions = event.data;
+                if (!draining) {
+                    applyPendingOptions();
+                }
+                return;
+            case "metrics":
+                self.postMessage({ type: "metrics", metrics: worker.metrics() });
+                return;
+            default:
+                worker.receive(event.data).forEach(post);
+        }
+    } catch (error) {
+        self.postMessage({ id: event.data.id, type: "error", message: String(error) });
+        return;
+    }
+
+    if (!draining) {
+        draining = true;
+        setTimeout(drain, 0);
+    }
+};